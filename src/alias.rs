@@ -0,0 +1,145 @@
+use crate::{
+    archive::Archive,
+    product::Product,
+    remote::RemoteArchive,
+    satellite::{Role, Satellite},
+};
+use chrono::naive::NaiveDateTime;
+use std::{
+    error::Error,
+    fs::{create_dir_all, read_dir, read_link, remove_file},
+    path::{Path, PathBuf},
+};
+
+/// The directory in the archive root that mirrors the satellite directories for a role.
+fn role_dir(role: Role) -> &'static str {
+    match role {
+        Role::East => "GOES-EAST",
+        Role::West => "GOES-WEST",
+    }
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Link every directory of the product already in the archive into the `GOES-EAST` and
+    /// `GOES-WEST` alias trees, and remove alias links whose directories are gone. Returns the
+    /// number of links created.
+    pub fn link_role_aliases(&self, prod: Product) -> Result<usize, Box<dyn Error>> {
+        for root in self.roots() {
+            for role in [Role::East, Role::West] {
+                let dir = root.join(role_dir(role)).join(prod.prefix());
+                if dir.is_dir() {
+                    remove_dangling_links(&dir)?;
+                }
+            }
+        }
+
+        let roots: Vec<PathBuf> = self.roots().map(Path::to_owned).collect();
+
+        let mut num_links = 0;
+        for sat in Satellite::all() {
+            for (dir, valid_time) in self.time_dirs(sat, prod)? {
+                if Self::link_role_alias(&roots, sat, valid_time, &dir)? {
+                    num_links += 1;
+                }
+            }
+        }
+
+        Ok(num_links)
+    }
+
+    /// Link a directory into the alias tree of the role its satellite had at the valid time, if
+    /// it had one, returning whether a new link was made. Only link directories that have been
+    /// written, or the link dangles.
+    pub(crate) fn link_role_alias(
+        roots: &[PathBuf],
+        sat: Satellite,
+        valid_time: NaiveDateTime,
+        dir: &Path,
+    ) -> Result<bool, Box<dyn Error>> {
+        let role = match [Role::East, Role::West]
+            .into_iter()
+            .find(|role| role.satellite_at(valid_time).short_name() == sat.short_name())
+        {
+            Some(role) => role,
+            None => return Ok(false),
+        };
+
+        let (root, relative) = match roots.iter().find_map(|root| {
+            let relative = dir.strip_prefix(root.join(sat.short_name())).ok()?;
+            Some((root, relative))
+        }) {
+            Some(found) => found,
+            None => return Ok(false),
+        };
+
+        let alias = root.join(role_dir(role)).join(relative);
+        if alias.symlink_metadata().is_ok() {
+            return Ok(false);
+        }
+
+        // Relative links keep working if the archive is moved or mounted elsewhere.
+        let depth = relative.components().count();
+        let mut target: PathBuf = std::iter::repeat_n("..", depth).collect();
+        target.push(sat.short_name());
+        target.push(relative);
+
+        if let Some(parent) = alias.parent() {
+            create_dir_all(parent)?;
+        }
+        link_dir(&target, dir, &alias)?;
+        log::debug!("Linked {:?} to {:?}", alias, target);
+
+        Ok(true)
+    }
+}
+
+/// Link `link` to `dir`, by the relative path `target` where the platform allows it.
+#[cfg(unix)]
+fn link_dir(target: &Path, _dir: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// Link `link` to `dir` with a junction, which unlike a symlink needs no special privileges, but
+/// only takes an absolute target.
+#[cfg(windows)]
+fn link_dir(_target: &Path, dir: &Path, link: &Path) -> std::io::Result<()> {
+    junction::create(std::path::absolute(dir)?, link)
+}
+
+/// Junctions and directory symlinks are removed like directories on Windows.
+#[cfg(unix)]
+fn remove_link(link: &Path) -> std::io::Result<()> {
+    remove_file(link)
+}
+
+#[cfg(windows)]
+fn remove_link(link: &Path) -> std::io::Result<()> {
+    std::fs::remove_dir(link)
+}
+
+/// Remove the links below `dir` that point at directories that no longer exist.
+fn remove_dangling_links(dir: &Path) -> Result<(), Box<dyn Error>> {
+    for entry in read_dir(dir)? {
+        let entry = entry?;
+        let pth = entry.path();
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() {
+            if !pth.exists() {
+                log::debug!(
+                    "Removing dangling alias {:?} -> {:?}",
+                    pth,
+                    read_link(&pth)?
+                );
+                remove_link(&pth)?;
+            }
+        } else if file_type.is_dir() {
+            remove_dangling_links(&pth)?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,296 +1,1418 @@
 use std::{
+    collections::HashSet,
     error::Error,
-    fs::{create_dir_all, read_dir, File},
+    fs::{create_dir_all, read_dir, rename, File, OpenOptions, TryLockError},
     io::Write,
     path::{Path, PathBuf},
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     thread::{self, JoinHandle},
 };
 
-use crate::{error::GoesArchError, product::Product, remote::RemoteArchive, satellite::Satellite};
-use chrono::{naive::NaiveDateTime, Datelike, Duration, Timelike};
-use crossbeam_channel::{bounded, Receiver, Sender};
+#[cfg(feature = "sqlite")]
+use crate::index::{ArchiveIndex, IndexedFile, INDEX_FNAME};
+use crate::{
+    bundle::{bundled_paths, extracted},
+    compression::{
+        decompress_to, decompressed_path, find_stored, is_data_extension, original_name,
+        Compression,
+    },
+    error::GoesArchError,
+    filename::{satellite_and_product, scan_start, scanned_within, split_superseded},
+    filter::FileFilter,
+    hooks::Hooks,
+    listing::{ListedFile, ListingSnapshot, LISTING_FNAME},
+    marker::{write_sidecar, CompletionMarker, MarkerBasis},
+    outage::DataAvailability,
+    product::Product,
+    product_set::ProductSet,
+    remote::{static_file_path, RemoteArchive},
+    report::{RetrievalFailure, RetrievalReport},
+    retry::RetryPolicy,
+    satellite::{Role, Satellite},
+};
+use chrono::{naive::NaiveDateTime, Datelike, Duration, NaiveTime, Timelike};
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
 
+#[derive(Clone)]
 pub struct Archive<T: RemoteArchive> {
     root: PathBuf,
     remote: T,
+    tiers: Vec<(PathBuf, Duration)>,
+    compression: Compression,
+    quota: Option<u64>,
+    retry_policy: RetryPolicy,
+    num_downloaders: usize,
+    all_versions: bool,
+    checksum_files: bool,
+    role_aliases: bool,
+    listing_snapshots: bool,
+    compaction: bool,
+    decompressed_dir: Option<PathBuf>,
+    hooks: Hooks,
+    #[cfg(feature = "sqlite")]
+    index: Option<ArchiveIndex>,
 }
 
-static COMPLETED_DOWNLOADS: AtomicUsize = AtomicUsize::new(0);
+pub(crate) static COMPLETED_DOWNLOADS: AtomicUsize = AtomicUsize::new(0);
 
 impl<RA: 'static> Archive<RA>
 where
     RA: RemoteArchive + Clone + Send,
 {
+    /// Connect to the archive at `root_path`. Nothing is created or checked until the first
+    /// retrieval, see `check_format`.
     pub fn connect<P>(root_path: P, remote: RA) -> Self
     where
         P: Into<PathBuf>,
     {
         let root = root_path.into();
-        log::info!("Connected to archive at: {:?}", &root);
-        Self { root, remote }
+        let archive = Self {
+            root,
+            remote,
+            tiers: vec![],
+            compression: Compression::default(),
+            quota: None,
+            retry_policy: RetryPolicy::default(),
+            num_downloaders: NUM_DOWNLOADERS,
+            all_versions: false,
+            checksum_files: false,
+            role_aliases: false,
+            listing_snapshots: false,
+            compaction: false,
+            decompressed_dir: None,
+            hooks: Hooks::default(),
+            #[cfg(feature = "sqlite")]
+            index: None,
+        };
+
+        log::info!("Connected to archive at: {:?}", archive.root());
+
+        archive
+    }
+
+    /// Add a tier for data older than `older_than`, e.g. a large slow disk for everything past a
+    /// month while recent data stays on a fast disk at the main root. New downloads are placed in
+    /// the tier for their age, and lookups search every tier. Move data that has aged into
+    /// another tier with `rebalance_tiers`.
+    pub fn with_tier<P: Into<PathBuf>>(mut self, root: P, older_than: Duration) -> Self {
+        self.tiers.push((root.into(), older_than));
+        self.tiers.sort_by_key(|(_, older_than)| *older_than);
+        self
+    }
+
+    /// How to compress newly downloaded files, zip by default.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Limit the archive to `max_bytes` on disk. Before each retrieval the directories written
+    /// longest ago are evicted until the archive fits, see `enforce_quota`. The files a
+    /// retrieval downloads aren't known in advance, so it can leave the archive over the limit
+    /// until the next retrieval.
+    pub fn with_quota(mut self, max_bytes: u64) -> Self {
+        self.quota = Some(max_bytes);
+        self
+    }
+
+    /// Try each failed download again up to `retries` times, waiting `initial_backoff` before the
+    /// first retry and twice as long before each one after, plus some random jitter. Errors that
+    /// won't go away, like a missing file or a full disk, aren't retried. A directory is only
+    /// marked complete once every file in it has landed. The default is 3 retries starting at 1
+    /// second.
+    pub fn with_retries(mut self, retries: u32, initial_backoff: Duration) -> Self {
+        self.retry_policy = RetryPolicy::new(retries, initial_backoff.to_std().unwrap_or_default());
+        self
+    }
+
+    /// How many files to download at once, 3 by default. Raise it on a fast connection or lower
+    /// it on a slow one. It's never more than the remote's `max_downloads`.
+    pub fn with_num_downloaders(mut self, num_downloaders: usize) -> Self {
+        self.num_downloaders = num_downloaders;
+        self
+    }
+
+    /// Download and return every version of a scan the remote has, instead of only the one created
+    /// last. Now and then NOAA reprocesses a scan and both versions stay on the remote.
+    pub fn with_all_versions(mut self) -> Self {
+        self.all_versions = true;
+        self
+    }
+
+    /// Write a `.sha256` checksum file next to each downloaded file, and record checksums in the
+    /// completion markers, so bit rot can be caught long after the download. `open_archived_file`
+    /// and `verify_local` check files against them.
+    pub fn with_checksum_files(mut self) -> Self {
+        self.checksum_files = true;
+        self
     }
 
+    /// Keep `GOES-EAST` and `GOES-WEST` trees in the archive root linking to the directories of
+    /// whichever satellite held the role at each time, so tools that think in roles have a
+    /// stable path across satellite handoffs. Links are made as directories are retrieved, use
+    /// `link_role_aliases` for data already in the archive.
+    pub fn with_role_aliases(mut self) -> Self {
+        self.role_aliases = true;
+        self
+    }
+
+    /// Save the remote listing of each directory as it's downloaded, with sizes and ETags when the
+    /// remote's listings have them, see `listing_snapshot`.
+    pub fn with_listing_snapshots(mut self) -> Self {
+        self.listing_snapshots = true;
+        self
+    }
+
+    /// Compact each directory into a bundle as soon as it's downloaded and marked complete, see
+    /// `compact`. File saved hooks still see the files before they're packed.
+    pub fn with_compaction(mut self) -> Self {
+        self.compaction = true;
+        self
+    }
+
+    /// Have retrievals return decompressed `.nc` copies of compressed files, written under `dir`
+    /// in the same layout as the archive, for tools that read NetCDF files directly. The archive
+    /// itself stays compressed, and the copies are left for the caller to clean up.
+    pub fn with_decompressed_copies<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.decompressed_dir = Some(dir.into());
+        self
+    }
+
+    /// Call `hook` with the path of each file as soon as it's saved to the archive, e.g. to ingest
+    /// it downstream without polling the file system. Hooks are called from the download
+    /// threads.
+    pub fn on_file_saved<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path) + Send + Sync + 'static,
+    {
+        self.hooks.file_saved = Some(Arc::new(hook));
+        self
+    }
+
+    /// Call `hook` with the directory of each satellite, product, and valid time when it's
+    /// marked complete after downloading.
+    pub fn on_hour_complete<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(Satellite, Product, NaiveDateTime, &Path) + Send + Sync + 'static,
+    {
+        self.hooks.hour_complete = Some(Arc::new(hook));
+        self
+    }
+
+    /// Call `hook` with the remote file name and the error when a download fails.
+    pub fn on_download_error<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &dyn Error) + Send + Sync + 'static,
+    {
+        self.hooks.download_error = Some(Arc::new(hook));
+        self
+    }
+
+    /// Keep an index of every archived file in a SQLite database in the archive root. Complete
+    /// directories are then answered from the index instead of reading them, as long as it has
+    /// the files their completion markers list, and the files can be queried with
+    /// `indexed_files`. Files archived before the index existed are added with
+    /// `rebuild_index`.
+    #[cfg(feature = "sqlite")]
+    pub fn with_index(mut self) -> Result<Self, Box<dyn Error>> {
+        self.index = Some(ArchiveIndex::open(&self.root.join(INDEX_FNAME))?);
+        Ok(self)
+    }
+
+    /// Add every file of the product already on disk to the index, returning how many were
+    /// indexed.
+    #[cfg(feature = "sqlite")]
+    pub fn rebuild_index(&self, sat: Satellite, prod: Product) -> Result<usize, Box<dyn Error>> {
+        let index = self
+            .index
+            .as_ref()
+            .ok_or_else(|| GoesArchError::new("Archive has no index."))?;
+
+        let mut num_files = 0;
+        let mut dirs = vec![self.root.join(sat.short_name()).join(prod.prefix())];
+        while let Some(dir) = dirs.pop() {
+            if !dir.is_dir() {
+                continue;
+            }
+
+            for entry in read_dir(&dir)? {
+                let pth = entry?.path();
+                if pth.is_dir() {
+                    dirs.push(pth);
+                } else if is_data_file(&pth) {
+                    index.record(&pth, sat, prod)?;
+                    num_files += 1;
+                }
+            }
+        }
+
+        Ok(num_files)
+    }
+
+    /// The indexed files of the product with a scan start from `start` through `end`, without
+    /// touching the remote or the archive directories.
+    #[cfg(feature = "sqlite")]
+    pub fn indexed_files(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<IndexedFile>, Box<dyn Error>> {
+        match self.index {
+            Some(ref index) => index.files(sat, prod, start, end),
+            None => Err(Box::new(GoesArchError::new("Archive has no index."))),
+        }
+    }
+
+    /// Retrieve the files of the product whose scans started from `start` through `end`. Whole
+    /// directories are downloaded, usually an hour each, and then trimmed to the requested
+    /// window. To skip downloading the rest of the directories too, filter with
+    /// `FileFilter::scan_times`. The paths are of the files as stored, e.g. `.nc.zip` with the
+    /// default compression, so open them with `open_archived_file`, or have decompressed copies
+    /// returned with `with_decompressed_copies`.
     pub fn retrieve_paths(
         &self,
         sat: Satellite,
         prod: Product,
         start: NaiveDateTime,
         end: NaiveDateTime,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        self.retrieve_paths_filtered(sat, prod, start, end, &FileFilter::default())
+    }
+
+    /// Same as `retrieve_paths`, but only download and return files that pass the filter.
+    pub fn retrieve_paths_filtered(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        filter: &FileFilter,
     ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
         let (start, end) = Self::validate_dates(sat, prod, start, end)?;
 
+        let hours = Self::time_steps(sat.directory_interval(), start, end).map(|t| (sat, prod, t));
+
+        let mut paths = self.retrieve(hours, filter)?;
+        paths.retain(|pth| scanned_within(pth, start, end));
+
+        Ok(paths)
+    }
+
+    /// Same as `retrieve_paths_filtered`, but returns right away with an iterator that yields
+    /// each file as soon as it's on disk, so processing can overlap with downloading. Files come
+    /// out in no particular order, and superseded versions of a scan aren't weeded out since
+    /// they may arrive before the newer version. Errors are logged. Dropping the iterator early
+    /// doesn't stop the retrieval, it carries on in the background.
+    pub fn retrieve_paths_iter(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<impl Iterator<Item = PathBuf>, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let hours = Self::time_steps(sat.directory_interval(), start, end).map(|t| (sat, prod, t));
+
+        let (paths, _, feeder) = self.start_retrieval(hours, filter)?;
+
+        // Report errors from the feeder once the retrieval winds down.
+        thread::Builder::new()
+            .name("Feeder Watcher".to_owned())
+            .spawn(move || {
+                if let Ok(Err(err)) = feeder.join() {
+                    log::error!("Error retrieving {:?} {:?} : {}", sat, prod, err);
+                }
+            })?;
+
+        Ok(paths
+            .into_iter()
+            .map(|(_, _, pth)| pth)
+            .filter(move |pth| scanned_within(pth, start, end)))
+    }
+
+    /// The files of the product from `start` through `end` that are already in the archive. This
+    /// never touches the remote or spawns threads, so it's cheap to call repeatedly over an
+    /// archive kept up to date by something else. Files in compacted directories are extracted
+    /// next to their bundles.
+    pub fn local_paths(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+        let filter = FileFilter::default();
+
+        let mut paths = vec![];
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let dir = self.build_path(sat, prod, valid_time);
+            if !dir.is_dir() {
+                continue;
+            }
+
+            #[cfg(feature = "sqlite")]
+            if let Some(indexed) = self
+                .index
+                .as_ref()
+                .and_then(|index| Self::indexed_dir_files(index, &dir))
+            {
+                paths.extend(indexed);
+                continue;
+            }
+
+            paths.extend(Self::dir_files(&dir, &filter));
+        }
+        paths.retain(|pth| scanned_within(pth, start, end));
+
+        split_superseded(paths)
+            .0
+            .iter()
+            .map(|pth| extracted(pth))
+            .collect()
+    }
+
+    /// Retrieve all the products in the set through a single download pipeline. Products with
+    /// no data available in the requested range are skipped.
+    pub fn retrieve_paths_for_set(
+        &self,
+        sat: Satellite,
+        set: &ProductSet,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let pairs = set.products().iter().map(|&prod| (sat, prod));
+        let hours = Self::product_hours(pairs, start, end)?;
+
+        let mut paths = self.retrieve(hours, &FileFilter::default())?;
+        paths.retain(|pth| scanned_within(pth, start, end));
+
+        Ok(paths)
+    }
+
+    /// Same as `retrieve_paths_for_set`, but the files come back grouped by product, in the order
+    /// the products were given. Products with no data available in the requested range are
+    /// skipped and come back empty.
+    pub fn retrieve_paths_multi(
+        &self,
+        sat: Satellite,
+        prods: &[Product],
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<ProductPaths>, Box<dyn Error>> {
+        let hours = Self::product_hours(prods.iter().map(|&prod| (sat, prod)), start, end)?;
+
+        let mut grouped: Vec<ProductPaths> = prods.iter().map(|&prod| (prod, vec![])).collect();
+        let (retrieved, _) = self.retrieve_tagged(hours, &FileFilter::default())?;
+        for (_, pth_prod, pth) in retrieved {
+            if !scanned_within(&pth, start, end) {
+                continue;
+            }
+
+            let group = grouped
+                .iter_mut()
+                .find(|(prod, _)| prod.prefix() == pth_prod.prefix());
+
+            if let Some((_, paths)) = group {
+                paths.push(pth);
+            }
+        }
+
+        Ok(grouped)
+    }
+
+    /// Retrieve the product from several satellites, e.g. both GOES-East and GOES-West, through a
+    /// single download pipeline. The files come back grouped by satellite, in the order the
+    /// satellites were given. Satellites with no data available in the requested range are
+    /// skipped and come back empty.
+    pub fn retrieve_paths_multi_sat(
+        &self,
+        sats: &[Satellite],
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<SatellitePaths>, Box<dyn Error>> {
+        let hours = Self::product_hours(sats.iter().map(|&sat| (sat, prod)), start, end)?;
+
+        let mut grouped: Vec<SatellitePaths> = sats.iter().map(|&sat| (sat, vec![])).collect();
+        let (retrieved, _) = self.retrieve_tagged(hours, &FileFilter::default())?;
+        for (pth_sat, _, pth) in retrieved {
+            if !scanned_within(&pth, start, end) {
+                continue;
+            }
+
+            let group = grouped
+                .iter_mut()
+                .find(|(sat, _)| sat.short_name() == pth_sat.short_name());
+
+            if let Some((_, paths)) = group {
+                paths.push(pth);
+            }
+        }
+
+        Ok(grouped)
+    }
+
+    /// Retrieve the product from whichever satellite was operating in the role at each hour,
+    /// switching satellites at the handoff.
+    pub fn retrieve_paths_for_role(
+        &self,
+        role: Role,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut hours = vec![];
+        for hour in Self::time_steps(Duration::hours(1), start, end) {
+            let sat = role.satellite_at(hour);
+
+            if prod.available_at(sat, hour) {
+                hours.push((sat, prod, hour));
+            }
+        }
+
+        if hours.is_empty() {
+            log::error!(
+                "No {:?} {:?} data between {} and {}",
+                role,
+                prod,
+                start,
+                end
+            );
+            return Err(Box::new(GoesArchError::new("Invalid satellite dates.")));
+        }
+
+        let mut paths = self.retrieve(hours, &FileFilter::default())?;
+        paths.retain(|pth| scanned_within(pth, start, end));
+
+        Ok(paths)
+    }
+
+    /// Retrieve an auxiliary file, such as a navigation file, that isn't tied to a valid time.
+    /// These are stored uncompressed under the `static` directory of the archive.
+    pub fn retrieve_static_path(
+        &self,
+        sat: Satellite,
+        remote_path: &str,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        let mut pth = self.root.join(STATIC_DIR);
+        pth.push(sat.short_name());
+        pth.push(static_file_path(remote_path)?);
+
+        if pth.exists() {
+            log::debug!("Static file already in archive: {:?}", pth);
+            return Ok(pth);
+        }
+
+        let data = self.remote.retrieve_static_file(sat, remote_path)?;
+
+        if let Some(parent) = pth.parent() {
+            create_dir_all(parent)?;
+        }
+
+        write_atomic(&pth, |mut f| {
+            f.write_all(&data)?;
+            Ok((f, ()))
+        })?;
+        log::debug!("Saved {:?}", pth);
+
+        Ok(pth)
+    }
+}
+
+// Private methods and associated functions.
+
+pub(crate) const HOUR_COMPLETE_FNAME: &str = "hour_complete.txt";
+pub(crate) const HOUR_LOCK_FNAME: &str = "hour.lock";
+pub(crate) const STATIC_DIR: &str = "static";
+pub(crate) const NUM_DOWNLOADERS: usize = 3;
+
+/// Take an exclusive advisory lock on a directory for downloading into it, waiting if another
+/// process or thread holds it, so processes sharing an archive don't download the same files or
+/// race on the completion marker. The lock is released when the returned file is dropped or the
+/// process exits.
+pub(crate) fn lock_dir(dir: &Path) -> Result<File, Box<dyn Error>> {
+    let f = open_lock(dir)?;
+
+    if f.try_lock().is_err() {
+        log::info!("Waiting for another download of {:?}", dir);
+        f.lock()?;
+    }
+
+    Ok(f)
+}
+
+/// Take the lock on a directory like `lock_dir` without waiting, returning `None` if another
+/// process or thread holds it.
+pub(crate) fn try_lock_dir(dir: &Path) -> Result<Option<File>, Box<dyn Error>> {
+    let f = open_lock(dir)?;
+
+    match f.try_lock() {
+        Ok(()) => Ok(Some(f)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(err)) => Err(err.into()),
+    }
+}
+
+fn open_lock(dir: &Path) -> Result<File, Box<dyn Error>> {
+    Ok(OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(HOUR_LOCK_FNAME))?)
+}
+
+/// The temporary file a file is written to before it's renamed into place.
+pub(crate) fn partial_path(pth: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.{}", pth.to_string_lossy(), PARTIAL_EXT))
+}
+
+pub(crate) const PARTIAL_EXT: &str = "part";
+
+/// Write a file under a temporary name and rename it into place once it's synced to disk, so a
+/// crash never leaves a truncated file under the real name. The writer gets the open temporary
+/// file and hands it back when it's done.
+pub(crate) fn write_atomic<T>(
+    pth: &Path,
+    write: impl FnOnce(File) -> Result<(File, T), Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    let partial = partial_path(pth);
+
+    let res = File::create(&partial)
+        .map_err(Box::<dyn Error>::from)
+        .and_then(write)
+        .and_then(|(f, val)| {
+            f.sync_all()?;
+            rename(&partial, pth)?;
+            Ok(val)
+        });
+
+    if res.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+
+    res
+}
+
+/// Whether the path is a data file, as opposed to a completion marker or other bookkeeping file.
+/// Files named for a product need one of its extensions, other names are taken as NetCDF.
+pub(crate) fn is_data_file(pth: &Path) -> bool {
+    let fname = match pth.file_name() {
+        Some(fname) => fname.to_string_lossy(),
+        None => return false,
+    };
+
+    match satellite_and_product(&fname) {
+        Some((_, prod)) => prod.has_data_extension(&fname),
+        None => pth
+            .extension()
+            .is_some_and(|ext| is_data_extension(&ext.to_string_lossy())),
+    }
+}
+
+/// A directory to retrieve, by satellite, product, and valid time.
+type Hour = (Satellite, Product, NaiveDateTime);
+
+/// A retrieved file with the satellite and product it's from.
+pub(crate) type Retrieved = (Satellite, Product, PathBuf);
+
+/// The files retrieved for a product.
+type ProductPaths = (Product, Vec<PathBuf>);
+
+/// The files retrieved from a satellite.
+type SatellitePaths = (Satellite, Vec<PathBuf>);
+
+/// The directories of a single day that need downloading, with their valid times.
+pub(crate) type DayOfDirs = (Satellite, Product, Vec<(PathBuf, NaiveDateTime)>);
+
+/// A directory that needs downloading along with the remote files that belong in it.
+pub(crate) type ListedDir = (PathBuf, Satellite, Product, NaiveDateTime, Vec<ListedFile>);
+
+/// A retrieval in progress, the files and failures as they come out and the thread feeding in
+/// the hours.
+type Retrieval = (
+    Receiver<Retrieved>,
+    Receiver<RetrievalFailure>,
+    JoinHandle<Result<(), String>>,
+);
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    pub(crate) fn retrieve<I>(
+        &self,
+        hours: I,
+        filter: &FileFilter,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>>
+    where
+        I: IntoIterator<Item = (Satellite, Product, NaiveDateTime)>,
+    {
+        Ok(self.retrieve_report(hours, filter)?.paths)
+    }
+
+    pub(crate) fn retrieve_report<I>(
+        &self,
+        hours: I,
+        filter: &FileFilter,
+    ) -> Result<RetrievalReport, Box<dyn Error>>
+    where
+        I: IntoIterator<Item = (Satellite, Product, NaiveDateTime)>,
+    {
+        let (retrieved, failures) = self.retrieve_tagged(hours, filter)?;
+
+        Ok(RetrievalReport {
+            paths: retrieved.into_iter().map(|(_, _, pth)| pth).collect(),
+            failures,
+        })
+    }
+
+    /// Run a retrieval to the end, returning each file with the satellite and product it's from,
+    /// along with the failures.
+    fn retrieve_tagged<I>(
+        &self,
+        hours: I,
+        filter: &FileFilter,
+    ) -> Result<(Vec<Retrieved>, Vec<RetrievalFailure>), Box<dyn Error>>
+    where
+        I: IntoIterator<Item = (Satellite, Product, NaiveDateTime)>,
+    {
+        let (retrieved, failures, feeder) = self.start_retrieval(hours, filter)?;
+        let mut retrieved: Vec<Retrieved> = retrieved.into_iter().collect();
+        feeder
+            .join()
+            .unwrap()
+            .map_err(|err| GoesArchError::new(&err))?;
+
+        if !self.all_versions {
+            let (kept, superseded) =
+                split_superseded(retrieved.iter().map(|(_, _, pth)| pth).collect());
+            if !superseded.is_empty() {
+                log::debug!("Ignoring superseded files: {:?}", superseded);
+            }
+
+            let kept: HashSet<PathBuf> = kept.into_iter().cloned().collect();
+            retrieved.retain(|(_, _, pth)| kept.contains(pth));
+        }
+
+        // Every thread that reports failures is done once the paths stop coming.
+        Ok((retrieved, failures.try_iter().collect()))
+    }
+
+    /// Start the retrieval pipeline, returning the channel each file comes out of as soon as
+    /// it's on disk, the channel failures come out of, and the thread feeding the hours in,
+    /// which finishes with any error from checking the archive directories.
+    pub(crate) fn start_retrieval<I>(
+        &self,
+        hours: I,
+        filter: &FileFilter,
+    ) -> Result<Retrieval, Box<dyn Error>>
+    where
+        I: IntoIterator<Item = (Satellite, Product, NaiveDateTime)>,
+    {
+        self.prepare_retrieval()?;
+
+        let hours: Vec<_> = hours.into_iter().collect();
+
         let (to_path_accumulator, paths_to_accumulate) = bounded(100);
+        let (to_lister, needs_listed) = bounded(10);
         let (to_downloader, needs_downloaded) = bounded(100);
-        let (to_saver, from_downloader) = bounded(10);
+        let (to_caller, results) = bounded(100);
+        let (to_failures, failures) = unbounded();
 
-        let accum_thrd = Self::start_accumulator_thread(paths_to_accumulate)?;
+        self.start_accumulator_thread(
+            paths_to_accumulate,
+            to_caller,
+            to_failures.clone(),
+            filter.clone(),
+        )?;
         self.start_download_thread(
-            sat,
-            prod,
             needs_downloaded,
-            to_saver,
             to_path_accumulator.clone(),
+            to_failures.clone(),
+            filter,
         )?;
-        let save_thrd = Self::start_save_thread(from_downloader, to_path_accumulator.clone())?;
+        self.start_listing_thread(needs_listed, to_downloader, to_failures, filter)?;
 
-        for curr_time in (0..)
-            .map(|i| end - Duration::hours(i))
-            .take_while(|time| *time >= start)
-        {
-            let dir = self.build_path(sat, prod, curr_time);
+        let archive = self.clone();
+        let filter = filter.clone();
+        let feeder = thread::Builder::new()
+            .name("Feeder Thread".to_owned())
+            .spawn(move || {
+                archive
+                    .feed_hours(hours, &filter, to_lister, to_path_accumulator)
+                    .map_err(|err| err.to_string())
+            })?;
+
+        Ok((results, failures, feeder))
+    }
+
+    /// Send complete directories straight to the accumulator and the rest to be listed.
+    pub(crate) fn feed_hours(
+        &self,
+        hours: Vec<(Satellite, Product, NaiveDateTime)>,
+        filter: &FileFilter,
+        to_lister: Sender<DayOfDirs>,
+        to_path_accumulator: Sender<Retrieved>,
+    ) -> Result<(), Box<dyn Error>> {
+        // Directories that need downloading are grouped by day so they can be listed together.
+        let mut day: Option<DayOfDirs> = None;
+        for (sat, prod, curr_time) in hours {
+            if !filter.accepts_time(sat, prod, curr_time) {
+                continue;
+            }
+
+            let (dir, complete) = self.check_hour(sat, prod, curr_time, filter)?;
+            if complete {
+                to_path_accumulator.send((sat, prod, dir))?;
+                continue;
+            }
+
+            match day {
+                Some((day_sat, day_prod, ref mut dirs))
+                    if day_sat.bucket_name() == sat.bucket_name()
+                        && day_prod.prefix() == prod.prefix()
+                        && dirs[0].1.date() == curr_time.date() =>
+                {
+                    dirs.push((dir, curr_time));
+                }
+                _ => {
+                    if let Some(full_day) = day.replace((sat, prod, vec![(dir, curr_time)])) {
+                        to_lister.send(full_day)?;
+                    }
+                }
+            }
+        }
+
+        if let Some(last_day) = day {
+            to_lister.send(last_day)?;
+        }
+
+        Ok(())
+    }
+
+    /// The directory of an hour and whether it's already complete. Complete directories get their
+    /// role alias linked, those still to be downloaded are linked once they're written.
+    pub(crate) fn check_hour(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        curr_time: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<(PathBuf, bool), Box<dyn Error>> {
+        let dir = self.build_path(sat, prod, curr_time);
+
+        let complete =
+            Self::path_is_complete(&dir, sat, prod, curr_time, filter, self.checksum_files)?;
+
+        if complete && self.role_aliases {
+            let roots: Vec<PathBuf> = self.roots().map(Path::to_owned).collect();
+            if let Err(err) = Self::link_role_alias(&roots, sat, curr_time, &dir) {
+                log::error!("Error linking role alias for {:?} : {}", dir, err);
+            }
+        }
+
+        Ok((dir, complete))
+    }
+
+    /// The checks run before every retrieval, making room under the quota if there is one.
+    pub(crate) fn prepare_retrieval(&self) -> Result<(), Box<dyn Error>> {
+        self.check_format()?;
+
+        if let Some(max_bytes) = self.quota {
+            self.enforce_quota(max_bytes)?;
+        }
+
+        Ok(())
+    }
 
-            if Self::path_is_complete(&dir, prod)? {
-                to_path_accumulator.send(dir)?;
-            } else {
-                to_downloader.send((dir, curr_time))?;
+    /// The directories of every satellite and product from `start` through `end`, skipping
+    /// those with no data available in the range.
+    fn product_hours(
+        pairs: impl IntoIterator<Item = (Satellite, Product)>,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<Hour>, Box<dyn Error>> {
+        let mut hours = vec![];
+        for (sat, prod) in pairs {
+            match Self::validate_dates(sat, prod, start, end) {
+                Ok((start, end)) => {
+                    let step = sat.directory_interval();
+                    hours.extend(Self::time_steps(step, start, end).map(|t| (sat, prod, t)));
+                }
+                Err(err) => log::warn!("Skipping {:?} {:?}: {}", sat, prod, err),
             }
         }
 
-        drop(to_downloader);
-        drop(to_path_accumulator);
-        save_thrd.join().unwrap();
-        let to_ret = accum_thrd.join().unwrap();
+        if hours.is_empty() {
+            return Err(Box::new(GoesArchError::new("Invalid satellite dates.")));
+        }
 
-        Ok(to_ret)
+        Ok(hours)
     }
-}
 
-// Private methods and associated functions.
+    /// The start of every directory interval from `end` back to `start`, most recent first.
+    pub(crate) fn time_steps(
+        step: Duration,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> impl Iterator<Item = NaiveDateTime> {
+        let start = Self::floor_time(start, step);
+        let end = Self::floor_time(end, step);
 
-const HOUR_COMPLETE_FNAME: &str = "hour_complete.txt";
+        (0..)
+            .map(move |i| end - step * i)
+            .take_while(move |time| *time >= start)
+    }
 
-impl<RA: 'static> Archive<RA>
-where
-    RA: RemoteArchive + Clone + Send,
-{
-    fn start_save_thread(
-        file_paths: Receiver<(PathBuf, Vec<u8>)>,
-        to_accumulator: Sender<PathBuf>,
+    /// Round down to the start of the directory interval. The interval must evenly divide a day.
+    pub(crate) fn floor_time(time: NaiveDateTime, step: Duration) -> NaiveDateTime {
+        let step = step.num_seconds();
+        let secs = time.num_seconds_from_midnight() as i64;
+
+        time.date().and_time(NaiveTime::MIN) + Duration::seconds(secs - secs % step)
+    }
+
+    /// The number of files expected in a complete directory.
+    pub(crate) fn expected_files(sat: Satellite, prod: Product, valid_time: NaiveDateTime) -> i32 {
+        let per_hour = prod.expected_files_per_hour(sat, valid_time);
+
+        (per_hour as i64 * sat.directory_interval().num_minutes() / 60) as i32
+    }
+
+    /// List the remote files for each day of directories, with a single request when the remote
+    /// lists whole days and more than one directory of the day is needed, otherwise a request per
+    /// directory. Listings run as many at a time as downloads.
+    fn start_listing_thread(
+        &self,
+        days: Receiver<DayOfDirs>,
+        to_downloader: Sender<ListedDir>,
+        to_failures: Sender<RetrievalFailure>,
+        filter: &FileFilter,
     ) -> Result<JoinHandle<()>, Box<dyn Error>> {
+        let remote = self.remote.clone();
+        let filter = filter.clone();
+        let pool =
+            threadpool::ThreadPool::with_name("Listing Thread".to_owned(), self.num_downloaders());
+
         let jh = thread::Builder::new()
-            .name("Save Thread".into())
+            .name("Listing Thread".into())
             .spawn(move || {
-                for (pth, data) in file_paths {
-                    // If this is a marker file, don't bother compressing it as a zip.
-                    if let Some(true) = pth
-                        .file_name()
-                        .map(|p| p.to_string_lossy())
-                        .map(|p| p == HOUR_COMPLETE_FNAME)
-                    {
-                        let mut f = match File::create(&pth) {
-                            Ok(f) => f,
-                            Err(err) => {
-                                log::error!("Error creating file: {:?} : {}", pth, err);
-                                continue;
-                            }
-                        };
-
-                        match f.write_all(&data) {
-                            Ok(()) => {}
-                            Err(err) => {
-                                log::error!("Error writing data to disk: {:?} : {}", pth, err);
-                            }
-                        };
-                    } else {
-                        let fname = pth.to_string_lossy().to_string();
-                        let zfname = fname.clone() + ".zip";
-                        let zpath: PathBuf = zfname.into();
-
-                        let f = match File::create(&zpath) {
-                            Ok(f) => f,
-                            Err(err) => {
-                                log::error!("Error creating file: {:?} : {}", pth, err);
-                                continue;
-                            }
-                        };
+                for (sat, prod, dirs) in days {
+                    if dirs.len() > 1 && remote.lists_whole_days() {
+                        let remote = remote.clone();
+                        let to_downloader = to_downloader.clone();
+                        let to_failures = to_failures.clone();
+                        let filter = filter.clone();
 
-                        let mut zipf = zip::ZipWriter::new(f);
-
-                        match zipf.start_file(fname, zip::write::FileOptions::default()) {
-                            Ok(()) => {}
-                            Err(err) => log::error!("Error starting zip file: {:?}: {}", pth, err),
-                        }
+                        pool.execute(move || {
+                            Self::list_day(
+                                &remote,
+                                sat,
+                                prod,
+                                dirs,
+                                &filter,
+                                &to_downloader,
+                                &to_failures,
+                            )
+                        });
+                        continue;
+                    }
 
-                        match zipf.write_all(&data) {
-                            Ok(()) => {}
-                            Err(err) => {
-                                log::error!("Error writing data to disk: {:?} : {}", pth, err);
-                            }
-                        };
+                    for (dir, curr_time) in dirs {
+                        let remote = remote.clone();
+                        let to_downloader = to_downloader.clone();
+                        let to_failures = to_failures.clone();
+                        let filter = filter.clone();
 
-                        log::debug!("Saved {:?}", pth);
-                        to_accumulator.send(pth).unwrap();
+                        pool.execute(move || {
+                            Self::list_dir(
+                                &remote,
+                                (dir, sat, prod, curr_time),
+                                &filter,
+                                &to_downloader,
+                                &to_failures,
+                            )
+                        });
                     }
                 }
+
+                pool.join();
             })?;
 
         Ok(jh)
     }
 
-    fn start_download_thread(
-        &self,
+    /// List a directory and pass it on with its files.
+    pub(crate) fn list_dir(
+        remote: &RA,
+        (dir, sat, prod, curr_time): (PathBuf, Satellite, Product, NaiveDateTime),
+        filter: &FileFilter,
+        to_downloader: &Sender<ListedDir>,
+        to_failures: &Sender<RetrievalFailure>,
+    ) {
+        match remote.retrieve_remote_listing(sat, prod, curr_time, filter) {
+            Ok(files) => {
+                let _ = to_downloader.send((dir, sat, prod, curr_time, files));
+            }
+            Err(err) => {
+                log::error!("Error retreiving remote file names: {}", err);
+                let failure = RetrievalFailure::new(sat, prod, curr_time, None, err.as_ref());
+                let _ = to_failures.send(failure);
+            }
+        }
+    }
+
+    /// List a day of directories with one request and pass each directory on with its files.
+    pub(crate) fn list_day(
+        remote: &RA,
         sat: Satellite,
         prod: Product,
-        local_dirs: Receiver<(PathBuf, NaiveDateTime)>,
-        to_data_saver: Sender<(PathBuf, Vec<u8>)>,
-        to_accumulator: Sender<PathBuf>,
-    ) -> Result<(), Box<dyn Error>> {
-        const NUM_DOWNLOADERS: usize = 3;
+        dirs: Vec<(PathBuf, NaiveDateTime)>,
+        filter: &FileFilter,
+        to_downloader: &Sender<ListedDir>,
+        to_failures: &Sender<RetrievalFailure>,
+    ) {
+        let day = dirs[0].1.date();
+        let day_files = match remote.retrieve_remote_listing_for_day(sat, prod, day, filter) {
+            Ok(files) => files,
+            Err(err) => {
+                log::error!("Error retreiving remote file names: {}", err);
+                for (_, curr_time) in dirs {
+                    let failure = RetrievalFailure::new(sat, prod, curr_time, None, err.as_ref());
+                    let _ = to_failures.send(failure);
+                }
+                return;
+            }
+        };
 
-        let pool = threadpool::ThreadPool::with_name("Download Thread".to_owned(), NUM_DOWNLOADERS);
+        for (dir, curr_time) in dirs {
+            let files = day_files
+                .iter()
+                .filter(|(valid_time, _)| *valid_time == curr_time)
+                .map(|(_, file)| file.clone())
+                .collect();
 
-        let num_max_downloads = self.remote.max_downloads();
+            let _ = to_downloader.send((dir, sat, prod, curr_time, files));
+        }
+    }
+
+    fn start_download_thread(
+        &self,
+        local_dirs: Receiver<ListedDir>,
+        to_accumulator: Sender<Retrieved>,
+        to_failures: Sender<RetrievalFailure>,
+        filter: &FileFilter,
+    ) -> Result<(), Box<dyn Error>> {
+        let num_downloaders = self.num_downloaders();
+        let pool = threadpool::ThreadPool::with_name("Download Thread".to_owned(), num_downloaders);
 
-        for _ in 0..NUM_DOWNLOADERS {
-            let remote = self.remote.clone();
-            let to_data_saver = to_data_saver.clone();
+        for _ in 0..num_downloaders {
+            let archive = self.clone();
             let to_accumulator = to_accumulator.clone();
+            let to_failures = to_failures.clone();
             let local_dirs = local_dirs.clone();
-            let too_old_to_not_be_done = chrono::Utc::now().naive_utc() - Duration::hours(24);
+            let filter = filter.clone();
 
             pool.execute(move || {
-                for (dir, curr_time) in local_dirs {
-                    let count = COMPLETED_DOWNLOADS.load(Ordering::SeqCst);
-                    if count > num_max_downloads {
-                        log::warn!("MAX_DOWNLOADS limit exceeded, skipping {:?}", &dir);
-                        continue;
+                for listed in local_dirs {
+                    let (sat, prod) = (listed.1, listed.2);
+                    for pth in archive.download_dir(listed, &filter, &to_failures) {
+                        to_accumulator.send((sat, prod, pth)).unwrap();
                     }
+                }
+            });
+        }
 
-                    log::info!(
-                        "Downloading directory: {:?} approx {} downloads left.",
-                        &dir,
-                        num_max_downloads - count
-                    );
+        Ok(())
+    }
 
-                    let remote_filenames =
-                        match remote.retrieve_remote_filenames(sat, prod, curr_time) {
-                            Ok(fnames) => fnames,
-                            Err(err) => {
-                                log::error!("Error retreiving remote file names: {}", err);
-                                continue;
-                            }
-                        };
-
-                    let mut num_files = 0;
-                    for remote_fname in &remote_filenames {
-                        let local_path = dir.join(remote_fname);
-                        if local_path.exists() {
-                            log::debug!("Skipping download for {:?}", local_path);
-                            to_accumulator.send(local_path).unwrap();
-                            num_files += 1;
-                        } else {
-                            let data: Vec<u8> = match remote.retrieve_remote_file(
-                                sat,
-                                prod,
-                                curr_time,
-                                remote_fname,
-                            ) {
-                                Ok(data) => data,
-                                Err(err) => {
-                                    log::error!(
-                                        "Error downloading data: {} : {}",
-                                        remote_fname,
-                                        err
-                                    );
-                                    continue;
-                                }
-                            };
+    /// Download the files of a listed directory, then link, snapshot, mark, and compact it as
+    /// configured. Returns the saved files, or the directory itself if it was compacted.
+    pub(crate) fn download_dir(
+        &self,
+        (dir, sat, prod, curr_time, remote_files): ListedDir,
+        filter: &FileFilter,
+        to_failures: &Sender<RetrievalFailure>,
+    ) -> Vec<PathBuf> {
+        let num_max_downloads = self.remote.max_downloads();
+        let count = COMPLETED_DOWNLOADS.load(Ordering::SeqCst);
+        if count > num_max_downloads {
+            log::warn!("MAX_DOWNLOADS limit exceeded, skipping {:?}", &dir);
+            let err = GoesArchError::new("Download limit reached.");
+            let failure = RetrievalFailure::new(sat, prod, curr_time, None, &err);
+            let _ = to_failures.send(failure);
+            return vec![];
+        }
+
+        log::info!(
+            "Downloading directory: {:?} approx {} downloads left.",
+            &dir,
+            num_max_downloads - count
+        );
+
+        let _lock = match lock_dir(&dir) {
+            Ok(lock) => lock,
+            Err(err) => {
+                log::error!("Error locking directory: {:?} : {}", dir, err);
+                let failure = RetrievalFailure::new(sat, prod, curr_time, None, err.as_ref());
+                let _ = to_failures.send(failure);
+                return vec![];
+            }
+        };
+
+        // Saved files are passed on once the directory is done, in case it's compacted.
+        let mut saved = vec![];
+        let mut num_files = 0;
+        let mut failed = false;
+        let remote_filenames: Vec<String> =
+            remote_files.iter().map(|file| file.name.clone()).collect();
+        let wanted = match self.all_versions {
+            true => remote_filenames,
+            false => split_superseded(remote_filenames).0,
+        };
+        for remote_fname in &wanted {
+            let local_path = dir.join(remote_fname);
+            if let Some(existing) = find_stored(&local_path) {
+                log::debug!("Skipping download for {:?}", local_path);
+                saved.push(existing);
+                num_files += 1;
+                continue;
+            }
+
+            let res = self.retry_policy.run(remote_fname, || {
+                Self::download_compressed(
+                    &self.remote,
+                    sat,
+                    prod,
+                    curr_time,
+                    remote_fname,
+                    &local_path,
+                    self.compression,
+                )
+            });
 
-                            to_data_saver.send((local_path, data)).unwrap();
-                            num_files += 1;
-                            COMPLETED_DOWNLOADS.fetch_add(1, Ordering::SeqCst);
+            match res {
+                Ok(zpath) => {
+                    log::debug!("Saved {:?}", zpath);
+                    if self.checksum_files {
+                        if let Err(err) = write_sidecar(&zpath) {
+                            log::error!("Error writing checksum {:?} : {}", zpath, err);
                         }
                     }
+                    #[cfg(feature = "sqlite")]
+                    if let Some(ref index) = self.index {
+                        if let Err(err) = index.record(&zpath, sat, prod) {
+                            log::error!("Error indexing {:?} : {}", zpath, err);
+                        }
+                    }
+                    self.hooks.file_saved(&zpath);
+                    saved.push(zpath);
+                    num_files += 1;
+                    COMPLETED_DOWNLOADS.fetch_add(1, Ordering::SeqCst);
+                }
+                Err(err) => {
+                    log::error!("Error downloading data: {} : {}", remote_fname, err);
+                    self.hooks.download_error(remote_fname, err.as_ref());
+                    let failure = RetrievalFailure::new(
+                        sat,
+                        prod,
+                        curr_time,
+                        Some(remote_fname),
+                        err.as_ref(),
+                    );
+                    let _ = to_failures.send(failure);
+                    failed = true;
+                }
+            }
+        }
+
+        if self.role_aliases && num_files > 0 {
+            let roots: Vec<PathBuf> = self.roots().map(Path::to_owned).collect();
+            if let Err(err) = Self::link_role_alias(&roots, sat, curr_time, &dir) {
+                log::error!("Error linking role alias for {:?} : {}", dir, err);
+            }
+        }
+
+        if self.listing_snapshots {
+            let snapshot = ListingSnapshot::new(remote_files);
+            if let Err(err) = snapshot.write(&dir.join(LISTING_FNAME)) {
+                log::error!("Error saving listing for {:?} : {}", dir, err);
+            }
+        }
+
+        // Only mark the directory once every file in it is safely on disk.
+        if !failed && Self::hour_is_done(sat, prod, curr_time, num_files, filter) {
+            match Self::mark_dir_as_complete(&dir, sat, prod, curr_time, self.checksum_files) {
+                Ok(()) => self.hooks.hour_complete(sat, prod, curr_time, &dir),
+                Err(err) => {
+                    log::error!("Error marking directory complete: {:?} : {}", dir, err);
+                    let failure = RetrievalFailure::new(sat, prod, curr_time, None, err.as_ref());
+                    let _ = to_failures.send(failure);
+                }
+            }
 
-                    if num_files >= prod.max_num_per_hour() || curr_time < too_old_to_not_be_done {
-                        let now = chrono::Utc::now().naive_utc();
-                        let completion_marker = dir.join(HOUR_COMPLETE_FNAME);
-                        let complete_time = format!("{}\n", now).as_bytes().to_vec();
-                        to_data_saver
-                            .send((completion_marker, complete_time))
-                            .unwrap();
+            if self.compaction && dir.join(HOUR_COMPLETE_FNAME).exists() {
+                match Self::compact_dir(&dir) {
+                    Ok(_removed) => {
+                        #[cfg(feature = "sqlite")]
+                        if let Some(ref index) = self.index {
+                            for pth in &_removed {
+                                if let Err(err) = index.remove(pth) {
+                                    log::error!("Error unindexing {:?} : {}", pth, err);
+                                }
+                            }
+                        }
+
+                        // The saved files are in the bundle now.
+                        saved = vec![dir.clone()];
                     }
+                    Err(err) => log::error!("Error compacting {:?} : {}", dir, err),
                 }
-            });
+            }
         }
 
-        Ok(())
+        saved
     }
 
+    /// Turn complete directories into their files and pass every file on to the caller. Files
+    /// keep draining through if the caller stops listening, so the downloads can finish.
     fn start_accumulator_thread(
-        paths: Receiver<PathBuf>,
-    ) -> Result<JoinHandle<Vec<PathBuf>>, Box<dyn Error>> {
-        let th = thread::Builder::new()
-            .name("PathBuf Accumulator".to_owned())
-            .spawn(|| {
-                let mut to_ret = vec![];
-
-                for pth in paths {
-                    if pth.is_dir() {
-                        let read_dir = match read_dir(&pth) {
-                            Ok(read_dir) => read_dir,
-                            Err(err) => {
-                                log::error!("Error reading directory: {:?} : {}", pth, err);
-                                continue;
-                            }
-                        };
-
-                        for entry_res in read_dir {
-                            let entry = match entry_res {
-                                Ok(entry) => entry,
-                                Err(err) => {
-                                    log::error!("Error reading directory entry: {}", err);
-                                    continue;
-                                }
-                            };
+        &self,
+        paths: Receiver<Retrieved>,
+        to_caller: Sender<Retrieved>,
+        to_failures: Sender<RetrievalFailure>,
+        filter: FileFilter,
+    ) -> Result<(), Box<dyn Error>> {
+        let archive = self.clone();
 
-                            let file_pth = entry.path();
+        thread::Builder::new()
+            .name("PathBuf Accumulator".to_owned())
+            .spawn(move || {
+                for (sat, prod, pth) in paths {
+                    archive.finish_path(sat, prod, pth, &filter, &to_failures, &mut |pth| {
+                        let _ = to_caller.send((sat, prod, pth));
+                    });
+                }
+            })?;
 
-                            if file_pth.is_dir() {
-                                continue;
-                            }
+        Ok(())
+    }
 
-                            if let Some(ext) = file_pth.extension().map(|p| p.to_string_lossy()) {
-                                if ext != "nc" {
-                                    continue;
-                                }
-                            }
+    /// Turn a retrieved path into the files handed back to the caller: complete directories are
+    /// expanded, compacted files extracted, and decompressed copies made as configured.
+    pub(crate) fn finish_path(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        pth: PathBuf,
+        filter: &FileFilter,
+        to_failures: &Sender<RetrievalFailure>,
+        send: &mut dyn FnMut(PathBuf),
+    ) {
+        let roots: Vec<PathBuf> = self.roots().map(Path::to_owned).collect();
+        let mut finish = |pth: PathBuf| {
+            // Files in compacted directories are extracted, so every path is a file.
+            let pth = match extracted(&pth) {
+                Ok(extracted) => extracted,
+                Err(err) => {
+                    log::error!("Error extracting {:?} : {}", pth, err);
+                    if let Some(failure) = Self::file_failure(sat, prod, &pth, err.as_ref()) {
+                        let _ = to_failures.send(failure);
+                    }
+                    return;
+                }
+            };
 
-                            to_ret.push(file_pth);
-                        }
-                    } else {
-                        to_ret.push(pth);
+            let copy = self
+                .decompressed_dir
+                .as_ref()
+                .and_then(|dir| decompressed_path(&pth, dir, &roots));
+            let pth = match copy {
+                Some(copy) => match decompress_to(&pth, &copy) {
+                    Ok(()) => copy,
+                    Err(err) => {
+                        log::error!("Error decompressing {:?} : {}", pth, err);
+                        pth
                     }
+                },
+                None => pth,
+            };
+
+            send(pth);
+        };
+
+        // Downloaded files come through with an extension, complete directories don't.
+        #[cfg(feature = "sqlite")]
+        if let Some(indexed) = self
+            .index
+            .as_ref()
+            .filter(|_| pth.extension().is_none())
+            .and_then(|index| Self::indexed_dir_files(index, &pth))
+        {
+            indexed
+                .into_iter()
+                .filter(|pth| {
+                    pth.file_name()
+                        .is_some_and(|fname| filter.accepts(&fname.to_string_lossy()))
+                })
+                .for_each(finish);
+            return;
+        }
+
+        if pth.is_dir() {
+            Self::dir_files(&pth, filter).into_iter().for_each(finish);
+        } else {
+            finish(pth);
+        }
+    }
+
+    /// The indexed files of a complete directory, if the index has exactly the files its
+    /// completion marker lists. Otherwise the index may be missing files or hold stale ones, and
+    /// the directory has to be read instead.
+    #[cfg(feature = "sqlite")]
+    fn indexed_dir_files(index: &ArchiveIndex, dir: &Path) -> Option<Vec<PathBuf>> {
+        let indexed = index
+            .dir_files(dir)
+            .map_err(|err| log::error!("Error reading index: {}", err))
+            .ok()??;
+
+        let marker = CompletionMarker::read(&dir.join(HOUR_COMPLETE_FNAME)).ok()?;
+        let marked: HashSet<&str> = marker.files.iter().map(|f| f.name.as_str()).collect();
+
+        let matches = !marked.is_empty()
+            && indexed.len() == marked.len()
+            && indexed.iter().all(|pth| {
+                pth.file_name()
+                    .is_some_and(|fname| marked.contains(&*fname.to_string_lossy()))
+            });
+
+        matches.then_some(indexed)
+    }
+
+    /// A failure for a file the retrieval couldn't hand back, if its name says when it's from.
+    fn file_failure(
+        sat: Satellite,
+        prod: Product,
+        pth: &Path,
+        err: &(dyn Error + 'static),
+    ) -> Option<RetrievalFailure> {
+        let fname = pth.file_name()?.to_string_lossy();
+        let valid_time = Self::floor_time(scan_start(&fname)?, sat.directory_interval());
+
+        Some(RetrievalFailure::new(
+            sat,
+            prod,
+            valid_time,
+            Some(&fname),
+            err,
+        ))
+    }
+
+    /// Whether a downloaded directory can be marked complete. A filtered hour never has all its
+    /// files, so it can't be.
+    pub(crate) fn hour_is_done(
+        sat: Satellite,
+        prod: Product,
+        curr_time: NaiveDateTime,
+        num_files: i32,
+        filter: &FileFilter,
+    ) -> bool {
+        if !filter.is_unrestricted() {
+            return false;
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let too_old_to_not_be_done = now - Duration::hours(24);
+
+        // Hours with known data problems won't ever fill up, so once the hour is over there is
+        // no point in listing it again.
+        let degraded = sat.data_availability(prod, curr_time) == DataAvailability::Degraded
+            && curr_time + sat.directory_interval() < now - Duration::hours(1);
+
+        num_files >= Self::expected_files(sat, prod, curr_time)
+            || curr_time < too_old_to_not_be_done
+            || degraded
+    }
+
+    /// Stream a remote file straight into a compressed file on disk, so it's never all in
+    /// memory at once, returning where it was stored. Nothing is left under the final name if
+    /// the download fails.
+    fn download_compressed(
+        remote: &RA,
+        sat: Satellite,
+        prod: Product,
+        valid_time: NaiveDateTime,
+        remote_fname: &str,
+        local_path: &Path,
+        compression: Compression,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        let zpath = compression.stored_path(local_path);
+
+        compression.write(local_path, &zpath, |writer| {
+            remote.retrieve_remote_file_to(sat, prod, valid_time, remote_fname, writer)
+        })?;
+
+        Ok(zpath)
+    }
+
+    /// The data files in an archive directory that pass the filter.
+    pub(crate) fn dir_files(pth: &Path, filter: &FileFilter) -> Vec<PathBuf> {
+        let mut to_ret = vec![];
+
+        let read_dir = match read_dir(pth) {
+            Ok(read_dir) => read_dir,
+            Err(err) => {
+                log::error!("Error reading directory: {:?} : {}", pth, err);
+                return to_ret;
+            }
+        };
+
+        for entry_res in read_dir {
+            let entry = match entry_res {
+                Ok(entry) => entry,
+                Err(err) => {
+                    log::error!("Error reading directory entry: {}", err);
+                    continue;
                 }
+            };
 
-                to_ret
-            })?;
+            let file_pth = entry.path();
+
+            if file_pth.is_dir() {
+                continue;
+            }
+
+            let accepted = file_pth.file_name().is_some_and(|fname| {
+                let fname = fname.to_string_lossy();
+                filter.is_data_file(&fname) && filter.accepts(&fname)
+            });
+            if !accepted {
+                continue;
+            }
+
+            to_ret.push(file_pth);
+        }
 
-        Ok(th)
+        // Files packed away by `compact`.
+        to_ret.extend(bundled_paths(pth).into_iter().filter(|bundled| {
+            bundled.file_name().is_some_and(|fname| {
+                let fname = fname.to_string_lossy();
+                filter.is_data_file(&fname) && filter.accepts(&fname)
+            })
+        }));
+
+        to_ret
     }
 
-    fn validate_dates(
+    pub(crate) fn validate_dates(
         sat: Satellite,
         prod: Product,
         start: NaiveDateTime,
@@ -298,6 +1420,11 @@ where
     ) -> Result<(NaiveDateTime, NaiveDateTime), GoesArchError> {
         log::info!("start - {} end {}", start, end);
 
+        if !prod.available_on(sat) {
+            log::error!("{:?} is not available on {:?}", prod, sat);
+            return Err(GoesArchError::new("Product not available on satellite."));
+        }
+
         if end < start {
             log::error!("End before start: start - {} end - {}", start, end);
             return Err(GoesArchError::new("Invalid satellite dates."));
@@ -310,15 +1437,35 @@ where
             log::warn!("valid start time was adjusted to start - {}", valid_start);
         }
 
-        if end < valid_start {
-            log::error!("End before start: start - {} end - {}", valid_start, end);
+        let valid_end = match sat.latest_operational_date(prod) {
+            Some(latest) if end > latest => latest,
+            _ => end,
+        };
+
+        if valid_end != end {
+            log::warn!("valid end time was adjusted to end - {}", valid_end);
+        }
+
+        if valid_end < valid_start {
+            log::error!(
+                "End before start: start - {} end - {}",
+                valid_start,
+                valid_end
+            );
             Err(GoesArchError::new("Invalid satellite dates."))
         } else {
-            Ok((valid_start, end))
+            Ok((valid_start, valid_end))
         }
     }
 
-    fn path_is_complete(pth: &Path, prod: Product) -> Result<bool, Box<dyn Error>> {
+    pub(crate) fn path_is_complete(
+        pth: &Path,
+        sat: Satellite,
+        prod: Product,
+        valid_time: NaiveDateTime,
+        filter: &FileFilter,
+        checksums: bool,
+    ) -> Result<bool, Box<dyn Error>> {
         if !pth.exists() {
             create_dir_all(pth)?;
             log::debug!("Creating path: {:?}", pth);
@@ -332,24 +1479,38 @@ where
             return Ok(true);
         }
 
-        let num_files: usize = read_dir(&pth)?
+        let num_files: usize = read_dir(pth)?
             .filter_map(|e| e.ok())
             .map(|e| e.path())
-            .filter_map(|pth| {
-                pth.extension().map(|ext| {
-                    let ext = ext.to_string_lossy();
-                    ext == "nc" || ext == "zip"
+            .filter(|pth| {
+                pth.file_name().is_some_and(|fname| {
+                    let fname = fname.to_string_lossy();
+                    filter.is_data_file(&fname) && filter.accepts(&fname)
                 })
             })
-            .filter(|ext_bool| *ext_bool)
             .count();
 
-        if num_files >= prod.max_num_per_hour() as usize {
+        let expected = filter.expected_files(prod, Self::expected_files(sat, prod, valid_time));
+        if num_files < expected as usize
+            && filter.is_unrestricted()
+            && Self::has_degraded_listing(pth, sat, prod, valid_time)
+        {
+            log::debug!("Degraded hour has every listed file: {:?}", pth);
+            Self::mark_dir_as_complete(pth, sat, prod, valid_time, checksums)?;
+            return Ok(true);
+        }
+
+        if num_files >= expected as usize {
+            if !filter.is_unrestricted() {
+                log::debug!("Enough filtered files found in path: {:?}", pth);
+                return Ok(true);
+            }
+
             log::debug!(
                 "Enough files found in path to mark it as complete: {:?}",
                 pth
             );
-            Self::mark_dir_as_complete(pth)?;
+            Self::mark_dir_as_complete(pth, sat, prod, valid_time, checksums)?;
             return Ok(true);
         }
 
@@ -357,34 +1518,227 @@ where
         Ok(false)
     }
 
-    fn mark_dir_as_complete(pth: &Path) -> Result<(), Box<dyn Error>> {
+    /// Whether an hour with known data problems is over and has every file in the listing it was
+    /// downloaded from, so it's short because the files were never made, not because the
+    /// download didn't finish. Without a listing snapshot there's no telling.
+    fn has_degraded_listing(
+        pth: &Path,
+        sat: Satellite,
+        prod: Product,
+        valid_time: NaiveDateTime,
+    ) -> bool {
         let now = chrono::Utc::now().naive_utc();
-        let completion_marker = pth.join(HOUR_COMPLETE_FNAME);
+        if sat.data_availability(prod, valid_time) != DataAvailability::Degraded
+            || valid_time + sat.directory_interval() >= now - Duration::hours(1)
+        {
+            return false;
+        }
 
-        let mut f = File::create(completion_marker)?;
-        let complete_time = format!("{}\n", now);
+        let snapshot = match ListingSnapshot::read(&pth.join(LISTING_FNAME)) {
+            Ok(snapshot) => snapshot,
+            Err(_) => return false,
+        };
 
-        f.write_all(complete_time.as_bytes())?;
+        let stored: HashSet<String> = Self::dir_files(pth, &FileFilter::default())
+            .iter()
+            .filter_map(|pth| pth.file_name())
+            .map(|fname| original_name(&fname.to_string_lossy()).to_owned())
+            .collect();
 
-        Ok(())
+        let listed: Vec<String> = snapshot.files.into_iter().map(|file| file.name).collect();
+        !listed.is_empty()
+            && split_superseded(listed)
+                .0
+                .iter()
+                .all(|fname| stored.contains(fname))
+    }
+
+    /// Write the completion marker of a directory, with checksums of its files if `checksums`.
+    pub(crate) fn mark_dir_as_complete(
+        pth: &Path,
+        sat: Satellite,
+        prod: Product,
+        valid_time: NaiveDateTime,
+        checksums: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let expected = Self::expected_files(sat, prod, valid_time);
+        let (files, _) = split_superseded(Self::dir_files(pth, &FileFilter::default()));
+
+        let basis = if files.len() >= expected.max(0) as usize {
+            MarkerBasis::Count
+        } else if sat.data_availability(prod, valid_time) == DataAvailability::Degraded {
+            MarkerBasis::Degraded
+        } else {
+            MarkerBasis::Age
+        };
+
+        CompletionMarker::for_dir(pth, expected, basis, checksums)?
+            .write(&pth.join(HOUR_COMPLETE_FNAME))
+    }
+
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub(crate) fn remote(&self) -> &RA {
+        &self.remote
+    }
+
+    pub(crate) fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// The number of download threads, at least one and at most the remote's `max_downloads`.
+    pub(crate) fn num_downloaders(&self) -> usize {
+        self.num_downloaders.min(self.remote.max_downloads()).max(1)
+    }
+
+    pub(crate) fn all_versions(&self) -> bool {
+        self.all_versions
+    }
+
+    pub(crate) fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    pub(crate) fn checksum_files(&self) -> bool {
+        self.checksum_files
+    }
+
+    pub(crate) fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn index(&self) -> Option<&ArchiveIndex> {
+        self.index.as_ref()
+    }
+
+    /// Every directory of the product in the archive paired with its valid time, the reverse of
+    /// `build_path`. Directories that don't fit the layout are skipped.
+    pub(crate) fn time_dirs(
+        &self,
+        sat: Satellite,
+        prod: Product,
+    ) -> Result<Vec<(PathBuf, NaiveDateTime)>, Box<dyn Error>> {
+        let has_minutes = sat.directory_interval() < Duration::hours(1);
+
+        // Subdirectories whose names parse as a number of the given width.
+        let numbered = |dir: &Path, width: usize| -> Result<Vec<(PathBuf, u32)>, Box<dyn Error>> {
+            let mut dirs = vec![];
+            if !dir.is_dir() {
+                return Ok(dirs);
+            }
+
+            for entry in read_dir(dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.len() != width || !entry.file_type()?.is_dir() {
+                    continue;
+                }
+                if let Ok(num) = name.parse() {
+                    dirs.push((entry.path(), num));
+                }
+            }
+
+            Ok(dirs)
+        };
+
+        let mut to_ret = vec![];
+        for root in self.roots() {
+            let prod_dir = root.join(sat.short_name()).join(prod.prefix());
+
+            for (year_dir, year) in numbered(&prod_dir, 4)? {
+                for (day_dir, day) in numbered(&year_dir, 3)? {
+                    let date = match chrono::NaiveDate::from_yo_opt(year as i32, day) {
+                        Some(date) => date,
+                        None => continue,
+                    };
+
+                    for (hour_dir, hour) in numbered(&day_dir, 2)? {
+                        if !has_minutes {
+                            if let Some(time) = date.and_hms_opt(hour, 0, 0) {
+                                to_ret.push((hour_dir, time));
+                            }
+                            continue;
+                        }
+
+                        for (minute_dir, minute) in numbered(&hour_dir, 2)? {
+                            if let Some(time) = date.and_hms_opt(hour, minute, 0) {
+                                to_ret.push((minute_dir, time));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        to_ret.sort_by_key(|(_, time)| *time);
+
+        Ok(to_ret)
+    }
+
+    /// The directory for the valid time in the tier it belongs in by age, whether or not it's
+    /// there.
+    pub(crate) fn placed_path(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_time: NaiveDateTime,
+    ) -> PathBuf {
+        Self::tier_path(self.tier_root(valid_time), sat, prod, valid_time)
     }
 
-    fn build_path(
+    /// The directory for the valid time. With tiers, this is wherever the directory already is,
+    /// or else the tier it belongs in by age.
+    pub(crate) fn build_path(
         &self,
         sat: Satellite,
         prod: Product,
-        valid_time_to_the_hour: NaiveDateTime,
+        valid_time: NaiveDateTime,
     ) -> PathBuf {
+        if self.tiers.is_empty() {
+            return Self::tier_path(&self.root, sat, prod, valid_time);
+        }
+
+        self.roots()
+            .map(|root| Self::tier_path(root, sat, prod, valid_time))
+            .find(|pth| pth.is_dir())
+            .unwrap_or_else(|| self.placed_path(sat, prod, valid_time))
+    }
+
+    /// The main root followed by the roots of any tiers.
+    pub(crate) fn roots(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.root.as_path())
+            .chain(self.tiers.iter().map(|(root, _)| root.as_path()))
+    }
+
+    /// The root of the tier data for the valid time belongs in, based on its age.
+    fn tier_root(&self, valid_time: NaiveDateTime) -> &Path {
+        let age = chrono::Utc::now().naive_utc() - valid_time;
+
+        self.tiers
+            .iter()
+            .rev()
+            .find(|(_, older_than)| age >= *older_than)
+            .map_or(&self.root, |(root, _)| root)
+    }
+
+    fn tier_path(root: &Path, sat: Satellite, prod: Product, valid_time: NaiveDateTime) -> PathBuf {
         let mut pth = PathBuf::new();
 
-        pth.push(&self.root);
-        pth.push::<&'static str>(sat.into());
-        pth.push::<&'static str>(prod.into());
+        pth.push(root);
+        pth.push(sat.short_name());
+        pth.push(prod.prefix());
 
-        let year = valid_time_to_the_hour.year();
-        let day = valid_time_to_the_hour.ordinal();
-        let hour = valid_time_to_the_hour.hour();
-        pth.push(&format!("{:04}/{:03}/{:02}", year, day, hour));
+        let year = valid_time.year();
+        let day = valid_time.ordinal();
+        let hour = valid_time.hour();
+        pth.push(format!("{:04}/{:03}/{:02}", year, day, hour));
+
+        if sat.directory_interval() < Duration::hours(1) {
+            pth.push(format!("{:02}", valid_time.minute()));
+        }
 
         pth
     }
@@ -0,0 +1,525 @@
+use crate::{
+    archive::{is_data_file, try_lock_dir, Archive, HOUR_COMPLETE_FNAME, STATIC_DIR},
+    bundle::{bundled_paths, BUNDLE_FNAME},
+    filename::split_superseded,
+    filter::FileFilter,
+    marker::sidecar_path,
+    product::Product,
+    remote::RemoteArchive,
+    retention::RetentionPolicy,
+    satellite::Satellite,
+};
+use chrono::{naive::NaiveDateTime, Duration, Utc};
+use std::{
+    error::Error,
+    fs::{copy, create_dir_all, read_dir, remove_dir, remove_dir_all, remove_file, rename},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Delete every directory of the product holding data older than `older_than`, along with its
+    /// completion marker, returning the number of data files deleted. Directories that are only
+    /// partly beyond the retention window are kept. Day and year directories left empty are
+    /// removed too.
+    pub fn prune(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        older_than: Duration,
+    ) -> Result<usize, Box<dyn Error>> {
+        let cutoff = Utc::now().naive_utc() - older_than;
+        let step = sat.directory_interval();
+
+        let mut num_files = 0;
+        for (dir, valid_time) in self.time_dirs(sat, prod)? {
+            if valid_time + step > cutoff {
+                break;
+            }
+
+            num_files += self.remove_time_dir(&dir)?;
+        }
+
+        for root in self.roots() {
+            remove_empty_dirs(&root.join(sat.short_name()).join(prod.prefix()))?;
+        }
+
+        log::info!(
+            "Pruned {} files of {:?} {:?} older than {}",
+            num_files,
+            sat,
+            prod,
+            cutoff
+        );
+
+        Ok(num_files)
+    }
+
+    /// Delete the product from `start` through `end` from the archive, files, completion markers,
+    /// and directories alike, so the period is downloaded fresh the next time it's requested.
+    /// Returns the number of data files deleted.
+    pub fn remove(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<usize, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let mut num_files = 0;
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let dir = self.build_path(sat, prod, valid_time);
+            if dir.is_dir() {
+                num_files += self.remove_time_dir(&dir)?;
+            }
+        }
+
+        for root in self.roots() {
+            remove_empty_dirs(&root.join(sat.short_name()).join(prod.prefix()))?;
+        }
+
+        log::info!(
+            "Removed {} files of {:?} {:?} from {} through {}",
+            num_files,
+            sat,
+            prod,
+            start,
+            end
+        );
+
+        Ok(num_files)
+    }
+
+    /// Enforce a retention policy, pruning each product of every satellite by its age rule and
+    /// then evicting data to fit the size limit, if there is one. Every tier is covered. Returns
+    /// the number of data files pruned by age.
+    pub fn apply_retention(&self, policy: &RetentionPolicy) -> Result<usize, Box<dyn Error>> {
+        let mut num_files = 0;
+        for sat in Satellite::all() {
+            for prod in Product::all() {
+                let max_age = match policy.max_age(prod) {
+                    Some(max_age) => max_age,
+                    None => continue,
+                };
+
+                let in_archive = self
+                    .roots()
+                    .any(|root| root.join(sat.short_name()).join(prod.prefix()).is_dir());
+                if in_archive {
+                    num_files += self.prune(sat, prod, max_age)?;
+                }
+            }
+        }
+
+        if let Some(max_bytes) = policy.quota() {
+            self.enforce_quota(max_bytes)?;
+        }
+
+        Ok(num_files)
+    }
+
+    /// Re-evaluate whether each directory of the product from `start` through `end` is complete
+    /// from the files on disk alone, writing completion markers for those with all their
+    /// expected files and removing them from those without. Superseded versions of a scan don't
+    /// count. Returns the number of directories marked complete.
+    pub fn rebuild_markers(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<usize, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let mut num_dirs = 0;
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let dir = self.build_path(sat, prod, valid_time);
+            if !dir.is_dir() {
+                continue;
+            }
+
+            let (files, _) = split_superseded(Self::dir_files(&dir, &FileFilter::default()));
+            let marker = dir.join(HOUR_COMPLETE_FNAME);
+
+            if files.len() >= Self::expected_files(sat, prod, valid_time).max(0) as usize {
+                Self::mark_dir_as_complete(&dir, sat, prod, valid_time, self.checksum_files())?;
+                num_dirs += 1;
+            } else if marker.exists() {
+                remove_file(&marker)?;
+                log::debug!("Removed completion marker from {:?}", dir);
+            }
+        }
+
+        log::info!(
+            "Marked {} directories of {:?} {:?} complete",
+            num_dirs,
+            sat,
+            prod
+        );
+
+        Ok(num_dirs)
+    }
+
+    /// Delete files superseded by a newer version of the same scan, returning how many were
+    /// deleted.
+    pub fn remove_superseded(
+        &self,
+        sat: Satellite,
+        prod: Product,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut num_files = 0;
+        for (dir, valid_time) in self.time_dirs(sat, prod)? {
+            let (_, superseded) = split_superseded(Self::dir_files(&dir, &FileFilter::default()));
+            if superseded.is_empty() {
+                continue;
+            }
+
+            for pth in superseded {
+                remove_file(&pth)?;
+                log::debug!("Removed superseded {:?}", pth);
+
+                let sidecar = sidecar_path(&pth);
+                if sidecar.exists() {
+                    remove_file(sidecar)?;
+                }
+                num_files += 1;
+
+                #[cfg(feature = "sqlite")]
+                if let Some(index) = self.index() {
+                    index.remove(&pth)?;
+                }
+            }
+
+            // Keep the file list in the completion marker in step with the directory.
+            if dir.join(HOUR_COMPLETE_FNAME).exists() {
+                Self::mark_dir_as_complete(&dir, sat, prod, valid_time, self.checksum_files())?;
+            }
+        }
+
+        Ok(num_files)
+    }
+
+    /// Move every directory that has aged out of its tier into the tier it now belongs in,
+    /// returning the number of directories moved. Directories already present in their new tier
+    /// are left where they are, as are those locked by a download in progress.
+    pub fn rebalance_tiers(&self) -> Result<usize, Box<dyn Error>> {
+        let mut num_dirs = 0;
+        for sat in Satellite::all() {
+            for prod in Product::all() {
+                for (dir, valid_time) in self.time_dirs(sat, prod)? {
+                    let dest = self.placed_path(sat, prod, valid_time);
+                    if dest == dir || dest.exists() {
+                        continue;
+                    }
+
+                    let lock = match try_lock_dir(&dir)? {
+                        Some(lock) => lock,
+                        None => {
+                            log::debug!("Not moving locked {:?}", dir);
+                            continue;
+                        }
+                    };
+
+                    move_dir(&dir, &dest)?;
+                    drop(lock);
+                    log::debug!("Moved {:?} to {:?}", dir, dest);
+                    self.remove_empty_parents(&dir)?;
+                    num_dirs += 1;
+
+                    #[cfg(feature = "sqlite")]
+                    if let Some(index) = self.index() {
+                        index.remove_dir(&dir)?;
+                        for entry in read_dir(&dest)? {
+                            let pth = entry?.path();
+                            if is_data_file(&pth) {
+                                index.record(&pth, sat, prod)?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        log::info!("Moved {} directories between tiers", num_dirs);
+
+        Ok(num_dirs)
+    }
+
+    /// Evict the directories written longest ago until the data files in the archive take up no
+    /// more than `max_bytes`, returning the number of bytes freed. A directory was written when
+    /// the newest of its files was, going by modification times since access times often aren't
+    /// kept. Directories locked by a download in progress are skipped. Only what's already on
+    /// disk counts, so when it's run before a retrieval the new files can take the archive over
+    /// the quota until the next one.
+    pub fn enforce_quota(&self, max_bytes: u64) -> Result<u64, Box<dyn Error>> {
+        let mut dirs = vec![];
+        for root in self.roots().filter(|root| root.is_dir()) {
+            data_dirs(root, &mut dirs)?;
+        }
+
+        let mut total: u64 = dirs.iter().map(|dir| dir.size).sum();
+        if total <= max_bytes {
+            return Ok(0);
+        }
+
+        dirs.sort_by_key(|dir| dir.last_written);
+
+        let mut freed = 0;
+        for dir in dirs {
+            if total <= max_bytes {
+                break;
+            }
+
+            let _lock = match try_lock_dir(&dir.path)? {
+                Some(lock) => lock,
+                None => {
+                    log::debug!("Not evicting locked {:?}", dir.path);
+                    continue;
+                }
+            };
+
+            self.remove_time_dir(&dir.path)?;
+            self.remove_empty_parents(&dir.path)?;
+            total -= dir.size;
+            freed += dir.size;
+        }
+
+        log::info!("Evicted {} bytes to stay under the quota", freed);
+
+        Ok(freed)
+    }
+
+    /// Remove the day, year, and other directories above a removed directory that it left empty,
+    /// stopping at the root of its tier.
+    fn remove_empty_parents(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        let root = match self
+            .roots()
+            .filter(|root| dir.starts_with(root))
+            .max_by_key(|root| root.components().count())
+        {
+            Some(root) => root,
+            None => return Ok(()),
+        };
+
+        for parent in dir.ancestors().skip(1) {
+            if parent == root || read_dir(parent)?.next().is_some() {
+                break;
+            }
+            remove_dir(parent)?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete a directory from the archive, returning the number of data files that were in it.
+    pub(crate) fn remove_time_dir(&self, dir: &Path) -> Result<usize, Box<dyn Error>> {
+        let mut num_files = bundled_paths(dir).len();
+        for entry in read_dir(dir)? {
+            if is_data_file(&entry?.path()) {
+                num_files += 1;
+            }
+        }
+
+        remove_dir_all(dir)?;
+        log::debug!("Removed {:?}", dir);
+
+        #[cfg(feature = "sqlite")]
+        if let Some(index) = self.index() {
+            index.remove_dir(dir)?;
+        }
+
+        Ok(num_files)
+    }
+}
+
+/// Remove the empty directories below `dir`, returning whether `dir` itself ended up empty.
+pub(crate) fn remove_empty_dirs(dir: &Path) -> Result<bool, Box<dyn Error>> {
+    if !dir.is_dir() {
+        return Ok(false);
+    }
+
+    let mut empty = true;
+    for entry in read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() && remove_empty_dirs(&entry.path())? {
+            remove_dir(entry.path())?;
+        } else {
+            empty = false;
+        }
+    }
+
+    Ok(empty)
+}
+
+/// A directory holding data files, with their total size and the last time any was written.
+struct DataDir {
+    path: PathBuf,
+    size: u64,
+    last_written: SystemTime,
+}
+
+/// Find every directory below `dir` holding data files, skipping the static files.
+fn data_dirs(dir: &Path, found: &mut Vec<DataDir>) -> Result<(), Box<dyn Error>> {
+    let mut size = 0;
+    let mut last_written = None;
+    for entry in read_dir(dir)? {
+        let entry = entry?;
+        let pth = entry.path();
+        let meta = entry.metadata()?;
+
+        if meta.is_dir() {
+            if entry.file_name() != STATIC_DIR {
+                data_dirs(&pth, found)?;
+            }
+        } else if is_data_file(&pth) || entry.file_name() == BUNDLE_FNAME {
+            size += meta.len();
+            last_written = last_written.max(Some(meta.modified()?));
+        }
+    }
+
+    if let Some(last_written) = last_written {
+        found.push(DataDir {
+            path: dir.to_owned(),
+            size,
+            last_written,
+        });
+    }
+
+    Ok(())
+}
+
+/// Move a directory of files, copying them if it's on another file system.
+fn move_dir(src: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = dest.parent() {
+        create_dir_all(parent)?;
+    }
+
+    if rename(src, dest).is_ok() {
+        return Ok(());
+    }
+
+    create_dir_all(dest)?;
+    for entry in read_dir(src)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            copy(entry.path(), dest.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(remove_dir_all(src)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{archive::lock_dir, LocalDirRemote};
+    use std::{fs::File, time::Duration as StdDuration};
+
+    /// A data file in the FDCC directory of the hour, written `age_secs` ago.
+    fn write_hour(root: &Path, hour: u32, age_secs: u64) -> PathBuf {
+        write_sat_hour(root, "G16", hour, age_secs)
+    }
+
+    fn write_sat_hour(root: &Path, sat_dir: &str, hour: u32, age_secs: u64) -> PathBuf {
+        let dir = root.join(format!("{}/ABI-L2-FDCC/2023/152/{:02}", sat_dir, hour));
+        create_dir_all(&dir).unwrap();
+
+        let fname = format!(
+            "OR_ABI-L2-FDCC-M6_G16_s2023152{:02}01172_e2023152{:02}03545_c2023152{:02}04087.nc",
+            hour, hour, hour
+        );
+        let f = File::create(dir.join(fname)).unwrap();
+        f.set_len(100).unwrap();
+        f.set_modified(SystemTime::now() - StdDuration::from_secs(age_secs))
+            .unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn quota_evicts_oldest_unlocked_directories() {
+        let root = std::env::temp_dir().join(format!("goes_arch_quota_{}", std::process::id()));
+        let oldest = write_hour(&root, 18, 300);
+        let older = write_hour(&root, 19, 200);
+        let newest = write_hour(&root, 20, 100);
+
+        let arch = Archive::connect(&root, LocalDirRemote::new(&root, 1));
+        let lock = lock_dir(&oldest).unwrap();
+
+        assert_eq!(arch.enforce_quota(150).unwrap(), 200);
+        assert!(oldest.is_dir());
+        assert!(!older.exists());
+        assert!(!newest.exists());
+
+        drop(lock);
+        assert_eq!(arch.enforce_quota(150).unwrap(), 0);
+        assert_eq!(arch.enforce_quota(0).unwrap(), 100);
+        assert!(!root.join("G16").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn retention_covers_every_tier_and_custom_satellites() {
+        let root = std::env::temp_dir().join(format!("goes_arch_retention_{}", std::process::id()));
+        let tier = root.join("tier");
+        let sat = Satellite::custom("retention-test-bucket", NaiveDateTime::MIN).unwrap();
+        let custom = write_sat_hour(&tier, sat.short_name(), 18, 0);
+        let goes = write_sat_hour(&tier, "G16", 19, 0);
+
+        let arch = Archive::connect(root.join("main"), LocalDirRemote::new(&root, 1))
+            .with_tier(&tier, Duration::days(30));
+        let keep = RetentionPolicy::new().keep_forever(Product::FDCC);
+        assert_eq!(arch.apply_retention(&keep).unwrap(), 0);
+        assert!(custom.is_dir() && goes.is_dir());
+
+        let policy = RetentionPolicy::new().keep_for(Product::FDCC, Duration::days(30));
+        assert_eq!(arch.apply_retention(&policy).unwrap(), 2);
+        assert!(!custom.exists());
+        assert!(!goes.exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn rebalancing_moves_custom_data_and_skips_locked_directories() {
+        let root = std::env::temp_dir().join(format!("goes_arch_tiers_{}", std::process::id()));
+        let main = root.join("main");
+        let tier = root.join("tier");
+        let sat = Satellite::custom("tiers-test-bucket", NaiveDateTime::MIN).unwrap();
+        let prod = Product::custom("ABI-L2-TIERTEST", 12).unwrap();
+
+        let custom_sat = write_sat_hour(&main, sat.short_name(), 18, 0);
+        let locked = write_hour(&main, 19, 0);
+        let custom_prod = main.join("G16").join(prod.prefix()).join("2023/152/20");
+        create_dir_all(&custom_prod).unwrap();
+        std::fs::write(custom_prod.join("data.nc"), b"data").unwrap();
+
+        let arch = Archive::connect(&main, LocalDirRemote::new(&root, 1))
+            .with_tier(&tier, Duration::days(30));
+        let lock = lock_dir(&locked).unwrap();
+
+        assert_eq!(arch.rebalance_tiers().unwrap(), 2);
+        assert!(!custom_sat.exists());
+        assert!(tier
+            .join(sat.short_name())
+            .join("ABI-L2-FDCC/2023/152/18")
+            .is_dir());
+        assert!(tier
+            .join("G16")
+            .join(prod.prefix())
+            .join("2023/152/20/data.nc")
+            .is_file());
+        assert!(locked.is_dir());
+
+        drop(lock);
+        assert_eq!(arch.rebalance_tiers().unwrap(), 1);
+        assert!(tier.join("G16/ABI-L2-FDCC/2023/152/19").is_dir());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    archive::{Archive, STATIC_DIR},
+    bundle::{bundle_member, BUNDLE_FNAME},
+    compression::{find_stored, open_archived_file, original_name},
+    error::GoesArchError,
+    filter::FileFilter,
+    product::Product,
+    remote::{static_file_path, RemoteArchive, RemoteMetadata},
+    satellite::Satellite,
+};
+use chrono::{naive::NaiveDateTime, DateTime, Utc};
+use std::{error::Error, fs::File, io::Write, path::Path};
+
+/// Serve the files already in one archive to another, e.g. a workstation pulling a subset from
+/// the lab server that was populated from S3. Only files on disk are served, nothing is
+/// downloaded from the upstream archive's own remote. Zipped files are served uncompressed.
+impl<RA: 'static> RemoteArchive for Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    fn connect(_num_max_downloads: usize) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        Err(Box::new(GoesArchError::new(
+            "Serving another archive needs a connected archive, use Archive::connect.",
+        )))
+    }
+
+    fn retrieve_remote_filenames(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let dir = self.build_path(sat, prod, valid_hour);
+        if !dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut fnames = vec![];
+        for pth in Self::dir_files(&dir, filter) {
+            let fname = match pth.file_name() {
+                Some(fname) => original_name(&fname.to_string_lossy()).to_owned(),
+                None => continue,
+            };
+
+            if !fnames.contains(&fname) {
+                fnames.push(fname);
+            }
+        }
+
+        Ok(fnames)
+    }
+
+    fn retrieve_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut data = vec![];
+        copy_archived_file(
+            &self.build_path(sat, prod, valid_hour).join(remote_path),
+            &mut data,
+        )?;
+
+        Ok(data)
+    }
+
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        copy_archived_file(
+            &self.build_path(sat, prod, valid_hour).join(remote_path),
+            writer,
+        )
+    }
+
+    fn retrieve_remote_metadata(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        let pth = self.build_path(sat, prod, valid_hour).join(remote_path);
+        if pth.exists() {
+            let meta = std::fs::metadata(&pth)?;
+            return Ok(RemoteMetadata {
+                size: meta.len(),
+                etag: None,
+                last_modified: meta
+                    .modified()
+                    .ok()
+                    .map(|t| DateTime::<Utc>::from(t).naive_utc()),
+            });
+        }
+
+        // Compressed files report the size of the original file.
+        let zpath = find_stored(&pth).ok_or_else(|| GoesArchError::new("File not in archive."))?;
+        let modified = match bundle_member(&zpath) {
+            Some((dir, _)) => std::fs::metadata(dir.join(BUNDLE_FNAME))?.modified().ok(),
+            None => std::fs::metadata(&zpath)?.modified().ok(),
+        };
+        let size = if zpath.extension().is_some_and(|ext| ext == "zip")
+            && bundle_member(&zpath).is_none()
+        {
+            zip::ZipArchive::new(File::open(zpath)?)?
+                .by_index(0)?
+                .size()
+        } else {
+            std::io::copy(&mut open_archived_file(zpath)?, &mut std::io::sink())?
+        };
+
+        Ok(RemoteMetadata {
+            size,
+            etag: None,
+            last_modified: modified.map(|t| DateTime::<Utc>::from(t).naive_utc()),
+        })
+    }
+
+    fn retrieve_static_file(
+        &self,
+        sat: Satellite,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let pth = self
+            .root()
+            .join(STATIC_DIR)
+            .join(sat.short_name())
+            .join(static_file_path(remote_path)?);
+
+        Ok(std::fs::read(pth)?)
+    }
+
+    fn retrieve_url(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.remote().retrieve_url(url)
+    }
+
+    fn max_downloads(&self) -> usize {
+        self.remote().max_downloads()
+    }
+}
+
+/// Copy a file as it was originally downloaded, whether it was stored as is or compressed.
+fn copy_archived_file(pth: &Path, writer: &mut dyn Write) -> Result<u64, Box<dyn Error>> {
+    let stored = find_stored(pth).ok_or_else(|| GoesArchError::new("File not in archive."))?;
+
+    Ok(std::io::copy(&mut open_archived_file(stored)?, writer)?)
+}
@@ -0,0 +1,442 @@
+use crate::{
+    archive::{Archive, DayOfDirs, ListedDir, Retrieved},
+    filename::{scanned_within, split_superseded},
+    filter::FileFilter,
+    listing::ListedFile,
+    product::Product,
+    remote::{ConditionalFile, RemoteArchive, RemoteMetadata},
+    report::RetrievalReport,
+    satellite::Satellite,
+};
+use chrono::naive::{NaiveDate, NaiveDateTime};
+use std::{error::Error, future::Future, io::Write, sync::Arc};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+/// The async counterpart of `RemoteArchive`, for embedding in async services.
+pub trait AsyncRemoteArchive: Clone + Send + Sync {
+    /// List the files available for the hour, skipping any that don't pass the filter.
+    fn retrieve_remote_filenames_async(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> impl Future<Output = Result<Vec<String>, Box<dyn Error + Send + Sync>>> + Send;
+
+    fn retrieve_remote_file_async(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> impl Future<Output = Result<Vec<u8>, Box<dyn Error + Send + Sync>>> + Send;
+}
+
+/// Use any blocking remote from async code by running its requests on tokio's blocking threads.
+#[derive(Debug, Clone)]
+pub struct SpawnBlocking<R>(pub R);
+
+impl<R> AsyncRemoteArchive for SpawnBlocking<R>
+where
+    R: RemoteArchive + Sync + 'static,
+{
+    fn retrieve_remote_filenames_async(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> impl Future<Output = Result<Vec<String>, Box<dyn Error + Send + Sync>>> + Send {
+        let remote = self.0.clone();
+        let filter = filter.clone();
+
+        blocking(move || remote.retrieve_remote_filenames(sat, prod, valid_hour, &filter))
+    }
+
+    fn retrieve_remote_file_async(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> impl Future<Output = Result<Vec<u8>, Box<dyn Error + Send + Sync>>> + Send {
+        let remote = self.0.clone();
+        let remote_path = remote_path.to_owned();
+
+        blocking(move || remote.retrieve_remote_file(sat, prod, valid_hour, &remote_path))
+    }
+}
+
+impl<R> RemoteArchive for SpawnBlocking<R>
+where
+    R: RemoteArchive,
+{
+    fn connect(num_max_downloads: usize) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        Ok(SpawnBlocking(R::connect(num_max_downloads)?))
+    }
+
+    fn retrieve_remote_filenames(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        self.0
+            .retrieve_remote_filenames(sat, prod, valid_hour, filter)
+    }
+
+    fn retrieve_remote_filenames_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, String)>, Box<dyn Error>> {
+        self.0
+            .retrieve_remote_filenames_for_day(sat, prod, day, filter)
+    }
+
+    fn lists_whole_days(&self) -> bool {
+        self.0.lists_whole_days()
+    }
+
+    fn retrieve_remote_listing(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<ListedFile>, Box<dyn Error>> {
+        self.0
+            .retrieve_remote_listing(sat, prod, valid_hour, filter)
+    }
+
+    fn retrieve_remote_listing_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, ListedFile)>, Box<dyn Error>> {
+        self.0
+            .retrieve_remote_listing_for_day(sat, prod, day, filter)
+    }
+
+    fn retrieve_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.0
+            .retrieve_remote_file(sat, prod, valid_hour, remote_path)
+    }
+
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        self.0
+            .retrieve_remote_file_to(sat, prod, valid_hour, remote_path, writer)
+    }
+
+    fn retrieve_remote_range(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.0
+            .retrieve_remote_range(sat, prod, valid_hour, remote_path, start, end)
+    }
+
+    fn retrieve_remote_file_if_changed(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        etag: &str,
+    ) -> Result<ConditionalFile, Box<dyn Error>> {
+        self.0
+            .retrieve_remote_file_if_changed(sat, prod, valid_hour, remote_path, etag)
+    }
+
+    fn retrieve_remote_metadata(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        self.0
+            .retrieve_remote_metadata(sat, prod, valid_hour, remote_path)
+    }
+
+    fn retrieve_static_file(
+        &self,
+        sat: Satellite,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.0.retrieve_static_file(sat, remote_path)
+    }
+
+    fn retrieve_url(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.0.retrieve_url(url)
+    }
+
+    fn max_downloads(&self) -> usize {
+        self.0.max_downloads()
+    }
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Same as `retrieve_paths_report`, but runs on the tokio runtime instead of dedicated
+    /// threads. Directories are checked, listed, downloaded, and finished by the same routines as
+    /// the blocking retrievals, on tokio's blocking threads, with up to `with_num_downloaders`
+    /// listings or directories in flight at a time.
+    pub async fn retrieve_paths_async(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<RetrievalReport, Box<dyn Error + Send + Sync>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let (to_failures, failures) = crossbeam_channel::unbounded();
+        let permits = Arc::new(Semaphore::new(self.num_downloaders()));
+
+        // Complete directories come back right away, the rest grouped by day for listing.
+        let (archive, feed_filter) = (self.clone(), filter.clone());
+        let (days, complete) = blocking(move || {
+            archive.prepare_retrieval()?;
+
+            let hours = Self::time_steps(sat.directory_interval(), start, end)
+                .map(|curr_time| (sat, prod, curr_time))
+                .collect();
+            let (to_lister, days) = crossbeam_channel::unbounded();
+            let (to_accumulator, complete) = crossbeam_channel::unbounded();
+            archive.feed_hours(hours, &feed_filter, to_lister, to_accumulator)?;
+
+            Ok((
+                days.into_iter().collect::<Vec<DayOfDirs>>(),
+                complete.into_iter().collect::<Vec<Retrieved>>(),
+            ))
+        })
+        .await?;
+
+        let mut listings = JoinSet::new();
+        for (sat, prod, dirs) in days {
+            let (archive, filter, to_failures) =
+                (self.clone(), filter.clone(), to_failures.clone());
+            let permits = Arc::clone(&permits);
+            listings.spawn(async move {
+                let _permit = permits.acquire_owned().await?;
+                blocking(move || {
+                    let (to_downloader, listed) = crossbeam_channel::unbounded();
+                    if dirs.len() > 1 && archive.remote().lists_whole_days() {
+                        let remote = archive.remote();
+                        Self::list_day(
+                            remote,
+                            sat,
+                            prod,
+                            dirs,
+                            &filter,
+                            &to_downloader,
+                            &to_failures,
+                        );
+                    } else {
+                        for (dir, curr_time) in dirs {
+                            let job = (dir, sat, prod, curr_time);
+                            Self::list_dir(
+                                archive.remote(),
+                                job,
+                                &filter,
+                                &to_downloader,
+                                &to_failures,
+                            );
+                        }
+                    }
+                    drop(to_downloader);
+
+                    Ok(listed.into_iter().collect::<Vec<ListedDir>>())
+                })
+                .await
+            });
+        }
+
+        let mut downloads = JoinSet::new();
+        while let Some(res) = listings.join_next().await {
+            for listed in res?? {
+                let (archive, filter, to_failures) =
+                    (self.clone(), filter.clone(), to_failures.clone());
+                let permits = Arc::clone(&permits);
+                downloads.spawn(async move {
+                    let _permit = permits.acquire_owned().await?;
+                    let (sat, prod) = (listed.1, listed.2);
+                    blocking(move || {
+                        let saved = archive.download_dir(listed, &filter, &to_failures);
+                        Ok(saved
+                            .into_iter()
+                            .map(|pth| (sat, prod, pth))
+                            .collect::<Vec<_>>())
+                    })
+                    .await
+                });
+            }
+        }
+
+        let mut retrieved = complete;
+        while let Some(res) = downloads.join_next().await {
+            retrieved.extend(res??);
+        }
+
+        let (archive, filter) = (self.clone(), filter.clone());
+        let mut paths = blocking(move || {
+            let mut paths = vec![];
+            for (sat, prod, pth) in retrieved {
+                archive.finish_path(sat, prod, pth, &filter, &to_failures, &mut |pth| {
+                    paths.push(pth)
+                });
+            }
+
+            Ok(paths)
+        })
+        .await?;
+
+        paths.retain(|pth| scanned_within(pth, start, end));
+        if !self.all_versions() {
+            paths = split_superseded(paths).0;
+        }
+
+        Ok(RetrievalReport {
+            paths,
+            failures: failures.into_iter().collect(),
+        })
+    }
+}
+
+/// Run blocking archive work on tokio's blocking threads.
+pub(crate) async fn blocking<T, F>(work: F) -> Result<T, Box<dyn Error + Send + Sync>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Box<dyn Error>> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || work().map_err(|err| err.to_string()))
+        .await?
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::compression::find_stored;
+    use crate::LocalDirRemote;
+    use chrono::NaiveDate;
+    use std::fs::{create_dir_all, write};
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+    }
+
+    fn hour(h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 6, 1)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn async_retrievals_finish_directories_like_blocking_ones() {
+        let root = std::env::temp_dir().join(format!("goes_arch_async_{}", std::process::id()));
+        let remote_dir = root.join("remote/noaa-goes16/ABI-L2-FDCC/2023/152/18");
+        create_dir_all(&remote_dir).unwrap();
+        let old = "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804087.nc";
+        let new = "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521809087.nc";
+        let next = "OR_ABI-L2-FDCC-M6_G16_s20231521806172_e20231521808545_c20231521809087.nc";
+        for fname in [old, new, next] {
+            write(remote_dir.join(fname), fname.as_bytes()).unwrap();
+        }
+
+        let plain = root.join("plain");
+        let arch = Archive::connect(
+            root.join("archive"),
+            LocalDirRemote::new(root.join("remote"), 1000),
+        )
+        .with_listing_snapshots()
+        .with_decompressed_copies(&plain);
+        let report = runtime()
+            .block_on(arch.retrieve_paths_async(
+                Satellite::GOES16,
+                Product::FDCC,
+                hour(18, 0),
+                hour(18, 5),
+                &FileFilter::default(),
+            ))
+            .unwrap();
+
+        assert!(report.is_complete());
+        assert_eq!(report.paths.len(), 1);
+        assert!(report.paths[0].starts_with(&plain));
+        assert!(report.paths[0].ends_with(new));
+        assert_eq!(std::fs::read(&report.paths[0]).unwrap(), new.as_bytes());
+
+        let local_dir = root.join("archive/G16/ABI-L2-FDCC/2023/152/18");
+        assert!(local_dir.join(crate::listing::LISTING_FNAME).is_file());
+        assert!(find_stored(&local_dir.join(next)).is_some());
+        assert!(find_stored(&local_dir.join(old)).is_none());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn blocking_remotes_are_usable_from_async_code() {
+        let root = std::env::temp_dir().join(format!("goes_arch_spawn_{}", std::process::id()));
+        let remote_dir = root.join("noaa-goes16/ABI-L2-FDCC/2023/152/18");
+        create_dir_all(&remote_dir).unwrap();
+        let fname = "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804087.nc";
+        write(remote_dir.join(fname), b"data").unwrap();
+
+        let remote = SpawnBlocking(LocalDirRemote::new(&root, 1));
+        let (names, data) = runtime().block_on(async {
+            let names = remote
+                .retrieve_remote_filenames_async(
+                    Satellite::GOES16,
+                    Product::FDCC,
+                    hour(18, 0),
+                    &FileFilter::default(),
+                )
+                .await
+                .unwrap();
+            let data = remote
+                .retrieve_remote_file_async(Satellite::GOES16, Product::FDCC, hour(18, 0), fname)
+                .await
+                .unwrap();
+            (names, data)
+        });
+
+        assert_eq!(names, vec![fname.to_owned()]);
+        assert_eq!(data, b"data");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
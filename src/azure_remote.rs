@@ -0,0 +1,376 @@
+use crate::{
+    error::GoesArchError,
+    filter::FileFilter,
+    http::{
+        get_if_none_match, get_range, head_metadata, parse_http_date, proxied_client, XmlElement,
+    },
+    listing::ListedFile,
+    product::Product,
+    remote::{
+        noaa_day_prefix, noaa_key_prefix, split_day_key, ConditionalFile, RemoteArchive,
+        RemoteMetadata,
+    },
+    satellite::Satellite,
+};
+use chrono::naive::{NaiveDate, NaiveDateTime};
+use reqwest::blocking::Client;
+use std::{error::Error, io::Write};
+
+/// The NOAA GOES mirror in Azure Blob Storage hosted by the Microsoft Planetary Computer. The
+/// containers are named and laid out the same as the NOAA buckets on AWS.
+#[derive(Debug, Clone)]
+pub struct AzureBlobNoaaGoes {
+    account: String,
+    client: Client,
+    num_max_downloads: usize,
+}
+
+impl AzureBlobNoaaGoes {
+    const DEFAULT_ACCOUNT: &'static str = "goeseuwest";
+
+    /// Connect to a storage account other than the Planetary Computer default.
+    pub fn connect_to_account(
+        account: &str,
+        num_max_downloads: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        Ok(AzureBlobNoaaGoes {
+            account: account.to_owned(),
+            client: Client::builder().build()?,
+            num_max_downloads,
+        })
+    }
+
+    /// Send all requests through this HTTP(S) proxy, e.g. `http://proxy.example.edu:3128`.
+    /// Without this the `HTTP_PROXY` and `HTTPS_PROXY` environment variables are used if they are
+    /// set.
+    pub fn with_proxy(mut self, proxy_url: &str) -> Result<Self, Box<dyn Error>> {
+        self.client = proxied_client(proxy_url)?;
+        Ok(self)
+    }
+
+    /// Use this client for all requests, e.g. one shared with other remotes so they pool their
+    /// connections, or one configured with custom TLS roots or keep-alive settings. It's a
+    /// `reqwest::blocking::Client` from reqwest 0.11.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn container_url(&self, sat: Satellite) -> String {
+        format!(
+            "https://{}.blob.core.windows.net/{}",
+            self.account,
+            sat.bucket_name()
+        )
+    }
+
+    fn get_blob(&self, url: &str) -> Result<reqwest::blocking::Response, Box<dyn Error>> {
+        let response = self.client.get(url).send()?;
+
+        if !response.status().is_success() {
+            log::error!("Error {} downloading {}", response.status(), url);
+            return Err(Box::new(GoesArchError::new("Download error")));
+        }
+
+        Ok(response)
+    }
+
+    /// All the blobs under the prefix with their sizes and ETags, following the paging markers.
+    /// The names are the full blob paths. An empty delimiter lists everything below the prefix
+    /// instead of a single level.
+    fn list_blobs(
+        &self,
+        sat: Satellite,
+        prefix: &str,
+        delimiter: &str,
+    ) -> Result<Vec<ListedFile>, Box<dyn Error>> {
+        let url = self.container_url(sat);
+
+        let mut blobs = vec![];
+        let mut marker = String::new();
+        loop {
+            let mut query = vec![
+                ("restype", "container"),
+                ("comp", "list"),
+                ("prefix", prefix),
+                ("marker", &marker),
+            ];
+            if !delimiter.is_empty() {
+                query.push(("delimiter", delimiter));
+            }
+
+            let response = self.client.get(&url).query(&query).send()?;
+
+            if !response.status().is_success() {
+                log::error!("Error {} listing {}{}", response.status(), url, prefix);
+                return Err(Box::new(GoesArchError::new("Listing error")));
+            }
+
+            let listing = XmlElement::parse(&response.text()?)?;
+
+            blobs.extend(listing_blobs(&listing));
+
+            // Results are paged, an empty marker means this was the last page.
+            match listing.child_text("NextMarker") {
+                Some(next) if !next.is_empty() => marker = next.to_owned(),
+                _ => break,
+            }
+        }
+
+        Ok(blobs)
+    }
+
+    fn blob_url(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        fname: &str,
+    ) -> String {
+        format!(
+            "{}/{}{}",
+            self.container_url(sat),
+            noaa_key_prefix(sat, prod, valid_hour),
+            fname
+        )
+    }
+}
+
+/// The blobs in a page of a `List Blobs` listing.
+fn listing_blobs(listing: &XmlElement) -> impl Iterator<Item = ListedFile> + '_ {
+    listing
+        .children("Blobs")
+        .flat_map(|blobs| blobs.children("Blob"))
+        .filter_map(|blob| {
+            let properties = blob.child("Properties");
+            let property = |name| properties.and_then(|props| props.child_text(name));
+
+            Some(ListedFile {
+                name: blob.child_text("Name")?.to_owned(),
+                size: property("Content-Length").and_then(|s| s.parse().ok()),
+                // Listings leave off the quotes the ETag header has.
+                etag: property("Etag").map(|etag| {
+                    if etag.starts_with('"') {
+                        etag.to_owned()
+                    } else {
+                        format!("\"{}\"", etag)
+                    }
+                }),
+                last_modified: property("Last-Modified").and_then(parse_http_date),
+            })
+        })
+}
+
+impl RemoteArchive for AzureBlobNoaaGoes {
+    fn connect(num_max_downloads: usize) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        Self::connect_to_account(Self::DEFAULT_ACCOUNT, num_max_downloads)
+    }
+
+    fn retrieve_remote_filenames(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self
+            .retrieve_remote_listing(sat, prod, valid_hour, filter)?
+            .into_iter()
+            .map(|file| file.name)
+            .collect())
+    }
+
+    fn retrieve_remote_filenames_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, String)>, Box<dyn Error>> {
+        Ok(self
+            .retrieve_remote_listing_for_day(sat, prod, day, filter)?
+            .into_iter()
+            .map(|(valid_time, file)| (valid_time, file.name))
+            .collect())
+    }
+
+    fn retrieve_remote_listing(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<ListedFile>, Box<dyn Error>> {
+        let prefix = noaa_key_prefix(sat, prod, valid_hour);
+
+        Ok(self
+            .list_blobs(sat, &prefix, "/")?
+            .into_iter()
+            .filter_map(|blob| {
+                let fname = blob.name.rsplit('/').next()?;
+                (!fname.is_empty() && filter.accepts(fname)).then(|| ListedFile {
+                    name: fname.to_owned(),
+                    ..blob
+                })
+            })
+            .collect())
+    }
+
+    fn retrieve_remote_listing_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, ListedFile)>, Box<dyn Error>> {
+        let day_prefix = noaa_day_prefix(sat, prod, day);
+
+        Ok(self
+            .list_blobs(sat, &day_prefix, "")?
+            .into_iter()
+            .filter_map(|blob| {
+                let (valid_time, fname) = split_day_key(day, blob.name.strip_prefix(&day_prefix)?)?;
+                if !filter.accepts(&fname) {
+                    return None;
+                }
+
+                Some((
+                    valid_time,
+                    ListedFile {
+                        name: fname,
+                        ..blob
+                    },
+                ))
+            })
+            .collect())
+    }
+
+    fn lists_whole_days(&self) -> bool {
+        true
+    }
+
+    fn retrieve_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = self.blob_url(sat, prod, valid_hour, remote_path);
+
+        Ok(self.get_blob(&url)?.bytes()?.to_vec())
+    }
+
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        let url = self.blob_url(sat, prod, valid_hour, remote_path);
+
+        Ok(self.get_blob(&url)?.copy_to(writer)?)
+    }
+
+    fn retrieve_remote_range(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = self.blob_url(sat, prod, valid_hour, remote_path);
+
+        get_range(&self.client, &url, start, end)
+    }
+
+    fn retrieve_remote_file_if_changed(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        etag: &str,
+    ) -> Result<ConditionalFile, Box<dyn Error>> {
+        let url = self.blob_url(sat, prod, valid_hour, remote_path);
+
+        get_if_none_match(&self.client, &url, etag)
+    }
+
+    fn retrieve_remote_metadata(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        head_metadata(
+            &self.client,
+            &self.blob_url(sat, prod, valid_hour, remote_path),
+        )
+    }
+
+    fn retrieve_static_file(
+        &self,
+        sat: Satellite,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = format!(
+            "{}/{}",
+            self.container_url(sat),
+            remote_path.trim_start_matches('/')
+        );
+
+        Ok(self.get_blob(&url)?.bytes()?.to_vec())
+    }
+
+    fn retrieve_url(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(self.get_blob(url)?.bytes()?.to_vec())
+    }
+
+    fn max_downloads(&self) -> usize {
+        self.num_max_downloads
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn listing_pages_are_parsed() {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults ServiceEndpoint="https://goeseuwest.blob.core.windows.net/" ContainerName="noaa-goes16">
+  <Prefix>ABI-L2-FDCC/2023/152/18/</Prefix>
+  <Blobs>
+    <Blob>
+      <Name>ABI-L2-FDCC/2023/152/18/OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804087.nc</Name>
+      <Properties>
+        <Last-Modified>Thu, 01 Jun 2023 18:05:11 GMT</Last-Modified>
+        <Etag>0x8DB62C4B1A7E5F2</Etag>
+        <Content-Length>372981</Content-Length>
+        <Content-Type>application/netcdf</Content-Type>
+      </Properties>
+    </Blob>
+  </Blobs>
+  <NextMarker />
+</EnumerationResults>"#;
+
+        let listing = XmlElement::parse(body).unwrap();
+        let blobs: Vec<ListedFile> = listing_blobs(&listing).collect();
+
+        assert_eq!(blobs.len(), 1);
+        assert!(blobs[0].name.ends_with("c20231521804087.nc"));
+        assert_eq!(blobs[0].size, Some(372981));
+        assert_eq!(blobs[0].etag.as_deref(), Some("\"0x8DB62C4B1A7E5F2\""));
+        assert!(blobs[0].last_modified.is_some());
+        assert_eq!(listing.child_text("NextMarker"), Some(""));
+    }
+}
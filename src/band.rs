@@ -0,0 +1,34 @@
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
+
+/// The 16 ABI channels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, IntoStaticStr)]
+pub enum AbiBand {
+    C01,
+    C02,
+    C03,
+    C04,
+    C05,
+    C06,
+    C07,
+    C08,
+    C09,
+    C10,
+    C11,
+    C12,
+    C13,
+    C14,
+    C15,
+    C16,
+}
+
+impl AbiBand {
+    /// Find the band in a file name like OR_ABI-L1b-RadC-M6C13_G16_s..., `None` if the file is not
+    /// from a band-resolved product.
+    pub fn from_file_name(fname: &str) -> Option<AbiBand> {
+        let product_field = fname.split('_').nth(1)?;
+        let mode_field = product_field.split('-').nth(3)?;
+        let band = &mode_field[mode_field.find('C')?..];
+
+        AbiBand::iter().find(|b| <&str>::from(*b) == band)
+    }
+}
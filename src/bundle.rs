@@ -0,0 +1,307 @@
+use crate::{
+    archive::{lock_dir, partial_path, write_atomic, Archive, HOUR_COMPLETE_FNAME},
+    compression::original_name,
+    filter::FileFilter,
+    marker::{sidecar_path, write_sidecar},
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use std::{
+    error::Error,
+    fs::{read_to_string, remove_file, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// The tarball a compacted directory keeps its data files in.
+pub(crate) const BUNDLE_FNAME: &str = "bundle.tar";
+
+/// Where each file is in the tarball of a compacted directory, one `name offset size` per line.
+pub(crate) const BUNDLE_INDEX_FNAME: &str = "bundle.idx";
+
+/// A file in the bundle of a directory, as listed in the bundle index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BundledFile {
+    /// The file name as stored, with any compression extension.
+    pub(crate) name: String,
+    offset: u64,
+    pub(crate) size: u64,
+}
+
+/// The files in the bundle of `dir`, or none if it isn't compacted.
+pub(crate) fn bundled_files(dir: &Path) -> Result<Vec<BundledFile>, Box<dyn Error>> {
+    let path = dir.join(BUNDLE_INDEX_FNAME);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let files = read_to_string(path)?
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            Some(BundledFile {
+                name: fields.next()?.to_owned(),
+                offset: fields.next()?.parse().ok()?,
+                size: fields.next()?.parse().ok()?,
+            })
+        })
+        .collect();
+
+    Ok(files)
+}
+
+/// The references to the files in the bundle of `dir` that aren't also extracted next to it.
+/// References are paths inside the tarball, e.g. `.../18/bundle.tar/OR_ABI...nc.zip`, which
+/// `open_archived_file` reads.
+pub(crate) fn bundled_paths(dir: &Path) -> Vec<PathBuf> {
+    let files = match bundled_files(dir) {
+        Ok(files) => files,
+        Err(err) => {
+            log::error!("Error reading bundle index in {:?} : {}", dir, err);
+            return vec![];
+        }
+    };
+
+    files
+        .into_iter()
+        .filter(|file| !dir.join(&file.name).exists())
+        .map(|file| dir.join(BUNDLE_FNAME).join(file.name))
+        .collect()
+}
+
+/// The directory and stored name of a reference to a bundled file, or `None` if the path is an
+/// ordinary file.
+pub(crate) fn bundle_member(path: &Path) -> Option<(&Path, String)> {
+    let bundle = path.parent()?;
+    if bundle.file_name()? != BUNDLE_FNAME {
+        return None;
+    }
+
+    Some((
+        bundle.parent()?,
+        path.file_name()?.to_string_lossy().to_string(),
+    ))
+}
+
+/// The reference to the bundled file for `pth`, the path of a file as downloaded, if the bundle
+/// of its directory has it stored any way.
+pub(crate) fn find_bundled(pth: &Path) -> Option<PathBuf> {
+    let dir = pth.parent()?;
+    let fname = pth.file_name()?.to_string_lossy();
+
+    bundled_files(dir)
+        .ok()?
+        .into_iter()
+        .find(|file| original_name(&file.name) == fname)
+        .map(|file| dir.join(BUNDLE_FNAME).join(file.name))
+}
+
+/// Read a bundled file as it's stored, still compressed, or `None` if `path` isn't a reference
+/// to one.
+pub(crate) fn read_bundled(path: &Path) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    let (dir, name) = match bundle_member(path) {
+        Some(member) => member,
+        None => return Ok(None),
+    };
+
+    let file = bundled_files(dir)?
+        .into_iter()
+        .find(|file| file.name == name)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Not in bundle"))?;
+
+    let mut f = File::open(dir.join(BUNDLE_FNAME))?;
+    f.seek(SeekFrom::Start(file.offset))?;
+
+    let mut data = vec![];
+    f.take(file.size).read_to_end(&mut data)?;
+    if data.len() as u64 != file.size {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Truncated bundle",
+        )));
+    }
+
+    Ok(Some(data))
+}
+
+/// Write a bundled file out next to the bundle, returning where it was written.
+pub(crate) fn extract_bundled(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let (dir, name) = bundle_member(path).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "Not a reference to a bundle")
+    })?;
+
+    let dest = dir.join(name);
+    if dest.exists() {
+        return Ok(dest);
+    }
+
+    let data = read_bundled(path)?.unwrap_or_default();
+    write_atomic(&dest, |mut f| {
+        f.write_all(&data)?;
+        Ok((f, ()))
+    })?;
+    log::debug!("Extracted {:?}", dest);
+
+    Ok(dest)
+}
+
+/// The file on disk for a stored path, extracting it first if it's a reference into a bundle.
+pub(crate) fn extracted(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    match bundle_member(path) {
+        Some(_) => extract_bundled(path),
+        None => Ok(path.to_owned()),
+    }
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Compact each complete directory of the product from `start` through `end` into a single
+    /// tarball with an index, `bundle.tar` and `bundle.idx`, to spare the file system millions of
+    /// small files. Retrievals extract the files they return next to the tarball, and compacting
+    /// again packs them away, along with any files added to the directory later. Returns the
+    /// number of files compacted.
+    pub fn compact(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<usize, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let mut num_files = 0;
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let dir = self.build_path(sat, prod, valid_time);
+            if !dir.join(HOUR_COMPLETE_FNAME).exists() {
+                continue;
+            }
+
+            let _lock = lock_dir(&dir)?;
+            let removed = Self::compact_dir(&dir)?;
+            num_files += removed.len();
+
+            #[cfg(feature = "sqlite")]
+            if let Some(index) = self.index() {
+                for pth in &removed {
+                    index.remove(pth)?;
+                }
+            }
+        }
+
+        log::info!("Compacted {} files of {:?} {:?}", num_files, sat, prod);
+
+        Ok(num_files)
+    }
+
+    /// Undo `compact` for the product from `start` through `end`, extracting the bundled files
+    /// and removing the bundles. Returns the number of files extracted.
+    pub fn unbundle(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<usize, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let mut num_files = 0;
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let dir = self.build_path(sat, prod, valid_time);
+            if !dir.join(BUNDLE_INDEX_FNAME).exists() {
+                continue;
+            }
+
+            let _lock = lock_dir(&dir)?;
+            for pth in bundled_paths(&dir) {
+                let stored = extract_bundled(&pth)?;
+                num_files += 1;
+
+                if self.checksum_files() {
+                    write_sidecar(&stored)?;
+                }
+
+                #[cfg(feature = "sqlite")]
+                if let Some(index) = self.index() {
+                    index.record(&stored, sat, prod)?;
+                }
+            }
+
+            remove_file(dir.join(BUNDLE_INDEX_FNAME))?;
+            remove_file(dir.join(BUNDLE_FNAME))?;
+        }
+
+        log::info!("Extracted {} files of {:?} {:?}", num_files, sat, prod);
+
+        Ok(num_files)
+    }
+
+    /// Pack every data file of a complete directory, bundled or not, into a fresh bundle and
+    /// remove the loose copies, returning the loose files removed. The directory should be
+    /// locked.
+    pub(crate) fn compact_dir(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let paths = Self::dir_files(dir, &FileFilter::default());
+        let loose: Vec<PathBuf> = paths
+            .iter()
+            .filter(|pth| bundle_member(pth).is_none())
+            .cloned()
+            .collect();
+        if loose.is_empty() {
+            return Ok(loose);
+        }
+
+        let bundle = dir.join(BUNDLE_FNAME);
+        let index = write_atomic(&bundle, |f| {
+            let mut tarball = tar::Builder::new(f);
+            for pth in &paths {
+                let fname = pth.file_name().unwrap_or_default();
+                match read_bundled(pth)? {
+                    Some(data) => {
+                        let mut header = tar::Header::new_gnu();
+                        header.set_size(data.len() as u64);
+                        header.set_mode(0o644);
+                        header.set_cksum();
+                        tarball.append_data(&mut header, fname, data.as_slice())?;
+                    }
+                    None => tarball.append_path_with_name(pth, fname)?,
+                }
+            }
+            let f = tarball.into_inner()?;
+
+            // Where each file landed, read back from the tarball before it's renamed into place.
+            let mut index = String::new();
+            let mut written = tar::Archive::new(File::open(partial_path(&bundle))?);
+            for entry in written.entries()? {
+                let entry = entry?;
+                index += &format!(
+                    "{} {} {}\n",
+                    entry.path()?.to_string_lossy(),
+                    entry.raw_file_position(),
+                    entry.size()
+                );
+            }
+
+            Ok((f, index))
+        })?;
+
+        write_atomic(&dir.join(BUNDLE_INDEX_FNAME), |mut f| {
+            f.write_all(index.as_bytes())?;
+            Ok((f, ()))
+        })?;
+
+        for pth in &loose {
+            remove_file(pth)?;
+
+            let sidecar = sidecar_path(pth);
+            if sidecar.exists() {
+                remove_file(sidecar)?;
+            }
+        }
+        log::debug!("Compacted {} files into {:?}", loose.len(), bundle);
+
+        Ok(loose)
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{
+    archive::write_atomic,
+    bundle::{bundle_member, find_bundled, read_bundled},
+    error::GoesArchError,
+    marker::check_sidecar,
+};
+use std::{
+    error::Error,
+    fs::{create_dir_all, File},
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// How downloaded files are compressed in the archive. Files already in the archive are read
+/// whichever way they were stored, so this can be changed at any time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// A zip file with a single entry, `.nc.zip`. The default.
+    #[default]
+    Zip,
+    /// A zstd stream at the given level, `.nc.zst`. Smaller and faster to read than zip. Needs
+    /// the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+/// The extensions of compressed files in the archive.
+const COMPRESSED_EXTENSIONS: &[&str] = &["zip", "zst"];
+
+impl Compression {
+    fn extension(&self) -> &'static str {
+        match self {
+            Compression::Zip => "zip",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(_) => "zst",
+        }
+    }
+
+    /// Where a downloaded file is stored in the archive after compression.
+    pub(crate) fn stored_path(&self, pth: &Path) -> PathBuf {
+        PathBuf::from(format!("{}.{}", pth.to_string_lossy(), self.extension()))
+    }
+
+    /// Compress everything written by `write` into `stored`, which only appears once it's
+    /// complete and synced to disk. Zip files have a single entry named for the original file.
+    pub(crate) fn write<T>(
+        &self,
+        pth: &Path,
+        stored: &Path,
+        write: impl FnOnce(&mut dyn Write) -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        write_atomic(stored, |f| match self {
+            Compression::Zip => {
+                let mut zipf = zip::ZipWriter::new(f);
+                let name = pth.file_name().unwrap_or(pth.as_os_str()).to_string_lossy();
+                zipf.start_file(name, zip::write::FileOptions::default())?;
+                let val = write(&mut zipf)?;
+                Ok((zipf.finish()?, val))
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd(level) => {
+                let mut enc = zstd::stream::write::Encoder::new(f, *level)?;
+                enc.include_checksum(true)?;
+                let val = write(&mut enc)?;
+                Ok((enc.finish()?, val))
+            }
+        })
+    }
+}
+
+/// The file for `pth` as it is stored in the archive, uncompressed or compressed any supported
+/// way, if there is one. Files in the bundle of a compacted directory are found as references
+/// into it.
+pub(crate) fn find_stored(pth: &Path) -> Option<PathBuf> {
+    if pth.exists() {
+        return Some(pth.to_owned());
+    }
+
+    COMPRESSED_EXTENSIONS
+        .iter()
+        .map(|ext| PathBuf::from(format!("{}.{}", pth.to_string_lossy(), ext)))
+        .find(|stored| stored.exists())
+        .or_else(|| find_bundled(pth))
+}
+
+/// Every path a file could be stored at, whether or not it exists.
+pub(crate) fn stored_paths(pth: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![pth.to_owned()];
+    paths.extend(
+        COMPRESSED_EXTENSIONS
+            .iter()
+            .map(|ext| PathBuf::from(format!("{}.{}", pth.to_string_lossy(), ext))),
+    );
+    paths
+}
+
+/// The name of a file as it was downloaded, without any compression extension.
+pub(crate) fn original_name(fname: &str) -> &str {
+    COMPRESSED_EXTENSIONS
+        .iter()
+        .find_map(|ext| fname.strip_suffix(ext)?.strip_suffix('.'))
+        .unwrap_or(fname)
+}
+
+/// Whether a file extension is one used for data files in the archive.
+pub(crate) fn is_data_extension(ext: &str) -> bool {
+    ext == "nc" || COMPRESSED_EXTENSIONS.contains(&ext)
+}
+
+/// Open a file from the archive, e.g. a path returned by `Archive::retrieve_paths`, reading it
+/// decompressed whether it was stored as is, zipped, or with zstd, and whether or not it was
+/// compacted into a bundle. Files with a checksum file are checked against it first.
+pub fn open_archived_file<P: AsRef<Path>>(path: P) -> Result<Box<dyn Read>, Box<dyn Error>> {
+    let path = path.as_ref();
+
+    if let Some(data) = read_bundled(path)? {
+        return decompress(Cursor::new(data), path);
+    }
+
+    if check_sidecar(path)? == Some(false) {
+        log::error!("Checksum mismatch reading {:?}", path);
+        return Err(Box::new(GoesArchError::new("Checksum mismatch")));
+    }
+
+    decompress(File::open(path)?, path)
+}
+
+/// Where the decompressed copy of a stored file goes under `dir`, mirroring its place under the
+/// deepest of `roots` it's in. `None` for files that aren't compressed.
+pub(crate) fn decompressed_path(stored: &Path, dir: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    let (parent, name) = match bundle_member(stored) {
+        Some(member) => member,
+        None => (
+            stored.parent()?,
+            stored.file_name()?.to_string_lossy().to_string(),
+        ),
+    };
+
+    let fname = original_name(&name);
+    if fname == name {
+        return None;
+    }
+
+    let root = roots
+        .iter()
+        .filter(|root| parent.starts_with(root))
+        .max_by_key(|root| root.components().count())?;
+
+    Some(dir.join(parent.strip_prefix(root).ok()?).join(fname))
+}
+
+/// Write a decompressed copy of a stored file to `dest`, unless one is already there.
+pub(crate) fn decompress_to(stored: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    if dest.exists() {
+        return Ok(());
+    }
+
+    if let Some(dir) = dest.parent() {
+        create_dir_all(dir)?;
+    }
+
+    let mut reader = open_archived_file(stored)?;
+    write_atomic(dest, |mut f| {
+        io::copy(&mut reader, &mut f)?;
+        Ok((f, ()))
+    })
+}
+
+/// Read a stored file decompressed, going by the extension of its path.
+fn decompress<R>(mut f: R, path: &Path) -> Result<Box<dyn Read>, Box<dyn Error>>
+where
+    R: Read + Seek + 'static,
+{
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("zip") => {
+            // Read the entry straight from the file, the zip crate's readers borrow the archive.
+            let (method, start, size, crc) = {
+                let mut zipf = zip::ZipArchive::new(&mut f)?;
+                let entry = zipf.by_index_raw(0)?;
+                (
+                    entry.compression(),
+                    entry.data_start(),
+                    entry.compressed_size(),
+                    entry.crc32(),
+                )
+            };
+
+            f.seek(SeekFrom::Start(start))?;
+            let data = f.take(size);
+
+            match method {
+                zip::CompressionMethod::Stored => Ok(Box::new(CrcReader::new(data, crc))),
+                zip::CompressionMethod::Deflated => Ok(Box::new(CrcReader::new(
+                    flate2::read::DeflateDecoder::new(data),
+                    crc,
+                ))),
+                _ => Err(Box::new(GoesArchError::new(&format!(
+                    "Unsupported zip compression {} in {:?}",
+                    method, path
+                )))),
+            }
+        }
+        Some("zst") => zstd_decoder(f),
+        _ => Ok(Box::new(f)),
+    }
+}
+
+/// Decompress a zstd stream.
+#[cfg(feature = "zstd")]
+pub(crate) fn zstd_decoder<R: Read + 'static>(f: R) -> Result<Box<dyn Read>, Box<dyn Error>> {
+    Ok(Box::new(zstd::stream::read::Decoder::new(f)?))
+}
+
+/// Decompress a zstd stream.
+#[cfg(not(feature = "zstd"))]
+pub(crate) fn zstd_decoder<R: Read + 'static>(_f: R) -> Result<Box<dyn Read>, Box<dyn Error>> {
+    Err(Box::new(GoesArchError::new(
+        "Reading zstd files needs the zstd feature.",
+    )))
+}
+
+/// Checks the CRC of a zip entry once it's read to the end.
+struct CrcReader<R> {
+    inner: R,
+    hasher: crc32fast::Hasher,
+    expected: u32,
+}
+
+impl<R: Read> CrcReader<R> {
+    fn new(inner: R, expected: u32) -> Self {
+        CrcReader {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+            expected,
+        }
+    }
+}
+
+impl<R: Read> Read for CrcReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+
+        if n == 0 && !buf.is_empty() && self.hasher.clone().finalize() != self.expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid zip checksum",
+            ));
+        }
+
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn zip_entries_stream_and_check_crc() {
+        let dir = std::env::temp_dir().join(format!("goes_arch_zip_{}", std::process::id()));
+        create_dir_all(&dir).unwrap();
+        let pth = dir.join("file.nc");
+        let stored = Compression::Zip.stored_path(&pth);
+        // Noisy enough that it doesn't compress away to nothing.
+        let data: Vec<u8> = (0..100_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+
+        Compression::Zip
+            .write(&pth, &stored, |w| Ok(w.write_all(&data)?))
+            .unwrap();
+
+        let mut read = vec![];
+        open_archived_file(&stored)
+            .unwrap()
+            .read_to_end(&mut read)
+            .unwrap();
+        assert_eq!(read, data);
+
+        // Flip a byte of the compressed data, past the local header.
+        let mut bytes = std::fs::read(&stored).unwrap();
+        bytes[100] ^= 0xff;
+        std::fs::write(&stored, bytes).unwrap();
+        let mut reader = open_archived_file(&stored).unwrap();
+        assert!(reader.read_to_end(&mut vec![]).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn decompressed_paths_mirror_the_archive() {
+        let roots = [PathBuf::from("/archive"), PathBuf::from("/archive/old")];
+        let dir = Path::new("/copies");
+
+        assert_eq!(
+            decompressed_path(Path::new("/archive/G16/FDCC/a.nc.zip"), dir, &roots),
+            Some(PathBuf::from("/copies/G16/FDCC/a.nc"))
+        );
+        assert_eq!(
+            decompressed_path(
+                Path::new("/archive/old/G16/bundle.tar/a.nc.zst"),
+                dir,
+                &roots
+            ),
+            Some(PathBuf::from("/copies/G16/a.nc"))
+        );
+        assert_eq!(
+            decompressed_path(Path::new("/archive/G16/a.nc"), dir, &roots),
+            None
+        );
+        assert_eq!(
+            decompressed_path(Path::new("/elsewhere/a.nc.zip"), dir, &roots),
+            None
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    archive::{write_atomic, Archive, HOUR_COMPLETE_FNAME},
+    bundle::read_bundled,
+    compression::{find_stored, original_name},
+    filename::split_superseded,
+    filter::FileFilter,
+    marker::write_sidecar,
+    product_set::ProductSet,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use std::{
+    error::Error,
+    fs::{create_dir_all, File},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Copy the files of the products in the set from `start` through `end` that pass the
+    /// filter into a new archive at `other_root`, e.g. to pull a small case study out of a large
+    /// archive. Only the newest version of each scan is copied, bundled files come out as plain
+    /// files, and nothing is downloaded. Complete directories get fresh completion markers in
+    /// the copy unless the filter held some of their files back. Files already in the copy are
+    /// skipped. Returns the number of files copied.
+    pub fn copy_to<P: Into<PathBuf>>(
+        &self,
+        other_root: P,
+        sat: Satellite,
+        set: &ProductSet,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<usize, Box<dyn Error>> {
+        let other = Archive::connect(other_root, self.remote().clone())
+            .with_compression(self.compression());
+        other.check_format()?;
+
+        let mut num_files = 0;
+        for &prod in set.products() {
+            let (start, end) = match Self::validate_dates(sat, prod, start, end) {
+                Ok(range) => range,
+                Err(err) => {
+                    log::warn!("Skipping {:?} in copy: {}", prod, err);
+                    continue;
+                }
+            };
+
+            for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+                if !filter.accepts_time(sat, prod, valid_time) {
+                    continue;
+                }
+
+                let dir = self.build_path(sat, prod, valid_time);
+                if !dir.is_dir() {
+                    continue;
+                }
+
+                let (paths, _) = split_superseded(Self::dir_files(&dir, filter));
+                if paths.is_empty() {
+                    continue;
+                }
+
+                let dest_dir = other.build_path(sat, prod, valid_time);
+                create_dir_all(&dest_dir)?;
+
+                for pth in paths {
+                    let fname = match pth.file_name() {
+                        Some(fname) => fname.to_string_lossy().to_string(),
+                        None => continue,
+                    };
+
+                    if find_stored(&dest_dir.join(original_name(&fname))).is_some() {
+                        continue;
+                    }
+                    let dest = dest_dir.join(&fname);
+
+                    let bundled = read_bundled(&pth)?;
+                    write_atomic(&dest, |mut f| {
+                        match bundled {
+                            Some(data) => f.write_all(&data)?,
+                            None => {
+                                io::copy(&mut File::open(&pth)?, &mut f)?;
+                            }
+                        }
+                        Ok((f, ()))
+                    })?;
+                    log::debug!("Copied {:?} to {:?}", pth, dest);
+                    num_files += 1;
+
+                    if self.checksum_files() {
+                        write_sidecar(&dest)?;
+                    }
+                }
+
+                if filter.is_unrestricted() && dir.join(HOUR_COMPLETE_FNAME).exists() {
+                    Self::mark_dir_as_complete(
+                        &dest_dir,
+                        sat,
+                        prod,
+                        valid_time,
+                        self.checksum_files(),
+                    )?;
+                }
+            }
+        }
+
+        log::info!("Copied {} files of {:?}", num_files, sat);
+
+        Ok(num_files)
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{
+    archive::{is_data_file, Archive, HOUR_COMPLETE_FNAME},
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use std::{error::Error, fs::read_dir};
+
+/// How much of a directory's data is in the archive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coverage {
+    /// Marked complete, or holding at least as many files as expected.
+    Complete,
+    /// Some files are present, but fewer than expected.
+    Partial { files: usize, expected: usize },
+    /// No files at all.
+    Missing,
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Report how complete each directory of the product from `start` through `end` is, from the
+    /// archive alone without touching the network. Directories are in order from most recent to
+    /// oldest.
+    pub fn coverage(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<(NaiveDateTime, Coverage)>, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let mut to_ret = vec![];
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let dir = self.build_path(sat, prod, valid_time);
+
+            let coverage = if !dir.is_dir() {
+                Coverage::Missing
+            } else if dir.join(HOUR_COMPLETE_FNAME).exists() {
+                Coverage::Complete
+            } else {
+                let mut files = 0;
+                for entry in read_dir(&dir)? {
+                    if is_data_file(&entry?.path()) {
+                        files += 1;
+                    }
+                }
+
+                let expected = Self::expected_files(sat, prod, valid_time).max(0) as usize;
+                match files {
+                    0 => Coverage::Missing,
+                    files if files >= expected => Coverage::Complete,
+                    files => Coverage::Partial { files, expected },
+                }
+            };
+
+            to_ret.push((valid_time, coverage));
+        }
+
+        Ok(to_ret)
+    }
+}
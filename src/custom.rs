@@ -0,0 +1,200 @@
+use crate::error::GoesArchError;
+use chrono::NaiveDateTime;
+use std::{error::Error, sync::Mutex};
+
+// Satellite and Product are Copy, so custom ones are handles to entries that live for the rest of
+// the process. The registries are only locked to register and look up names, each distinct name
+// is stored once and can't be changed afterwards.
+static SATELLITES: Mutex<Vec<&'static SatelliteEntry>> = Mutex::new(Vec::new());
+static PRODUCTS: Mutex<Vec<&'static ProductEntry>> = Mutex::new(Vec::new());
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct SatelliteEntry {
+    bucket: String,
+    earliest: NaiveDateTime,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct ProductEntry {
+    prefix: String,
+    max_per_hour: u32,
+    extensions: Vec<&'static str>,
+}
+
+/// A satellite backed by an arbitrary bucket, made with `Satellite::custom`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CustomSatellite(&'static SatelliteEntry);
+
+impl CustomSatellite {
+    /// Registering a bucket again returns the same satellite, or an error if the earliest date
+    /// differs.
+    pub(crate) fn register(bucket: &str, earliest: NaiveDateTime) -> Result<Self, GoesArchError> {
+        let mut sats = SATELLITES.lock().unwrap_or_else(|err| err.into_inner());
+
+        if let Some(&entry) = sats.iter().find(|entry| entry.bucket == bucket) {
+            if entry.earliest != earliest {
+                return Err(GoesArchError::new(&format!(
+                    "Custom satellite {} is already registered with data from {}",
+                    bucket, entry.earliest
+                )));
+            }
+            return Ok(CustomSatellite(entry));
+        }
+
+        let entry = Box::leak(Box::new(SatelliteEntry {
+            bucket: bucket.to_owned(),
+            earliest,
+        }));
+        sats.push(entry);
+        Ok(CustomSatellite(entry))
+    }
+
+    /// The custom satellite already registered for the bucket.
+    pub(crate) fn find(bucket: &str) -> Option<Self> {
+        let sats = SATELLITES.lock().unwrap_or_else(|err| err.into_inner());
+        sats.iter()
+            .find(|entry| entry.bucket == bucket)
+            .map(|&entry| CustomSatellite(entry))
+    }
+
+    /// Every custom satellite registered so far.
+    pub(crate) fn registered() -> impl Iterator<Item = Self> {
+        let sats = SATELLITES.lock().unwrap_or_else(|err| err.into_inner());
+        sats.iter()
+            .map(|&entry| CustomSatellite(entry))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn bucket(&self) -> &'static str {
+        &self.0.bucket
+    }
+
+    pub fn earliest(&self) -> NaiveDateTime {
+        self.0.earliest
+    }
+}
+
+/// A product prefix not built in to the crate, made with `Product::custom`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CustomProduct(&'static ProductEntry);
+
+impl CustomProduct {
+    /// Registering a prefix again returns the same product, or an error if the files per hour or
+    /// extensions differ.
+    pub(crate) fn register(
+        prefix: &str,
+        max_per_hour: u32,
+        extensions: &[&str],
+    ) -> Result<Self, Box<dyn Error>> {
+        if max_per_hour == 0 {
+            return Err(GoesArchError::new(&format!(
+                "Custom product {} must have at least one file per hour",
+                prefix
+            ))
+            .into());
+        }
+
+        if extensions.is_empty() {
+            return Err(GoesArchError::new(&format!(
+                "Custom product {} must have at least one file extension",
+                prefix
+            ))
+            .into());
+        }
+
+        let extensions: Vec<&str> = extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.'))
+            .collect();
+
+        let mut prods = PRODUCTS.lock().unwrap_or_else(|err| err.into_inner());
+
+        if let Some(&entry) = prods.iter().find(|entry| entry.prefix == prefix) {
+            if entry.max_per_hour != max_per_hour || entry.extensions != extensions {
+                return Err(GoesArchError::new(&format!(
+                    "Custom product {} is already registered with {} files per hour and \
+                     extensions {:?}",
+                    prefix, entry.max_per_hour, entry.extensions
+                ))
+                .into());
+            }
+            return Ok(CustomProduct(entry));
+        }
+
+        let entry = Box::leak(Box::new(ProductEntry {
+            prefix: prefix.to_owned(),
+            max_per_hour,
+            extensions: extensions
+                .into_iter()
+                .map(|ext| &*Box::leak(ext.to_owned().into_boxed_str()))
+                .collect(),
+        }));
+        prods.push(entry);
+        Ok(CustomProduct(entry))
+    }
+
+    /// Every custom product registered so far.
+    pub(crate) fn registered() -> impl Iterator<Item = Self> {
+        let prods = PRODUCTS.lock().unwrap_or_else(|err| err.into_inner());
+        prods
+            .iter()
+            .map(|&entry| CustomProduct(entry))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn prefix(&self) -> &'static str {
+        &self.0.prefix
+    }
+
+    pub fn max_per_hour(&self) -> u32 {
+        self.0.max_per_hour
+    }
+
+    pub fn extensions(&self) -> &'static [&'static str] {
+        &self.0.extensions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn custom_satellite_registers_once() {
+        let earliest = NaiveDateTime::parse_from_str("2024-01-01 00:00", "%Y-%m-%d %H:%M").unwrap();
+        let first = CustomSatellite::register("test-bucket-1", earliest).unwrap();
+        let second = CustomSatellite::register("test-bucket-1", earliest).unwrap();
+
+        assert!(std::ptr::eq(first.0, second.0));
+        assert_eq!(CustomSatellite::find("test-bucket-1"), Some(first));
+        assert_eq!(first.bucket(), "test-bucket-1");
+        assert_eq!(first.earliest(), earliest);
+        assert_eq!(CustomSatellite::find("test-bucket-2"), None);
+
+        assert!(CustomSatellite::register("test-bucket-1", NaiveDateTime::MIN).is_err());
+        assert_eq!(first.earliest(), earliest);
+    }
+
+    #[test]
+    fn custom_product_needs_files() {
+        assert!(CustomProduct::register("ABI-L2-TEST0", 0, &["nc"]).is_err());
+        assert!(CustomProduct::register("ABI-L2-TEST0", 1, &[]).is_err());
+    }
+
+    #[test]
+    fn custom_product_registers_once() {
+        let first = CustomProduct::register("ABI-L2-TEST1", 12, &["json"]).unwrap();
+        let second = CustomProduct::register("ABI-L2-TEST1", 12, &[".json"]).unwrap();
+
+        assert!(std::ptr::eq(first.0, second.0));
+        assert_eq!(first.prefix(), "ABI-L2-TEST1");
+        assert_eq!(first.max_per_hour(), 12);
+        assert_eq!(first.extensions(), &["json"]);
+
+        assert!(CustomProduct::register("ABI-L2-TEST1", 6, &["json"]).is_err());
+        assert!(CustomProduct::register("ABI-L2-TEST1", 12, &["nc"]).is_err());
+        assert_eq!(first.max_per_hour(), 12);
+    }
+}
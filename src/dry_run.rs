@@ -0,0 +1,129 @@
+use crate::{
+    archive::{Archive, HOUR_COMPLETE_FNAME},
+    compression::find_stored,
+    filename::split_superseded,
+    filter::FileFilter,
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use std::{collections::HashMap, error::Error};
+
+/// A file a retrieval would download.
+#[derive(Clone, Debug)]
+pub struct PlannedFile {
+    pub sat: Satellite,
+    pub prod: Product,
+    pub valid_time: NaiveDateTime,
+    pub fname: String,
+    /// Size in bytes, if the remote reports it.
+    pub size: Option<u64>,
+}
+
+/// What a retrieval would do, see `Archive::retrieve_paths_dry_run`.
+#[derive(Clone, Debug, Default)]
+pub struct DryRun {
+    /// The files that would be downloaded, most recent hour first.
+    pub files: Vec<PlannedFile>,
+    /// The number of files already in the archive that would be returned as they are.
+    pub num_archived: usize,
+}
+
+impl DryRun {
+    pub fn num_files(&self) -> usize {
+        self.files.len()
+    }
+
+    /// The total size of the files that would be downloaded. Files the remote didn't report a
+    /// size for are assumed to be the average size of those it did.
+    pub fn estimated_bytes(&self) -> u64 {
+        let sizes: Vec<u64> = self.files.iter().filter_map(|file| file.size).collect();
+        if sizes.is_empty() {
+            return 0;
+        }
+
+        let known: u64 = sizes.iter().sum();
+        let average = known / sizes.len() as u64;
+
+        known + average * (self.files.len() - sizes.len()) as u64
+    }
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Work out what `retrieve_paths_filtered` would download without downloading or writing
+    /// anything, not even directories or completion markers. The remote is listed as usual, sizes
+    /// come from the listing and a file's metadata is only requested when the listing has none.
+    pub fn retrieve_paths_dry_run(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<DryRun, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let mut dry_run = DryRun::default();
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            if !filter.accepts_time(sat, prod, valid_time) {
+                continue;
+            }
+
+            let dir = self.build_path(sat, prod, valid_time);
+            if dir.join(HOUR_COMPLETE_FNAME).exists() {
+                dry_run.num_archived += Self::dir_files(&dir, filter).len();
+                continue;
+            }
+
+            let listing = self
+                .remote()
+                .retrieve_remote_listing(sat, prod, valid_time, filter)?;
+            let sizes: HashMap<&str, Option<u64>> = listing
+                .iter()
+                .map(|file| (file.name.as_str(), file.size))
+                .collect();
+
+            let fnames: Vec<String> = listing.iter().map(|file| file.name.clone()).collect();
+            let fnames = match self.all_versions() {
+                true => fnames,
+                false => split_superseded(fnames).0,
+            };
+
+            for fname in fnames {
+                if find_stored(&dir.join(&fname)).is_some() {
+                    dry_run.num_archived += 1;
+                    continue;
+                }
+
+                let size = sizes.get(fname.as_str()).copied().flatten().or_else(|| {
+                    self.remote()
+                        .retrieve_remote_metadata(sat, prod, valid_time, &fname)
+                        .ok()
+                        .map(|meta| meta.size)
+                });
+
+                dry_run.files.push(PlannedFile {
+                    sat,
+                    prod,
+                    valid_time,
+                    fname,
+                    size,
+                });
+            }
+        }
+
+        log::info!(
+            "Dry run of {:?} {:?} would download {} files, about {} bytes",
+            sat,
+            prod,
+            dry_run.num_files(),
+            dry_run.estimated_bytes()
+        );
+
+        Ok(dry_run)
+    }
+}
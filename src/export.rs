@@ -0,0 +1,136 @@
+use crate::{
+    archive::{Archive, HOUR_COMPLETE_FNAME},
+    bundle::{bundle_member, read_bundled},
+    listing::LISTING_FNAME,
+    marker::sidecar_path,
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use std::{
+    error::Error,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// How an exported subset of the archive is packaged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A plain tarball, `.tar`.
+    #[default]
+    Tar,
+    /// A tarball compressed with zstd at the given level, `.tar.zst`. The data files are already
+    /// compressed, so this mostly saves space on the tar headers and markers. Needs the `zstd`
+    /// feature.
+    #[cfg(feature = "zstd")]
+    TarZstd(i32),
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Write the archived files of the product from `start` through `end` to `writer` as a
+    /// tarball, with the same layout they have in the archive, so it can be unpacked as an
+    /// archive of its own. Completion markers, listing snapshots, and checksum files go along
+    /// with the data. Only files already in the archive are exported. Returns the number of data
+    /// files written.
+    pub fn export_archive<W: Write>(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        writer: W,
+        format: ExportFormat,
+    ) -> Result<usize, Box<dyn Error>> {
+        let paths = self.local_paths(sat, prod, start, end)?;
+
+        match format {
+            ExportFormat::Tar => {
+                let mut tarball = tar::Builder::new(writer);
+                self.append_paths(&mut tarball, &paths)?;
+                tarball.into_inner()?.flush()?;
+            }
+            #[cfg(feature = "zstd")]
+            ExportFormat::TarZstd(level) => {
+                let mut tarball =
+                    tar::Builder::new(zstd::stream::write::Encoder::new(writer, level)?);
+                self.append_paths(&mut tarball, &paths)?;
+                tarball.into_inner()?.finish()?.flush()?;
+            }
+        }
+
+        log::info!("Exported {} files of {:?} {:?}", paths.len(), sat, prod);
+
+        Ok(paths.len())
+    }
+
+    fn append_paths<W: Write>(
+        &self,
+        tarball: &mut tar::Builder<W>,
+        paths: &[PathBuf],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut dirs: Vec<&Path> = vec![];
+        for pth in paths {
+            self.append_file(tarball, pth)?;
+
+            let sidecar = sidecar_path(pth);
+            if sidecar.exists() {
+                self.append_file(tarball, &sidecar)?;
+            }
+
+            let dir = match bundle_member(pth) {
+                Some((dir, _)) => Some(dir),
+                None => pth.parent(),
+            };
+            if let Some(dir) = dir {
+                if !dirs.contains(&dir) {
+                    dirs.push(dir);
+                }
+            }
+        }
+
+        for dir in dirs {
+            for fname in [HOUR_COMPLETE_FNAME, LISTING_FNAME] {
+                let pth = dir.join(fname);
+                if pth.exists() {
+                    self.append_file(tarball, &pth)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add a file to the tarball under its path relative to the root of its tier. Files from the
+    /// bundle of a compacted directory are added as if they were never bundled.
+    fn append_file<W: Write>(
+        &self,
+        tarball: &mut tar::Builder<W>,
+        pth: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        let unbundled = match bundle_member(pth) {
+            Some((dir, name)) => dir.join(name),
+            None => pth.to_owned(),
+        };
+        let relative = self
+            .roots()
+            .find_map(|root| unbundled.strip_prefix(root).ok())
+            .unwrap_or(&unbundled);
+
+        match read_bundled(pth)? {
+            Some(data) => {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                tarball.append_data(&mut header, relative, data.as_slice())?;
+            }
+            None => tarball.append_path_with_name(pth, relative)?,
+        }
+
+        Ok(())
+    }
+}
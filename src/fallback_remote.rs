@@ -0,0 +1,296 @@
+use crate::{
+    filter::FileFilter,
+    listing::ListedFile,
+    product::Product,
+    remote::{ConditionalFile, RemoteArchive, RemoteMetadata},
+    satellite::Satellite,
+};
+use chrono::naive::{NaiveDate, NaiveDateTime};
+use std::{error::Error, io::Write};
+
+/// Try the primary remote and fall back to the secondary when listing or downloading fails, e.g.
+/// S3 first and then Azure, so an outage at one provider doesn't stall archive updates.
+#[derive(Debug, Clone)]
+pub struct FallbackRemote<A, B> {
+    primary: A,
+    secondary: B,
+}
+
+impl<A, B> FallbackRemote<A, B>
+where
+    A: RemoteArchive,
+    B: RemoteArchive,
+{
+    pub fn new(primary: A, secondary: B) -> Self {
+        FallbackRemote { primary, secondary }
+    }
+}
+
+impl<A, B> RemoteArchive for FallbackRemote<A, B>
+where
+    A: RemoteArchive,
+    B: RemoteArchive,
+{
+    fn connect(num_max_downloads: usize) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        Ok(FallbackRemote {
+            primary: A::connect(num_max_downloads)?,
+            secondary: B::connect(num_max_downloads)?,
+        })
+    }
+
+    fn retrieve_remote_filenames(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        self.primary
+            .retrieve_remote_filenames(sat, prod, valid_hour, filter)
+            .or_else(|err| {
+                log::warn!(
+                    "Listing failed on primary remote, trying secondary: {}",
+                    err
+                );
+                self.secondary
+                    .retrieve_remote_filenames(sat, prod, valid_hour, filter)
+            })
+    }
+
+    fn retrieve_remote_filenames_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, String)>, Box<dyn Error>> {
+        self.primary
+            .retrieve_remote_filenames_for_day(sat, prod, day, filter)
+            .or_else(|err| {
+                log::warn!(
+                    "Listing failed on primary remote, trying secondary: {}",
+                    err
+                );
+                self.secondary
+                    .retrieve_remote_filenames_for_day(sat, prod, day, filter)
+            })
+    }
+
+    fn lists_whole_days(&self) -> bool {
+        self.primary.lists_whole_days()
+    }
+
+    fn retrieve_remote_listing(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<ListedFile>, Box<dyn Error>> {
+        self.primary
+            .retrieve_remote_listing(sat, prod, valid_hour, filter)
+            .or_else(|err| {
+                log::warn!(
+                    "Listing failed on primary remote, trying secondary: {}",
+                    err
+                );
+                self.secondary
+                    .retrieve_remote_listing(sat, prod, valid_hour, filter)
+            })
+    }
+
+    fn retrieve_remote_listing_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, ListedFile)>, Box<dyn Error>> {
+        self.primary
+            .retrieve_remote_listing_for_day(sat, prod, day, filter)
+            .or_else(|err| {
+                log::warn!(
+                    "Listing failed on primary remote, trying secondary: {}",
+                    err
+                );
+                self.secondary
+                    .retrieve_remote_listing_for_day(sat, prod, day, filter)
+            })
+    }
+
+    fn retrieve_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.primary
+            .retrieve_remote_file(sat, prod, valid_hour, remote_path)
+            .or_else(|err| {
+                log::warn!(
+                    "Download of {} failed on primary remote, trying secondary: {}",
+                    remote_path,
+                    err
+                );
+                self.secondary
+                    .retrieve_remote_file(sat, prod, valid_hour, remote_path)
+            })
+    }
+
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        let mut counter = CountingWriter {
+            inner: writer,
+            count: 0,
+        };
+
+        match self
+            .primary
+            .retrieve_remote_file_to(sat, prod, valid_hour, remote_path, &mut counter)
+        {
+            Ok(num_bytes) => Ok(num_bytes),
+            // Once part of the file is written the secondary can't start over.
+            Err(err) if counter.count > 0 => Err(err),
+            Err(err) => {
+                log::warn!(
+                    "Download of {} failed on primary remote, trying secondary: {}",
+                    remote_path,
+                    err
+                );
+                self.secondary.retrieve_remote_file_to(
+                    sat,
+                    prod,
+                    valid_hour,
+                    remote_path,
+                    counter.inner,
+                )
+            }
+        }
+    }
+
+    fn retrieve_remote_range(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.primary
+            .retrieve_remote_range(sat, prod, valid_hour, remote_path, start, end)
+            .or_else(|err| {
+                log::warn!(
+                    "Download of {} failed on primary remote, trying secondary: {}",
+                    remote_path,
+                    err
+                );
+                self.secondary
+                    .retrieve_remote_range(sat, prod, valid_hour, remote_path, start, end)
+            })
+    }
+
+    fn retrieve_remote_file_if_changed(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        etag: &str,
+    ) -> Result<ConditionalFile, Box<dyn Error>> {
+        self.primary
+            .retrieve_remote_file_if_changed(sat, prod, valid_hour, remote_path, etag)
+            .or_else(|err| {
+                log::warn!(
+                    "Download of {} failed on primary remote, trying secondary: {}",
+                    remote_path,
+                    err
+                );
+                self.secondary.retrieve_remote_file_if_changed(
+                    sat,
+                    prod,
+                    valid_hour,
+                    remote_path,
+                    etag,
+                )
+            })
+    }
+
+    fn retrieve_remote_metadata(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        self.primary
+            .retrieve_remote_metadata(sat, prod, valid_hour, remote_path)
+            .or_else(|err| {
+                log::warn!(
+                    "Metadata failed on primary remote, trying secondary: {}",
+                    err
+                );
+                self.secondary
+                    .retrieve_remote_metadata(sat, prod, valid_hour, remote_path)
+            })
+    }
+
+    fn retrieve_static_file(
+        &self,
+        sat: Satellite,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.primary
+            .retrieve_static_file(sat, remote_path)
+            .or_else(|err| {
+                log::warn!(
+                    "Download of {} failed on primary remote, trying secondary: {}",
+                    remote_path,
+                    err
+                );
+                self.secondary.retrieve_static_file(sat, remote_path)
+            })
+    }
+
+    fn retrieve_url(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.primary.retrieve_url(url).or_else(|err| {
+            log::warn!(
+                "Download of {} failed on primary remote, trying secondary: {}",
+                url,
+                err
+            );
+            self.secondary.retrieve_url(url)
+        })
+    }
+
+    fn max_downloads(&self) -> usize {
+        self.primary.max_downloads()
+    }
+}
+
+struct CountingWriter<'a> {
+    inner: &'a mut dyn Write,
+    count: u64,
+}
+
+impl Write for CountingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let num_bytes = self.inner.write(buf)?;
+        self.count += num_bytes as u64;
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
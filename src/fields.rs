@@ -0,0 +1,92 @@
+use crate::error::GoesArchError;
+use chrono::naive::NaiveDateTime;
+use serde_json::{Map, Value};
+use std::error::Error;
+
+// Times in the files the crate writes, e.g. manifests and completion markers, look like
+// 2023-06-01T18:00:00.
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+pub(crate) fn format_time(time: &NaiveDateTime) -> String {
+    time.format(DATE_FORMAT).to_string()
+}
+
+pub(crate) fn parse_time(text: &str) -> Result<NaiveDateTime, Box<dyn Error>> {
+    Ok(NaiveDateTime::parse_from_str(text, DATE_FORMAT)?)
+}
+
+/// Optional times are written as `null` in JSON and left empty in CSV.
+pub(crate) fn parse_optional_time(text: &str) -> Result<Option<NaiveDateTime>, Box<dyn Error>> {
+    match text {
+        "" => Ok(None),
+        text => parse_time(text).map(Some),
+    }
+}
+
+/// The fields of a JSON object, for the files the crate reads.
+pub(crate) fn object<'a>(
+    value: &'a Value,
+    what: &str,
+) -> Result<&'a Map<String, Value>, Box<dyn Error>> {
+    value
+        .as_object()
+        .ok_or_else(|| GoesArchError::new(&format!("Expected a JSON object for {}", what)).into())
+}
+
+/// A field that must be there, converted with `get`.
+pub(crate) fn required<'a, T>(
+    fields: &'a Map<String, Value>,
+    key: &str,
+    get: impl FnOnce(&'a Value) -> Option<T>,
+) -> Result<T, Box<dyn Error>> {
+    optional(fields, key, get)?
+        .ok_or_else(|| GoesArchError::new(&format!("Missing field: {}", key)).into())
+}
+
+/// A field that may be missing or `null`, converted with `get`.
+pub(crate) fn optional<'a, T>(
+    fields: &'a Map<String, Value>,
+    key: &str,
+    get: impl FnOnce(&'a Value) -> Option<T>,
+) -> Result<Option<T>, Box<dyn Error>> {
+    match fields.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => get(value)
+            .map(Some)
+            .ok_or_else(|| GoesArchError::new(&format!("Invalid field: {}", key)).into()),
+    }
+}
+
+/// A time field that may be missing, `null`, or empty.
+pub(crate) fn optional_time(
+    fields: &Map<String, Value>,
+    key: &str,
+) -> Result<Option<NaiveDateTime>, Box<dyn Error>> {
+    match optional(fields, key, Value::as_str)? {
+        Some(text) => parse_optional_time(text),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fields_are_checked() {
+        let value: Value =
+            serde_json::from_str(r#"{"time": "2023-06-01T18:00:00", "size": "10", "none": null}"#)
+                .unwrap();
+        let fields = object(&value, "test").unwrap();
+
+        let time = required(fields, "time", Value::as_str).unwrap();
+        assert_eq!(format_time(&parse_time(time).unwrap()), time);
+        assert!(optional_time(fields, "time").unwrap().is_some());
+        assert_eq!(optional_time(fields, "none").unwrap(), None);
+
+        assert!(required(fields, "size", Value::as_u64).is_err());
+        assert!(required(fields, "missing", Value::as_u64).is_err());
+        assert_eq!(optional(fields, "missing", Value::as_u64).unwrap(), None);
+        assert!(object(&Value::Null, "test").is_err());
+    }
+}
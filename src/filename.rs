@@ -0,0 +1,212 @@
+use crate::{product::Product, satellite::Satellite};
+use chrono::naive::{NaiveDate, NaiveDateTime};
+use std::{collections::HashMap, path::Path};
+use strum::IntoEnumIterator;
+
+// GOES file names look like OR_ABI-L2-FDCC-M6_G16_s20231521801172_e..._c..., with the scan start
+// as year, day of year, hour, minute, second, and tenths of a second.
+pub(crate) fn scan_start(fname: &str) -> Option<NaiveDateTime> {
+    time_field(fname, 's')
+}
+
+// The c field is when NOAA created the file. Now and then a scan is written more than once, and the
+// file created last supersedes the others.
+pub(crate) fn creation_time(fname: &str) -> Option<NaiveDateTime> {
+    time_field(fname, 'c')
+}
+
+fn time_field(fname: &str, tag: char) -> Option<NaiveDateTime> {
+    let field = fname
+        .split(['_', '.'])
+        .find(|f| f.len() == 15 && f.starts_with(tag))?;
+
+    let digit = |range: std::ops::Range<usize>| field.get(range)?.parse::<u32>().ok();
+
+    NaiveDate::from_yo_opt(digit(1..5)? as i32, digit(5..8)?)?.and_hms_opt(
+        digit(8..10)?,
+        digit(10..12)?,
+        digit(12..14)?,
+    )
+}
+
+// Everything before the e field names the scan a file holds: the product, scan mode, satellite,
+// and scan start.
+fn scan_key(fname: &str) -> Option<&str> {
+    scan_start(fname)?;
+    fname.find("_e").map(|end| &fname[..end])
+}
+
+/// Whether the scan in a file started from `start` through `end`. Files without a scan start in
+/// their name, like those of some custom products, always pass.
+pub(crate) fn scanned_within(pth: &Path, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+    pth.file_name()
+        .and_then(|fname| scan_start(&fname.to_string_lossy()))
+        .is_none_or(|scan| scan >= start && scan <= end)
+}
+
+/// Split paths, or remote file names, into the newest version of each scan and the superseded
+/// versions, keeping the order they came in. Files that don't look like GOES files are always
+/// kept.
+pub(crate) fn split_superseded<P: AsRef<Path>>(paths: Vec<P>) -> (Vec<P>, Vec<P>) {
+    let fname = |pth: &P| {
+        pth.as_ref()
+            .file_name()
+            .map(|fname| fname.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut newest: HashMap<String, (usize, Option<NaiveDateTime>)> = HashMap::new();
+    for (i, pth) in paths.iter().enumerate() {
+        let fname = fname(pth);
+        if let Some(key) = scan_key(&fname) {
+            let created = creation_time(&fname);
+            newest
+                .entry(key.to_owned())
+                .and_modify(|(idx, newest_created)| {
+                    if created > *newest_created {
+                        *idx = i;
+                        *newest_created = created;
+                    }
+                })
+                .or_insert((i, created));
+        }
+    }
+
+    let (kept, stale): (Vec<_>, Vec<_>) =
+        paths
+            .into_iter()
+            .enumerate()
+            .partition(|(i, pth)| match scan_key(&fname(pth)) {
+                Some(key) => newest[key].0 == *i,
+                None => true,
+            });
+
+    (
+        kept.into_iter().map(|(_, pth)| pth).collect(),
+        stale.into_iter().map(|(_, pth)| pth).collect(),
+    )
+}
+
+// The second and third fields of a GOES file name are the product with the scan mode, e.g.
+// ABI-L2-FDCM1-M6 or ABI-L1b-RadC-M6C01, and the satellite, e.g. G16. Custom products can't be
+// recognized. Himawari names are laid out differently, see `himawari_satellite_and_product`.
+pub(crate) fn satellite_and_product(fname: &str) -> Option<(Satellite, Product)> {
+    if fname.starts_with("HS_") {
+        return himawari_satellite_and_product(fname);
+    }
+
+    let mut fields = fname.split('_');
+    let prod_field = fields.nth(1)?;
+    let sat_field = fields.next()?;
+
+    let sat = Satellite::iter().find(|sat| sat.short_name() == sat_field)?;
+
+    let prod = Product::all()
+        .filter(|prod| {
+            prod_field
+                .strip_prefix(prod.prefix())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '1', '2']))
+        })
+        .max_by_key(|prod| prod.prefix().len())?;
+
+    Some((sat, prod))
+}
+
+// Himawari names are the satellite, the scan start date and time, the band, and the area, e.g.
+// HS_H08_20230601_1800_B01_FLDK_R10_S0110.DAT.bz2. The area is FLDK for the full disk, JP01
+// through JP04 for Japan, and R301 through R304 for the target area.
+fn himawari_satellite_and_product(fname: &str) -> Option<(Satellite, Product)> {
+    let fields: Vec<&str> = fname.split('_').collect();
+    let sat_field = fields.get(1)?;
+    let area = fields.get(5)?;
+
+    let sat = Satellite::iter().find(|sat| sat.short_name() == *sat_field)?;
+    let prod = match *area {
+        "FLDK" => Product::AhiFldk,
+        area if area.starts_with("JP") => Product::AhiJapan,
+        area if area.starts_with("R3") => Product::AhiTarget,
+        _ => return None,
+    };
+
+    Some((sat, prod))
+}
+
+/// Whether a file name ends with the extension, which may have more than one part, e.g. `nc.gz`.
+pub(crate) fn has_extension(fname: &str, ext: &str) -> bool {
+    fname
+        .strip_suffix(ext)
+        .is_some_and(|rest| rest.ends_with('.'))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FNAME: &str = "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804120.nc";
+
+    fn time(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn scan_start_from_file_name() {
+        assert_eq!(scan_start(FNAME), Some(time("2023-06-01 18:01:17")));
+        assert_eq!(creation_time(FNAME), Some(time("2023-06-01 18:04:12")));
+        assert_eq!(scan_start(&format!("{}.zip", FNAME)), scan_start(FNAME));
+
+        assert_eq!(scan_start("land_mask.nc"), None);
+        assert_eq!(
+            scan_start("OR_ABI-L2-FDCC-M6_G16_s20233991801172_e1_c1.nc"),
+            None
+        );
+    }
+
+    #[test]
+    fn split_superseded_keeps_newest_version() {
+        let old = "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804120.nc";
+        let new = "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521809000.nc.zip";
+        let other = "OR_ABI-L2-FDCC-M6_G16_s20231521806172_e20231521808545_c20231521809120.nc";
+
+        let (kept, stale) = split_superseded(vec![old, other, "land_mask.nc", new]);
+        assert_eq!(kept, vec![other, "land_mask.nc", new]);
+        assert_eq!(stale, vec![old]);
+    }
+
+    #[test]
+    fn satellite_and_product_from_file_name() {
+        let (sat, prod) = satellite_and_product(FNAME).unwrap();
+        assert_eq!(sat.short_name(), Satellite::GOES16.short_name());
+        assert_eq!(prod.prefix(), Product::FDCC.prefix());
+
+        // Mesoscale products carry the sector number after the prefix.
+        let meso = "OR_ABI-L2-FDCM1-M6_G18_s20231521801172_e20231521801230_c20231521801300.nc";
+        let (sat, prod) = satellite_and_product(meso).unwrap();
+        assert_eq!(sat.short_name(), Satellite::GOES18.short_name());
+        assert_eq!(prod.prefix(), Product::FDCM.prefix());
+
+        assert!(satellite_and_product("land_mask.nc").is_none());
+        assert!(satellite_and_product("OR_ABI-L2-FDCC-M6_G99_s20231521801172.nc").is_none());
+    }
+
+    #[test]
+    fn himawari_files_are_data_files() {
+        use crate::archive::is_data_file;
+        use std::path::Path;
+
+        let fname = "HS_H08_20230601_1800_B01_FLDK_R10_S0110.DAT.bz2";
+        let (sat, prod) = satellite_and_product(fname).unwrap();
+        assert_eq!(sat.short_name(), Satellite::HIMAWARI8.short_name());
+        assert_eq!(prod.prefix(), Product::AhiFldk.prefix());
+        assert_eq!(prod.extensions(), &["DAT.bz2"]);
+
+        assert!(is_data_file(Path::new(fname)));
+        assert!(is_data_file(Path::new(&format!("{}.zip", fname))));
+        assert!(!is_data_file(Path::new(&format!("{}.sha256", fname))));
+
+        let japan = "HS_H09_20230601_1802_B03_JP01_R05_S0101.DAT.bz2";
+        assert_eq!(
+            satellite_and_product(japan).unwrap().1.prefix(),
+            Product::AhiJapan.prefix()
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{
+    archive::is_data_file,
+    band::AbiBand,
+    compression::original_name,
+    filename::{has_extension, scan_start},
+    maturity::Maturity,
+    product::{Product, Sector},
+    satellite::Satellite,
+    scan_mode::ScanMode,
+};
+use chrono::NaiveDateTime;
+use std::{collections::HashSet, fmt, path::Path, sync::Arc};
+
+/// The two mesoscale domains. Mesoscale products interleave the files from both domains in the
+/// same hourly directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MesoSector {
+    M1,
+    M2,
+}
+
+/// A test of file names supplied by the user, see `FileFilter::matching`.
+#[derive(Clone)]
+struct FnamePredicate(Arc<dyn Fn(&str) -> bool + Send + Sync>);
+
+impl fmt::Debug for FnamePredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FnamePredicate")
+    }
+}
+
+/// Restricts which remote files are downloaded and which local files are returned.
+#[derive(Clone, Debug, Default)]
+pub struct FileFilter {
+    meso_sector: Option<MesoSector>,
+    bands: Option<Vec<AbiBand>>,
+    scan_modes: Option<Vec<ScanMode>>,
+    scan_times: Option<(NaiveDateTime, NaiveDateTime)>,
+    min_maturity: Option<Maturity>,
+    fnames: Option<HashSet<String>>,
+    extensions: Option<Vec<String>>,
+    predicates: Vec<FnamePredicate>,
+}
+
+impl FileFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keep files from a single mesoscale domain. This has no effect on files from other
+    /// sectors.
+    pub fn meso_sector(mut self, sector: MesoSector) -> Self {
+        self.meso_sector = Some(sector);
+        self
+    }
+
+    /// Only keep files from these bands. This has no effect on products that are not band
+    /// resolved.
+    pub fn bands(mut self, bands: &[AbiBand]) -> Self {
+        self.bands = Some(bands.to_vec());
+        self
+    }
+
+    /// Only keep ABI files scanned in one of these modes. This has no effect on files from other
+    /// instruments.
+    pub fn scan_modes(mut self, modes: &[ScanMode]) -> Self {
+        self.scan_modes = Some(modes.to_vec());
+        self
+    }
+
+    /// Only keep files whose scans started from `start` through `end`. Retrievals already trim
+    /// what they return to the requested window, this also skips downloading the rest of the
+    /// directories at either end of it, which then can't be marked complete.
+    pub fn scan_times(mut self, start: NaiveDateTime, end: NaiveDateTime) -> Self {
+        self.scan_times = Some((start, end));
+        self
+    }
+
+    /// Skip hours when the product had not reached this maturity level.
+    pub fn min_maturity(mut self, maturity: Maturity) -> Self {
+        self.min_maturity = Some(maturity);
+        self
+    }
+
+    /// Only keep files with these extensions, ignoring any compression added by the archive, e.g.
+    /// `&["nc", "nc.gz"]`. Without this, files with the extensions of their product are returned
+    /// from the archive, see `Product::extensions`.
+    pub fn extensions(mut self, extensions: &[&str]) -> Self {
+        self.extensions = Some(
+            extensions
+                .iter()
+                .map(|ext| ext.trim_start_matches('.').to_owned())
+                .collect(),
+        );
+        self
+    }
+
+    /// Only keep files whose names pass `predicate`, for restricting by any part of the name
+    /// there isn't a dedicated filter for, e.g. `|fname| fname.contains("-M6_")` for scan mode 6.
+    /// The predicate sees the remote file name, without any compression added by the archive.
+    /// Each call adds another predicate that files must pass.
+    pub fn matching<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.push(FnamePredicate(Arc::new(predicate)));
+        self
+    }
+
+    /// Only keep files with exactly these names, e.g. the files listed in a manifest.
+    pub(crate) fn only_files<I: IntoIterator<Item = String>>(mut self, fnames: I) -> Self {
+        self.fnames = Some(fnames.into_iter().collect());
+        self
+    }
+
+    pub fn accepts_time(&self, sat: Satellite, prod: Product, valid_time: NaiveDateTime) -> bool {
+        self.min_maturity
+            .is_none_or(|min| sat.maturity(prod, valid_time) >= min)
+    }
+
+    pub fn accepts(&self, fname: &str) -> bool {
+        if let Some(sector) = self.meso_sector {
+            if meso_sector_of(fname).is_some_and(|s| s != sector) {
+                return false;
+            }
+        }
+
+        if let Some(ref bands) = self.bands {
+            if AbiBand::from_file_name(fname).is_some_and(|b| !bands.contains(&b)) {
+                return false;
+            }
+        }
+
+        if let Some(ref modes) = self.scan_modes {
+            if ScanMode::from_file_name(fname).is_some_and(|m| !modes.contains(&m)) {
+                return false;
+            }
+        }
+
+        if let Some((start, end)) = self.scan_times {
+            if scan_start(fname).is_some_and(|scan| scan < start || scan > end) {
+                return false;
+            }
+        }
+
+        if let Some(ref fnames) = self.fnames {
+            // Archived files may have been compressed.
+            if !fnames.contains(original_name(fname)) {
+                return false;
+            }
+        }
+
+        if self.extensions.is_some() && !self.is_data_file(fname) {
+            return false;
+        }
+
+        let remote_fname = original_name(fname);
+        if !self.predicates.iter().all(|pred| (pred.0)(remote_fname)) {
+            return false;
+        }
+
+        true
+    }
+
+    /// Whether a file in the archive holds data, as opposed to a marker, lock, or checksum file.
+    pub(crate) fn is_data_file(&self, fname: &str) -> bool {
+        match self.extensions {
+            Some(ref extensions) => {
+                let fname = original_name(fname);
+                extensions.iter().any(|ext| has_extension(fname, ext))
+            }
+            None => is_data_file(Path::new(fname)),
+        }
+    }
+
+    /// True if no files are filtered out, in which case an hour can be marked as complete.
+    pub(crate) fn is_unrestricted(&self) -> bool {
+        self.meso_sector.is_none()
+            && self.bands.is_none()
+            && self.scan_modes.is_none()
+            && self.scan_times.is_none()
+            && self.fnames.is_none()
+            && self.extensions.is_none()
+            && self.predicates.is_empty()
+    }
+
+    /// Scale the number of files expected in an hour to what will pass this filter.
+    pub(crate) fn expected_files(&self, prod: Product, num_files: i32) -> i32 {
+        let mut num_files = num_files;
+
+        if self.meso_sector.is_some() && prod.sector() == Some(Sector::Meso) {
+            num_files /= 2;
+        }
+
+        if let Some(ref bands) = self.bands {
+            let prod_bands = prod.bands();
+            if !prod_bands.is_empty() {
+                let num_kept = prod_bands.iter().filter(|b| bands.contains(b)).count() as i32;
+                num_files = num_files * num_kept / prod_bands.len() as i32;
+            }
+        }
+
+        num_files
+    }
+}
+
+// File names look like OR_ABI-L2-FDCM1-M6_G16_s20231231200000_e..., the domain is the digit after
+// the M at the end of the product name.
+fn meso_sector_of(fname: &str) -> Option<MesoSector> {
+    let product_field = fname.split('_').nth(1)?;
+    let name = product_field.split('-').nth(2)?;
+
+    if name.ends_with("M1") {
+        Some(MesoSector::M1)
+    } else if name.ends_with("M2") {
+        Some(MesoSector::M2)
+    } else {
+        None
+    }
+}
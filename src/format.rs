@@ -0,0 +1,196 @@
+use crate::{
+    archive::{write_atomic, Archive},
+    error::GoesArchError,
+    migrate::ArchiveLayout,
+    remote::RemoteArchive,
+};
+use std::{
+    error::Error,
+    fs::{create_dir_all, read_to_string},
+    io::Write,
+};
+use toml::{Table, Value};
+
+/// The file in the archive root describing how the archive is laid out.
+pub(crate) const FORMAT_FNAME: &str = "archive.toml";
+
+/// The version of the archive structure written by this crate. Version 1 archives had no format
+/// file and plain timestamp completion markers, which are still read.
+const FORMAT_VERSION: u32 = 2;
+
+/// The version of the completion markers written by this crate.
+const MARKER_FORMAT: u32 = 1;
+
+/// What the format file of an archive records. Compression and checksum files are options of
+/// each connection, stored files are read whichever way they were written, so they aren't part
+/// of the format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ArchiveFormat {
+    version: u32,
+    layout: ArchiveLayout,
+    marker_format: u32,
+}
+
+impl ArchiveFormat {
+    fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let table: Table = text.parse()?;
+        let invalid = || GoesArchError::new("Invalid archive format file.");
+        let int = |key: &str| match table.get(key) {
+            Some(Value::Integer(v)) => u32::try_from(*v).ok(),
+            _ => None,
+        };
+
+        let layout = match table.get("layout").map(Value::as_str) {
+            Some(Some("native")) | None => ArchiveLayout::Native,
+            Some(Some("noaa_bucket")) => ArchiveLayout::NoaaBucket,
+            Some(Some("flat")) => ArchiveLayout::Flat,
+            Some(_) => return Err(Box::new(invalid())),
+        };
+
+        Ok(ArchiveFormat {
+            version: int("format_version").ok_or_else(invalid)?,
+            layout,
+            marker_format: int("marker_format").unwrap_or(0),
+        })
+    }
+
+    fn to_toml(&self) -> String {
+        let layout = match self.layout {
+            ArchiveLayout::Native => "native",
+            ArchiveLayout::NoaaBucket => "noaa_bucket",
+            ArchiveLayout::Flat => "flat",
+        };
+
+        let mut table = Table::new();
+        table.insert("format_version".into(), i64::from(self.version).into());
+        table.insert("layout".into(), layout.into());
+        table.insert("marker_format".into(), i64::from(self.marker_format).into());
+
+        format!(
+            "# Written by goes_arch, describes how this archive is laid out.\n{}",
+            table
+        )
+    }
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Check the format file in the archive root against what this version of the crate
+    /// writes, creating it if the archive doesn't have one yet and upgrading it if the archive
+    /// is older. Archives written by a newer version of the crate, or in another layout, are
+    /// refused rather than risk mixing structures, including roots without a format file that
+    /// hold data in another layout. This runs before every retrieval, archives in another layout
+    /// can still be connected to and moved over with `migrate_layout`.
+    pub fn check_format(&self) -> Result<(), Box<dyn Error>> {
+        let path = self.root().join(FORMAT_FNAME);
+
+        let found = if path.exists() {
+            ArchiveFormat::parse(&read_to_string(&path)?)?
+        } else {
+            ArchiveFormat {
+                version: 0,
+                layout: ArchiveLayout::detect(self.root())?,
+                marker_format: 0,
+            }
+        };
+
+        if found.version > FORMAT_VERSION || found.marker_format > MARKER_FORMAT {
+            log::error!(
+                "Archive at {:?} is format {}, newer than the supported {}",
+                self.root(),
+                found.version,
+                FORMAT_VERSION
+            );
+            return Err(Box::new(GoesArchError::new(
+                "Archive was written by a newer version of goes_arch.",
+            )));
+        }
+
+        if found.layout != ArchiveLayout::Native {
+            log::error!(
+                "Archive at {:?} is in the {:?} layout",
+                self.root(),
+                found.layout
+            );
+            return Err(Box::new(GoesArchError::new(
+                "Archive is not in the native layout, see Archive::migrate_layout.",
+            )));
+        }
+
+        if found == current_format(found.layout) {
+            return Ok(());
+        }
+
+        match path.exists() {
+            true => log::info!("Upgrading archive format file at {:?}", path),
+            false => log::info!("Creating archive format file at {:?}", path),
+        }
+
+        self.write_format(ArchiveLayout::Native)
+    }
+
+    /// Record the archive as being in `layout`.
+    pub(crate) fn write_format(&self, layout: ArchiveLayout) -> Result<(), Box<dyn Error>> {
+        create_dir_all(self.root())?;
+
+        let text = current_format(layout).to_toml();
+        write_atomic(&self.root().join(FORMAT_FNAME), |mut f| {
+            f.write_all(text.as_bytes())?;
+            Ok((f, ()))
+        })
+    }
+}
+
+fn current_format(layout: ArchiveLayout) -> ArchiveFormat {
+    ArchiveFormat {
+        version: FORMAT_VERSION,
+        layout,
+        marker_format: MARKER_FORMAT,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LocalDirRemote;
+
+    #[test]
+    fn connection_options_are_not_part_of_the_format() {
+        let text = "format_version = 2\n\
+                    layout = \"native\"\n\
+                    marker_format = 1\n\
+                    compression = \"zstd:3\"\n\
+                    checksum_files = true\n";
+
+        assert_eq!(
+            ArchiveFormat::parse(text).unwrap(),
+            current_format(ArchiveLayout::Native)
+        );
+        assert_eq!(
+            ArchiveFormat::parse(&current_format(ArchiveLayout::Flat).to_toml()).unwrap(),
+            current_format(ArchiveLayout::Flat)
+        );
+    }
+
+    #[test]
+    fn other_layouts_connect_but_are_refused_for_retrieval() {
+        let root = std::env::temp_dir().join(format!("goes_arch_format_{}", std::process::id()));
+        create_dir_all(root.join("noaa-goes16")).unwrap();
+
+        let arch = Archive::connect(&root, LocalDirRemote::new(&root, 1));
+        assert!(!root.join(FORMAT_FNAME).exists());
+        assert!(arch.check_format().is_err());
+        assert!(!root.join(FORMAT_FNAME).exists());
+
+        std::fs::remove_dir(root.join("noaa-goes16")).unwrap();
+        arch.check_format().unwrap();
+        assert_eq!(
+            ArchiveFormat::parse(&read_to_string(root.join(FORMAT_FNAME)).unwrap()).unwrap(),
+            current_format(ArchiveLayout::Native)
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
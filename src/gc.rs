@@ -0,0 +1,199 @@
+use crate::{
+    archive::{is_data_file, try_lock_dir, Archive, HOUR_COMPLETE_FNAME, HOUR_LOCK_FNAME},
+    bundle::{BUNDLE_FNAME, BUNDLE_INDEX_FNAME},
+    listing::LISTING_FNAME,
+    marker::SIDECAR_EXT,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use std::{
+    error::Error,
+    fs::{read_dir, remove_dir, remove_file},
+    path::Path,
+};
+
+/// The files other than data files that belong in an archive directory.
+const BOOKKEEPING_FNAMES: &[&str] = &[
+    HOUR_COMPLETE_FNAME,
+    HOUR_LOCK_FNAME,
+    LISTING_FNAME,
+    BUNDLE_FNAME,
+    BUNDLE_INDEX_FNAME,
+];
+
+/// What a garbage collection pass removed from the archive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GarbageReport {
+    pub files_removed: usize,
+    pub dirs_removed: usize,
+    /// Total size of the removed files.
+    pub bytes_reclaimed: u64,
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Clean up after interrupted downloads and other tools: remove empty data files, temporary
+    /// files from saves that never finished, files that aren't data files or their checksum
+    /// files, and the directories left empty. Directories being downloaded into by another
+    /// process are skipped. Completion markers of directories that lost an empty data file are
+    /// removed so they're downloaded again.
+    pub fn collect_garbage(&self) -> Result<GarbageReport, Box<dyn Error>> {
+        let mut report = GarbageReport::default();
+
+        for root in self.roots() {
+            for sat in Satellite::all() {
+                let sat_dir = root.join(sat.short_name());
+                if sat_dir.is_dir() && self.collect_dir(&sat_dir, &mut report)? {
+                    remove_dir(&sat_dir)?;
+                    report.dirs_removed += 1;
+                }
+            }
+        }
+
+        log::info!(
+            "Removed {} files and {} directories, reclaiming {} bytes",
+            report.files_removed,
+            report.dirs_removed,
+            report.bytes_reclaimed
+        );
+
+        Ok(report)
+    }
+
+    /// Collect the garbage below `dir`, returning whether it ended up empty.
+    fn collect_dir(&self, dir: &Path, report: &mut GarbageReport) -> Result<bool, Box<dyn Error>> {
+        // Hold the lock while cleaning so a download in progress doesn't lose its files, taking
+        // it the same way downloads do. Directories with nothing but other directories in them
+        // have nothing to lose.
+        let has_files = read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .any(|entry| entry.file_type().is_ok_and(|kind| !kind.is_dir()));
+        let lock = match has_files {
+            true => match try_lock_dir(dir)? {
+                Some(lock) => Some(lock),
+                None => {
+                    log::debug!("Skipping garbage collection of locked {:?}", dir);
+                    return Ok(false);
+                }
+            },
+            false => None,
+        };
+
+        let mut lost_data = false;
+        for entry in read_dir(dir)? {
+            let entry = entry?;
+            let pth = entry.path();
+            let meta = entry.metadata()?;
+
+            if meta.is_dir() {
+                if self.collect_dir(&pth, report)? {
+                    remove_dir(&pth)?;
+                    report.dirs_removed += 1;
+                }
+                continue;
+            }
+
+            let fname = entry.file_name();
+            let garbage = if is_data_file(&pth) {
+                lost_data |= meta.len() == 0;
+                meta.len() == 0
+            } else if pth.extension().is_some_and(|ext| ext == SIDECAR_EXT) {
+                // Checksum files without their data file.
+                !pth.with_extension("").exists() || pth.with_extension("").metadata()?.len() == 0
+            } else {
+                !BOOKKEEPING_FNAMES.iter().any(|keep| fname == *keep)
+            };
+
+            if garbage {
+                remove_file(&pth)?;
+                log::debug!("Removed garbage {:?}", pth);
+                report.files_removed += 1;
+                report.bytes_reclaimed += meta.len();
+
+                #[cfg(feature = "sqlite")]
+                if let Some(index) = self.index() {
+                    index.remove(&pth)?;
+                }
+            }
+        }
+
+        let marker = dir.join(HOUR_COMPLETE_FNAME);
+        if lost_data && marker.exists() {
+            remove_file(&marker)?;
+        }
+
+        // A lock file alone doesn't keep a directory around.
+        let empty = read_dir(dir)?
+            .filter_map(|entry| entry.ok())
+            .all(|entry| entry.file_name() == HOUR_LOCK_FNAME);
+        if empty && lock.is_some() {
+            drop(lock);
+            remove_file(dir.join(HOUR_LOCK_FNAME))?;
+        }
+
+        Ok(empty)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{archive::lock_dir, LocalDirRemote};
+    use std::fs::{create_dir_all, write};
+
+    #[test]
+    fn garbage_is_collected_outside_locked_directories() {
+        let root = std::env::temp_dir().join(format!("goes_arch_gc_{}", std::process::id()));
+        let hour_dir = |hour: u32| {
+            let dir = root.join(format!("G16/ABI-L2-FDCC/2023/152/{:02}", hour));
+            create_dir_all(&dir).unwrap();
+            dir
+        };
+        let fname = |hour: u32| {
+            format!(
+                "OR_ABI-L2-FDCC-M6_G16_s2023152{:02}01172_e2023152{:02}03545_c2023152{:02}04087.nc",
+                hour, hour, hour
+            )
+        };
+
+        // An empty data file, so the hour has to be downloaded again.
+        let lost = hour_dir(18);
+        write(lost.join(fname(18)), b"").unwrap();
+        write(
+            lost.join(fname(18).replace("s20231521801", "s20231521806")),
+            b"data",
+        )
+        .unwrap();
+        write(lost.join(HOUR_COMPLETE_FNAME), b"marker").unwrap();
+        write(lost.join("download.tmp"), [0; 10]).unwrap();
+
+        let locked = hour_dir(19);
+        write(locked.join("download.tmp"), [0; 10]).unwrap();
+        let lock = lock_dir(&locked).unwrap();
+
+        let stray = hour_dir(20);
+        write(stray.join("notes.txt"), [0; 5]).unwrap();
+
+        let arch = Archive::connect(&root, LocalDirRemote::new(&root, 1));
+        let report = arch.collect_garbage().unwrap();
+
+        assert_eq!(
+            report,
+            GarbageReport {
+                files_removed: 3,
+                dirs_removed: 1,
+                bytes_reclaimed: 15,
+            }
+        );
+        assert!(!lost.join(fname(18)).exists());
+        assert!(!lost.join(HOUR_COMPLETE_FNAME).exists());
+        assert_eq!(read_dir(&lost).unwrap().count(), 2);
+        assert!(locked.join("download.tmp").exists());
+        assert!(!stray.exists());
+
+        drop(lock);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
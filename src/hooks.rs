@@ -0,0 +1,42 @@
+use crate::{product::Product, satellite::Satellite};
+use chrono::naive::NaiveDateTime;
+use std::{error::Error, path::Path, sync::Arc};
+
+type FileSavedHook = Arc<dyn Fn(&Path) + Send + Sync>;
+type HourCompleteHook = Arc<dyn Fn(Satellite, Product, NaiveDateTime, &Path) + Send + Sync>;
+type DownloadErrorHook = Arc<dyn Fn(&str, &dyn Error) + Send + Sync>;
+
+/// The callbacks registered on an archive. They're called from the download threads, so they
+/// should be quick or hand the work off elsewhere.
+#[derive(Clone, Default)]
+pub(crate) struct Hooks {
+    pub(crate) file_saved: Option<FileSavedHook>,
+    pub(crate) hour_complete: Option<HourCompleteHook>,
+    pub(crate) download_error: Option<DownloadErrorHook>,
+}
+
+impl Hooks {
+    pub(crate) fn file_saved(&self, pth: &Path) {
+        if let Some(ref hook) = self.file_saved {
+            hook(pth);
+        }
+    }
+
+    pub(crate) fn hour_complete(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_time: NaiveDateTime,
+        dir: &Path,
+    ) {
+        if let Some(ref hook) = self.hour_complete {
+            hook(sat, prod, valid_time, dir);
+        }
+    }
+
+    pub(crate) fn download_error(&self, remote_fname: &str, err: &dyn Error) {
+        if let Some(ref hook) = self.download_error {
+            hook(remote_fname, err);
+        }
+    }
+}
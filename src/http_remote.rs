@@ -0,0 +1,206 @@
+use crate::{
+    error::GoesArchError,
+    filter::FileFilter,
+    http::{get_if_none_match, get_range, head_metadata, index_links, proxied_client},
+    product::Product,
+    remote::{noaa_key_prefix, ConditionalFile, RemoteArchive, RemoteMetadata},
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use reqwest::blocking::Client;
+use std::{error::Error, io::Write};
+
+/// How the files in a remote directory are discovered.
+#[derive(Debug, Clone)]
+pub enum HttpListing {
+    /// Parse the links out of the server generated directory index page.
+    IndexPage,
+    /// Read a plain text file in each directory listing one file name per line.
+    Manifest(String),
+}
+
+/// Any HTTP(S) server mirroring the NOAA buckets, with a directory per bucket below the base URL,
+/// e.g. `https://mirror.example.edu/goes/noaa-goes16/ABI-L2-FDCC/2023/152/18/`.
+#[derive(Debug, Clone)]
+pub struct HttpRemote {
+    base_url: String,
+    listing: HttpListing,
+    client: Client,
+    num_max_downloads: usize,
+}
+
+impl HttpRemote {
+    pub fn new(
+        base_url: &str,
+        listing: HttpListing,
+        num_max_downloads: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        Ok(HttpRemote {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            listing,
+            client: Client::builder().build()?,
+            num_max_downloads,
+        })
+    }
+
+    /// Send all requests through this HTTP(S) proxy, e.g. `http://proxy.example.edu:3128`.
+    /// Without this the `HTTP_PROXY` and `HTTPS_PROXY` environment variables are used if they are
+    /// set.
+    pub fn with_proxy(mut self, proxy_url: &str) -> Result<Self, Box<dyn Error>> {
+        self.client = proxied_client(proxy_url)?;
+        Ok(self)
+    }
+
+    /// Use this client for all requests, e.g. one shared with other remotes so they pool their
+    /// connections, or one configured with custom TLS roots or keep-alive settings. It's a
+    /// `reqwest::blocking::Client` from reqwest 0.11.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn directory_url(&self, sat: Satellite, prod: Product, valid_hour: NaiveDateTime) -> String {
+        format!(
+            "{}/{}/{}",
+            self.base_url,
+            sat.bucket_name(),
+            noaa_key_prefix(sat, prod, valid_hour)
+        )
+    }
+
+    fn get(&self, url: &str) -> Result<reqwest::blocking::Response, Box<dyn Error>> {
+        let response = self.client.get(url).send()?;
+
+        if !response.status().is_success() {
+            log::error!("Error {} requesting {}", response.status(), url);
+            return Err(Box::new(GoesArchError::new("Download error")));
+        }
+
+        Ok(response)
+    }
+}
+
+impl RemoteArchive for HttpRemote {
+    fn connect(_num_max_downloads: usize) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        Err(Box::new(GoesArchError::new(
+            "HttpRemote needs a base URL, use HttpRemote::new.",
+        )))
+    }
+
+    fn retrieve_remote_filenames(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let dir_url = self.directory_url(sat, prod, valid_hour);
+
+        let fnames: Vec<String> = match self.listing {
+            HttpListing::IndexPage => index_links(&self.get(&dir_url)?.text()?),
+            HttpListing::Manifest(ref manifest) => self
+                .get(&(dir_url + manifest))?
+                .text()?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect(),
+        };
+
+        Ok(fnames
+            .into_iter()
+            .filter(|fname| filter.accepts(fname))
+            .collect())
+    }
+
+    fn retrieve_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = self.directory_url(sat, prod, valid_hour) + remote_path;
+
+        Ok(self.get(&url)?.bytes()?.to_vec())
+    }
+
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        let url = self.directory_url(sat, prod, valid_hour) + remote_path;
+
+        Ok(self.get(&url)?.copy_to(writer)?)
+    }
+
+    fn retrieve_remote_range(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = self.directory_url(sat, prod, valid_hour) + remote_path;
+
+        get_range(&self.client, &url, start, end)
+    }
+
+    fn retrieve_remote_file_if_changed(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        etag: &str,
+    ) -> Result<ConditionalFile, Box<dyn Error>> {
+        let url = self.directory_url(sat, prod, valid_hour) + remote_path;
+
+        get_if_none_match(&self.client, &url, etag)
+    }
+
+    fn retrieve_remote_metadata(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        let url = self.directory_url(sat, prod, valid_hour) + remote_path;
+
+        head_metadata(&self.client, &url)
+    }
+
+    fn retrieve_static_file(
+        &self,
+        sat: Satellite,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = format!(
+            "{}/{}/{}",
+            self.base_url,
+            sat.bucket_name(),
+            remote_path.trim_start_matches('/')
+        );
+
+        Ok(self.get(&url)?.bytes()?.to_vec())
+    }
+
+    fn retrieve_url(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(self.get(url)?.bytes()?.to_vec())
+    }
+
+    fn max_downloads(&self) -> usize {
+        self.num_max_downloads
+    }
+}
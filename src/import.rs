@@ -0,0 +1,184 @@
+use crate::{
+    archive::{is_data_file, write_atomic, Archive, HOUR_COMPLETE_FNAME},
+    compression::{find_stored, original_name, zstd_decoder},
+    filename::{satellite_and_product, scan_start, split_superseded},
+    filter::FileFilter,
+    marker::write_sidecar,
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use std::{
+    error::Error,
+    fs::{create_dir_all, read_dir, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// A directory that files were imported into.
+type ImportedDir = (PathBuf, Satellite, Product, NaiveDateTime);
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Bring GOES files from elsewhere into the archive: a directory laid out any way at all, a
+    /// tarball (`.tar`, `.tar.zst`, or `.tzst`) such as one written by `export_archive`, or a zip
+    /// file. Files are placed by the satellite, product, and scan start in their names, and
+    /// compressed the way the archive compresses downloads unless they already are. Files that
+    /// can't be placed or are already in the archive are skipped. Directories that end up with
+    /// all their files are marked complete. Returns the number of files imported.
+    pub fn import<P: AsRef<Path>>(&self, source: P) -> Result<usize, Box<dyn Error>> {
+        let source = source.as_ref();
+        let name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let mut dirs: Vec<ImportedDir> = vec![];
+        let mut num_files = 0;
+
+        if source.is_dir() {
+            let mut stack = vec![source.to_owned()];
+            while let Some(dir) = stack.pop() {
+                for entry in read_dir(&dir)? {
+                    let pth = entry?.path();
+                    if pth.is_dir() {
+                        stack.push(pth);
+                    } else if let Some(fname) = pth.file_name() {
+                        let fname = fname.to_string_lossy().to_string();
+                        if self.import_file(&fname, &mut File::open(&pth)?, &mut dirs)? {
+                            num_files += 1;
+                        }
+                    }
+                }
+            }
+        } else if name.ends_with(".tar") {
+            num_files += self.import_tar(File::open(source)?, &mut dirs)?;
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            let decoder = zstd_decoder(File::open(source)?)?;
+            num_files += self.import_tar(decoder, &mut dirs)?;
+        } else if name.ends_with(".zip") && satellite_and_product(&name).is_none() {
+            let mut zipf = zip::ZipArchive::new(File::open(source)?)?;
+            for i in 0..zipf.len() {
+                let mut entry = zipf.by_index(i)?;
+                let fname = match Path::new(entry.name()).file_name() {
+                    Some(fname) => fname.to_string_lossy().to_string(),
+                    None => continue,
+                };
+
+                if entry.is_file() && self.import_file(&fname, &mut entry, &mut dirs)? {
+                    num_files += 1;
+                }
+            }
+        } else if self.import_file(&name, &mut File::open(source)?, &mut dirs)? {
+            num_files += 1;
+        }
+
+        for (dir, sat, prod, valid_time) in dirs {
+            let (files, _) = split_superseded(Self::dir_files(&dir, &FileFilter::default()));
+            let expected = Self::expected_files(sat, prod, valid_time).max(0) as usize;
+
+            if files.len() >= expected || dir.join(HOUR_COMPLETE_FNAME).exists() {
+                Self::mark_dir_as_complete(&dir, sat, prod, valid_time, self.checksum_files())?;
+            }
+        }
+
+        log::info!("Imported {} files from {:?}", num_files, source);
+
+        Ok(num_files)
+    }
+
+    fn import_tar<R: Read>(
+        &self,
+        reader: R,
+        dirs: &mut Vec<ImportedDir>,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut tarball = tar::Archive::new(reader);
+
+        let mut num_files = 0;
+        for entry in tarball.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let fname = match entry.path()?.file_name() {
+                Some(fname) => fname.to_string_lossy().to_string(),
+                None => continue,
+            };
+
+            if self.import_file(&fname, &mut entry, dirs)? {
+                num_files += 1;
+            }
+        }
+
+        Ok(num_files)
+    }
+
+    /// Store a single file in the archive, returning whether it was imported.
+    fn import_file(
+        &self,
+        fname: &str,
+        reader: &mut dyn Read,
+        dirs: &mut Vec<ImportedDir>,
+    ) -> Result<bool, Box<dyn Error>> {
+        let orig = original_name(fname);
+        if !is_data_file(Path::new(orig)) {
+            return Ok(false);
+        }
+
+        let (sat, prod, start) = match (satellite_and_product(orig), scan_start(orig)) {
+            (Some((sat, prod)), Some(start)) => (sat, prod, start),
+            _ => {
+                log::warn!("Can't place {} in the archive, skipping it", fname);
+                return Ok(false);
+            }
+        };
+
+        // Files go in the directory of the interval their scan starts in.
+        let valid_time = Self::floor_time(start, sat.directory_interval());
+        let dir = self.build_path(sat, prod, valid_time);
+        let local_path = dir.join(orig);
+
+        if find_stored(&local_path).is_some() {
+            log::debug!("{:?} is already in the archive", local_path);
+            return Ok(false);
+        }
+
+        create_dir_all(&dir)?;
+        let stored = if fname == orig {
+            let stored = self.compression().stored_path(&local_path);
+            self.compression()
+                .write(&local_path, &stored, |writer| Ok(io::copy(reader, writer)?))?;
+            stored
+        } else {
+            let stored = dir.join(fname);
+            write_atomic(&stored, |mut f| {
+                io::copy(reader, &mut f)?;
+                Ok((f, ()))
+            })?;
+            stored
+        };
+
+        log::debug!("Imported {:?}", stored);
+
+        if self.checksum_files() {
+            write_sidecar(&stored)?;
+        }
+
+        #[cfg(feature = "sqlite")]
+        if let Some(index) = self.index() {
+            index.record(&stored, sat, prod)?;
+        }
+
+        self.hooks().file_saved(&stored);
+
+        if !dirs.iter().any(|(d, ..)| *d == dir) {
+            dirs.push((dir, sat, prod, valid_time));
+        }
+
+        Ok(true)
+    }
+}
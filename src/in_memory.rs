@@ -0,0 +1,165 @@
+use crate::{
+    archive::Archive,
+    filename::{scan_start, split_superseded},
+    filter::FileFilter,
+    product::Product,
+    remote::{RemoteArchive, RemoteMetadata},
+    report::RetrievalFailure,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use crossbeam_channel::unbounded;
+use std::{error::Error, thread};
+
+/// A file downloaded by `Archive::retrieve_bytes`.
+#[derive(Clone, Debug)]
+pub struct RetrievedFile {
+    pub sat: Satellite,
+    pub prod: Product,
+    /// The start of the directory interval the file is in.
+    pub valid_time: NaiveDateTime,
+    pub fname: String,
+    pub scan_start: Option<NaiveDateTime>,
+    /// What the remote's listing said about the file. The size is the downloaded size when the
+    /// listing doesn't have one.
+    pub metadata: RemoteMetadata,
+    /// The contents of the file as the remote serves it.
+    pub data: Vec<u8>,
+}
+
+/// What `Archive::retrieve_bytes` downloaded and what it couldn't.
+#[derive(Clone, Debug, Default)]
+pub struct BytesReport {
+    /// The files downloaded, oldest scan first.
+    pub files: Vec<RetrievedFile>,
+    pub failures: Vec<RetrievalFailure>,
+}
+
+impl BytesReport {
+    /// True if nothing failed.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Download the files of the product whose scans started from `start` through `end` straight
+    /// into memory, without reading or writing the archive at all, for processing in places with
+    /// no persistent disk. Files come back oldest scan first. Every file is held in memory at
+    /// once, so keep the window small. Hours that couldn't be listed and files that couldn't be
+    /// downloaded come back as failures.
+    pub fn retrieve_bytes(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<BytesReport, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+        let filter = FileFilter::default().scan_times(start, end);
+
+        let mut report = BytesReport::default();
+        let (to_downloader, needs_downloaded) = unbounded();
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let listing = match self
+                .remote()
+                .retrieve_remote_listing(sat, prod, valid_time, &filter)
+            {
+                Ok(listing) => listing,
+                Err(err) => {
+                    log::error!("Error retreiving remote file names: {}", err);
+                    let failure = RetrievalFailure::new(sat, prod, valid_time, None, err.as_ref());
+                    report.failures.push(failure);
+                    continue;
+                }
+            };
+
+            let fnames: Vec<String> = listing.iter().map(|file| file.name.clone()).collect();
+            let fnames = match self.all_versions() {
+                true => fnames,
+                false => split_superseded(fnames).0,
+            };
+
+            for file in listing
+                .into_iter()
+                .filter(|file| fnames.contains(&file.name))
+            {
+                to_downloader.send((valid_time, file))?;
+            }
+        }
+        drop(to_downloader);
+
+        let retry_policy = self.retry_policy();
+        let results = thread::scope(|scope| {
+            let downloaders: Vec<_> = (0..self.num_downloaders())
+                .map(|_| {
+                    let remote = self.remote().clone();
+                    let needs_downloaded = needs_downloaded.clone();
+
+                    scope.spawn(move || {
+                        let mut results = vec![];
+                        for (valid_time, file) in needs_downloaded {
+                            let fname = file.name;
+                            let res = retry_policy.run(&fname, || {
+                                remote.retrieve_remote_file(sat, prod, valid_time, &fname)
+                            });
+
+                            results.push(match res {
+                                Ok(data) => Ok(RetrievedFile {
+                                    sat,
+                                    prod,
+                                    valid_time,
+                                    scan_start: scan_start(&fname),
+                                    metadata: RemoteMetadata {
+                                        size: file.size.unwrap_or(data.len() as u64),
+                                        etag: file.etag,
+                                        last_modified: file.last_modified,
+                                    },
+                                    fname,
+                                    data,
+                                }),
+                                Err(err) => {
+                                    log::error!("Error downloading data: {} : {}", fname, err);
+                                    Err(RetrievalFailure::new(
+                                        sat,
+                                        prod,
+                                        valid_time,
+                                        Some(&fname),
+                                        err.as_ref(),
+                                    ))
+                                }
+                            });
+                        }
+                        results
+                    })
+                })
+                .collect();
+
+            downloaders
+                .into_iter()
+                .flat_map(|downloader| downloader.join().unwrap_or_default())
+                .collect::<Vec<_>>()
+        });
+
+        for res in results {
+            match res {
+                Ok(file) => report.files.push(file),
+                Err(failure) => report.failures.push(failure),
+            }
+        }
+
+        report.files.sort_by_key(|file| file.scan_start);
+        log::info!(
+            "Retrieved {} files of {:?} {:?} into memory, {} failed",
+            report.files.len(),
+            sat,
+            prod,
+            report.failures.len()
+        );
+
+        Ok(report)
+    }
+}
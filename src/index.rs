@@ -0,0 +1,180 @@
+use crate::{filename::scan_start, marker::file_checksum, product::Product, satellite::Satellite};
+use chrono::naive::NaiveDateTime;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+pub(crate) const INDEX_FNAME: &str = "index.sqlite";
+
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A file recorded in the archive index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedFile {
+    pub path: PathBuf,
+    /// The short name of the satellite, e.g. `G16`.
+    pub satellite: String,
+    /// The product prefix, e.g. `ABI-L2-FDCC`.
+    pub product: String,
+    pub scan_start: Option<NaiveDateTime>,
+    /// Size in bytes of the file as stored, i.e. zipped.
+    pub size: u64,
+    /// Hex encoded SHA-256 of the file as stored.
+    pub checksum: String,
+}
+
+/// A SQLite database in the archive root recording every archived file. Clones share the same
+/// connection, so the downloader threads can all record into it.
+#[derive(Clone, Debug)]
+pub(crate) struct ArchiveIndex {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl ArchiveIndex {
+    pub(crate) fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS files (
+                path TEXT PRIMARY KEY,
+                dir TEXT NOT NULL,
+                satellite TEXT NOT NULL,
+                product TEXT NOT NULL,
+                scan_start TEXT,
+                size INTEGER NOT NULL,
+                checksum TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS files_by_dir ON files (dir);
+            CREATE INDEX IF NOT EXISTS files_by_scan ON files (satellite, product, scan_start);",
+        )?;
+
+        Ok(ArchiveIndex {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Add or update the entry for a file, computing its size and checksum from disk.
+    pub(crate) fn record(
+        &self,
+        path: &Path,
+        sat: Satellite,
+        prod: Product,
+    ) -> Result<(), Box<dyn Error>> {
+        let size = path.metadata()?.len();
+        let checksum = file_checksum(path)?;
+        let fname = path
+            .file_name()
+            .map(|fname| fname.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let dir = path.parent().unwrap_or(path);
+
+        self.conn().execute(
+            "INSERT OR REPLACE INTO files
+                (path, dir, satellite, product, scan_start, size, checksum)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                path.to_string_lossy(),
+                dir.to_string_lossy(),
+                sat.short_name(),
+                prod.prefix(),
+                scan_start(&fname).map(|time| time.format(DATE_FORMAT).to_string()),
+                size as i64,
+                checksum,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Drop the entry for a file.
+    pub(crate) fn remove(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.conn().execute(
+            "DELETE FROM files WHERE path = ?1",
+            params![path.to_string_lossy()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Drop the entries for every file in a directory, returning how many were dropped.
+    pub(crate) fn remove_dir(&self, dir: &Path) -> Result<usize, Box<dyn Error>> {
+        Ok(self.conn().execute(
+            "DELETE FROM files WHERE dir = ?1",
+            params![dir.to_string_lossy()],
+        )?)
+    }
+
+    /// The paths of the indexed files in a directory, or `None` if none are indexed there.
+    pub(crate) fn dir_files(&self, dir: &Path) -> Result<Option<Vec<PathBuf>>, Box<dyn Error>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached("SELECT path FROM files WHERE dir = ?1")?;
+
+        let paths = stmt
+            .query_map(params![dir.to_string_lossy()], |row| {
+                row.get::<_, String>(0)
+            })?
+            .map(|path| path.map(PathBuf::from))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(if paths.is_empty() { None } else { Some(paths) })
+    }
+
+    /// The stored checksum of a file, if it's indexed.
+    pub(crate) fn checksum(&self, path: &Path) -> Result<Option<String>, Box<dyn Error>> {
+        Ok(self
+            .conn()
+            .query_row(
+                "SELECT checksum FROM files WHERE path = ?1",
+                params![path.to_string_lossy()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Every indexed file of the product with a scan start from `start` through `end`.
+    pub(crate) fn files(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<IndexedFile>, Box<dyn Error>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(
+            "SELECT path, satellite, product, scan_start, size, checksum FROM files
+                WHERE satellite = ?1 AND product = ?2 AND scan_start BETWEEN ?3 AND ?4
+                ORDER BY scan_start",
+        )?;
+
+        let files = stmt
+            .query_map(
+                params![
+                    sat.short_name(),
+                    prod.prefix(),
+                    start.format(DATE_FORMAT).to_string(),
+                    end.format(DATE_FORMAT).to_string(),
+                ],
+                |row| {
+                    Ok(IndexedFile {
+                        path: PathBuf::from(row.get::<_, String>(0)?),
+                        satellite: row.get(1)?,
+                        product: row.get(2)?,
+                        scan_start: row.get::<_, Option<String>>(3)?.and_then(|time| {
+                            NaiveDateTime::parse_from_str(&time, DATE_FORMAT).ok()
+                        }),
+                        size: row.get::<_, i64>(4)? as u64,
+                        checksum: row.get(5)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(files)
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{
+    archive::{Archive, HOUR_COMPLETE_FNAME},
+    compression::original_name,
+    filename::{scan_start, split_superseded},
+    filter::FileFilter,
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::Duration;
+use std::{error::Error, path::PathBuf};
+
+/// How far back `retrieve_latest` looks before settling for fewer files than asked for.
+const LATEST_LOOKBACK_HOURS: i64 = 24;
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Retrieve the `n` most recent files of the product on the remote right now, newest scan
+    /// first, e.g. to show the latest fire detections. Hours are searched back from the current
+    /// one until enough files turn up, as far back as a day, so a current hour that's still
+    /// filling up is handled. Hours retrieved this way aren't marked complete since only some of
+    /// their files are downloaded.
+    pub fn retrieve_latest(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        n: usize,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let now = chrono::Utc::now().naive_utc();
+        let (start, end) =
+            Self::validate_dates(sat, prod, now - Duration::hours(LATEST_LOOKBACK_HOURS), now)?;
+
+        let mut hours = vec![];
+        let mut fnames = vec![];
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            if fnames.len() >= n {
+                break;
+            }
+
+            // Complete hours are answered from the archive, the rest from the remote.
+            let dir = self.build_path(sat, prod, valid_time);
+            let hour_fnames = if dir.join(HOUR_COMPLETE_FNAME).exists() {
+                Self::dir_files(&dir, &FileFilter::default())
+                    .iter()
+                    .filter_map(|pth| pth.file_name())
+                    .map(|fname| original_name(&fname.to_string_lossy()).to_owned())
+                    .collect()
+            } else {
+                self.remote().retrieve_remote_filenames(
+                    sat,
+                    prod,
+                    valid_time,
+                    &FileFilter::default(),
+                )?
+            };
+
+            if !hour_fnames.is_empty() {
+                hours.push((sat, prod, valid_time));
+                match self.all_versions() {
+                    true => fnames.extend(hour_fnames),
+                    false => fnames.extend(split_superseded(hour_fnames).0),
+                }
+            }
+        }
+
+        fnames.sort_by_key(|fname| std::cmp::Reverse(scan_start(fname)));
+        fnames.truncate(n);
+
+        let mut paths = self.retrieve(hours, &FileFilter::default().only_files(fnames))?;
+        paths.sort_by_key(|pth| {
+            std::cmp::Reverse(
+                pth.file_name()
+                    .and_then(|fname| scan_start(&fname.to_string_lossy())),
+            )
+        });
+
+        Ok(paths)
+    }
+}
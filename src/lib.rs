@@ -1,16 +1,98 @@
 /**************************************************************************************************
  *                                           Public API
  *************************************************************************************************/
+#[cfg(feature = "tokio")]
+pub use crate::async_archive::{AsyncRemoteArchive, SpawnBlocking};
+#[cfg(feature = "sqlite")]
+pub use crate::index::IndexedFile;
 pub use crate::{
-    archive::Archive, error::GoesArchError, product::Product, remote::RemoteArchive,
-    s3_remote::AmazonS3NoaaBigData, satellite::Satellite,
+    archive::Archive,
+    azure_remote::AzureBlobNoaaGoes,
+    band::AbiBand,
+    compression::{open_archived_file, Compression},
+    coverage::Coverage,
+    custom::{CustomProduct, CustomSatellite},
+    dry_run::{DryRun, PlannedFile},
+    error::GoesArchError,
+    export::ExportFormat,
+    fallback_remote::FallbackRemote,
+    filter::{FileFilter, MesoSector},
+    gc::GarbageReport,
+    http_remote::{HttpListing, HttpRemote},
+    in_memory::{BytesReport, RetrievedFile},
+    listing::{ListedFile, ListingSnapshot},
+    local_remote::LocalDirRemote,
+    manifest::{ManifestEntry, ManifestFormat},
+    maturity::Maturity,
+    migrate::ArchiveLayout,
+    outage::DataAvailability,
+    product::{Instrument, Product, Sector},
+    product_set::ProductSet,
+    rate_limited_remote::RateLimitedRemote,
+    remote::{ConditionalFile, RemoteArchive, RemoteMetadata, RemoteSink},
+    report::{RetrievalFailure, RetrievalReport},
+    retention::RetentionPolicy,
+    s3_remote::{AmazonS3NoaaBigData, S3RemoteBuilder},
+    satellite::{Role, Satellite},
+    scan_mode::ScanMode,
+    thredds_remote::{ThreddsRemote, NOAA_CATALOG_LAYOUT},
+    verify::{BadFile, FileProblem},
 };
+pub use strum::IntoEnumIterator;
 /**************************************************************************************************
  *                                      Private Implementation
  *************************************************************************************************/
+mod alias;
 mod archive;
+mod archive_maintenance;
+mod archive_remote;
+#[cfg(feature = "tokio")]
+mod async_archive;
+mod azure_remote;
+mod band;
+mod bundle;
+mod compression;
+mod copy;
+mod coverage;
+mod custom;
+mod dry_run;
 mod error;
+mod export;
+mod fallback_remote;
+mod fields;
+mod filename;
+mod filter;
+mod format;
+mod gc;
+mod hooks;
+mod http;
+mod http_remote;
+mod import;
+mod in_memory;
+#[cfg(feature = "sqlite")]
+mod index;
+mod latest;
+mod listing;
+mod local_remote;
+mod manifest;
+mod marker;
+mod maturity;
+mod migrate;
+mod outage;
+mod poll;
 mod product;
+mod product_set;
+mod push;
+mod rate_limited_remote;
 mod remote;
+mod report;
+mod retention;
+mod retry;
 mod s3_remote;
 mod satellite;
+mod scan_mode;
+#[cfg(feature = "serde")]
+mod serde_time;
+mod thredds_remote;
+mod verify;
+mod watch;
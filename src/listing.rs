@@ -0,0 +1,171 @@
+use crate::{
+    archive::{write_atomic, Archive},
+    fields::{format_time, object, optional, optional_time, parse_time, required},
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use serde_json::{json, Value};
+use std::{error::Error, fs::read_to_string, io::Write, path::Path};
+
+/// The file in each directory recording the remote listing it was downloaded from.
+pub(crate) const LISTING_FNAME: &str = "listing.json";
+
+/// A file as the remote listed it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListedFile {
+    pub name: String,
+    /// Size in bytes, if the remote's listings report it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub size: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub etag: Option<String>,
+    /// When the remote last modified the file, if its listings report it.
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_time::option"))]
+    pub last_modified: Option<NaiveDateTime>,
+}
+
+impl ListedFile {
+    /// A file listed with only its name.
+    pub(crate) fn named(name: String) -> Self {
+        ListedFile {
+            name,
+            size: None,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "size": self.size,
+            "etag": self.etag,
+            "last_modified": self.last_modified.as_ref().map(format_time),
+        })
+    }
+
+    /// Sizes, ETags, and times left out read as unknown.
+    fn from_json(value: &Value) -> Result<Self, Box<dyn Error>> {
+        let fields = object(value, "a listed file")?;
+
+        Ok(ListedFile {
+            name: required(fields, "name", Value::as_str)?.to_owned(),
+            size: optional(fields, "size", Value::as_u64)?,
+            etag: optional(fields, "etag", Value::as_str)?.map(ToOwned::to_owned),
+            last_modified: optional_time(fields, "last_modified")?,
+        })
+    }
+}
+
+/// What the remote had for a directory when it was downloaded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListingSnapshot {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_time"))]
+    pub listed: NaiveDateTime,
+    pub files: Vec<ListedFile>,
+}
+
+impl ListingSnapshot {
+    /// A snapshot of the files as listed just now.
+    pub(crate) fn new(files: Vec<ListedFile>) -> Self {
+        ListingSnapshot {
+            listed: chrono::Utc::now().naive_utc(),
+            files,
+        }
+    }
+
+    pub(crate) fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::parse(&read_to_string(path)?)
+    }
+
+    fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let value: Value = serde_json::from_str(text)?;
+        let fields = object(&value, "a listing")?;
+
+        Ok(ListingSnapshot {
+            listed: parse_time(required(fields, "listed", Value::as_str)?)?,
+            files: required(fields, "files", Value::as_array)?
+                .iter()
+                .map(ListedFile::from_json)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "listed": format_time(&self.listed),
+            "files": self.files.iter().map(ListedFile::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        write_atomic(path, |mut f| {
+            serde_json::to_writer_pretty(&mut f, &self.to_json())?;
+            f.write_all(b"\n")?;
+            Ok((f, ()))
+        })
+    }
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// The remote listing saved when a directory was last downloaded, if listing snapshots were
+    /// on, see `with_listing_snapshots`.
+    pub fn listing_snapshot(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_time: NaiveDateTime,
+    ) -> Result<Option<ListingSnapshot>, Box<dyn Error>> {
+        let valid_time = Self::floor_time(valid_time, sat.directory_interval());
+        let path = self.build_path(sat, prod, valid_time).join(LISTING_FNAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        ListingSnapshot::read(&path).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snapshot_round_trip() {
+        let snapshot = ListingSnapshot {
+            listed: NaiveDateTime::parse_from_str("2023-06-01T18:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap(),
+            files: vec![
+                ListedFile {
+                    name: "a.nc".to_owned(),
+                    size: Some(10),
+                    etag: Some("\"abc\"".to_owned()),
+                    last_modified: NaiveDateTime::parse_from_str(
+                        "2023-06-01T18:01:23",
+                        "%Y-%m-%dT%H:%M:%S",
+                    )
+                    .ok(),
+                },
+                ListedFile::named("b.nc".to_owned()),
+            ],
+        };
+
+        let text = snapshot.to_json().to_string();
+        assert_eq!(ListingSnapshot::parse(&text).unwrap(), snapshot);
+
+        // Sizes, ETags, and times left out of a snapshot read as unknown.
+        let old = r#"{"listed": "2023-06-01T18:00:00", "files": [{"name": "b.nc"}]}"#;
+        assert_eq!(
+            ListingSnapshot::parse(old).unwrap().files,
+            vec![ListedFile::named("b.nc".to_owned())]
+        );
+        assert!(ListingSnapshot::parse(r#"{"files": []}"#).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    archive::write_atomic,
+    error::GoesArchError,
+    filter::FileFilter,
+    listing::ListedFile,
+    product::Product,
+    remote::{
+        check_range, noaa_key_prefix, static_file_path, RemoteArchive, RemoteMetadata, RemoteSink,
+    },
+    satellite::Satellite,
+};
+use chrono::{naive::NaiveDateTime, DateTime, Utc};
+use std::{
+    error::Error,
+    fs::{create_dir_all, File},
+    io::{Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+/// A directory laid out like the NOAA buckets, e.g. an NFS mount of a lab mirror, with a
+/// directory per bucket such as `<root>/noaa-goes16/ABI-L2-FDCC/2023/152/18/`.
+#[derive(Debug, Clone)]
+pub struct LocalDirRemote {
+    root: PathBuf,
+    num_max_downloads: usize,
+}
+
+impl LocalDirRemote {
+    pub fn new<P: Into<PathBuf>>(root: P, num_max_downloads: usize) -> Self {
+        LocalDirRemote {
+            root: root.into(),
+            num_max_downloads,
+        }
+    }
+
+    fn directory(&self, sat: Satellite, prod: Product, valid_hour: NaiveDateTime) -> PathBuf {
+        self.root
+            .join(sat.bucket_name())
+            .join(noaa_key_prefix(sat, prod, valid_hour))
+    }
+}
+
+impl RemoteArchive for LocalDirRemote {
+    fn connect(_num_max_downloads: usize) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        Err(Box::new(GoesArchError::new(
+            "LocalDirRemote needs a root directory, use LocalDirRemote::new.",
+        )))
+    }
+
+    fn retrieve_remote_filenames(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self
+            .retrieve_remote_listing(sat, prod, valid_hour, filter)?
+            .into_iter()
+            .map(|file| file.name)
+            .collect())
+    }
+
+    fn retrieve_remote_listing(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<ListedFile>, Box<dyn Error>> {
+        let dir = self.directory(sat, prod, valid_hour);
+        if !dir.is_dir() {
+            return Ok(vec![]);
+        }
+
+        let mut files = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            let fname = entry.file_name().to_string_lossy().to_string();
+            if filter.accepts(&fname) {
+                let meta = entry.metadata()?;
+                files.push(ListedFile {
+                    name: fname,
+                    size: Some(meta.len()),
+                    etag: None,
+                    last_modified: meta
+                        .modified()
+                        .ok()
+                        .map(|t| DateTime::<Utc>::from(t).naive_utc()),
+                });
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn retrieve_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(std::fs::read(
+            self.directory(sat, prod, valid_hour).join(remote_path),
+        )?)
+    }
+
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        let mut f = File::open(self.directory(sat, prod, valid_hour).join(remote_path))?;
+
+        Ok(std::io::copy(&mut f, writer)?)
+    }
+
+    fn retrieve_remote_range(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        check_range(start, end)?;
+
+        let mut f = File::open(self.directory(sat, prod, valid_hour).join(remote_path))?;
+        f.seek(SeekFrom::Start(start))?;
+
+        let mut data = vec![];
+        match end {
+            Some(end) => f.take(end - start + 1).read_to_end(&mut data)?,
+            None => f.read_to_end(&mut data)?,
+        };
+
+        Ok(data)
+    }
+
+    fn retrieve_remote_metadata(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        let meta = std::fs::metadata(self.directory(sat, prod, valid_hour).join(remote_path))?;
+
+        Ok(RemoteMetadata {
+            size: meta.len(),
+            etag: None,
+            last_modified: meta
+                .modified()
+                .ok()
+                .map(|t| DateTime::<Utc>::from(t).naive_utc()),
+        })
+    }
+
+    fn retrieve_static_file(
+        &self,
+        sat: Satellite,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(std::fs::read(
+            self.root
+                .join(sat.bucket_name())
+                .join(static_file_path(remote_path)?),
+        )?)
+    }
+
+    fn max_downloads(&self) -> usize {
+        self.num_max_downloads
+    }
+}
+
+impl RemoteSink for LocalDirRemote {
+    fn upload_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        fname: &str,
+        data: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let dir = self.directory(sat, prod, valid_hour);
+        create_dir_all(&dir)?;
+
+        write_atomic(&dir.join(fname), |mut f| {
+            f.write_all(data)?;
+            Ok((f, ()))
+        })
+    }
+}
@@ -0,0 +1,351 @@
+use crate::{
+    archive::{is_data_file, Archive},
+    compression::original_name,
+    error::GoesArchError,
+    fields::{
+        format_time, object, optional, optional_time, parse_optional_time, parse_time, required,
+    },
+    filename::scan_start,
+    filter::FileFilter,
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use csv::StringRecord;
+use serde_json::{json, Value};
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs::{read_dir, read_to_string, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+/// The file format of a manifest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ManifestFormat {
+    /// An array of objects, one per file.
+    Json,
+    /// A header row followed by a row per file.
+    Csv,
+}
+
+/// A file listed in a manifest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ManifestEntry {
+    /// The short name of the satellite, e.g. `G16`.
+    pub satellite: String,
+    /// The product prefix, e.g. `ABI-L2-FDCC`.
+    pub product: String,
+    /// The start of the remote directory the file is in.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_time"))]
+    pub valid_time: NaiveDateTime,
+    /// The file name on the remote.
+    pub fname: String,
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_time::option"))]
+    pub scan_start: Option<NaiveDateTime>,
+    /// Size in bytes of the file as stored in the archive.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub size: u64,
+}
+
+/// The columns of a CSV manifest, also the fields of each JSON object.
+const COLUMNS: [&str; 6] = [
+    "satellite",
+    "product",
+    "valid_time",
+    "fname",
+    "scan_start",
+    "size",
+];
+
+impl ManifestEntry {
+    fn to_json(&self) -> Value {
+        json!({
+            "satellite": self.satellite,
+            "product": self.product,
+            "valid_time": format_time(&self.valid_time),
+            "fname": self.fname,
+            "scan_start": self.scan_start.as_ref().map(format_time),
+            "size": self.size,
+        })
+    }
+
+    fn from_json(value: &Value) -> Result<Self, Box<dyn Error>> {
+        let fields = object(value, "a manifest entry")?;
+
+        Ok(ManifestEntry {
+            satellite: required(fields, "satellite", Value::as_str)?.to_owned(),
+            product: required(fields, "product", Value::as_str)?.to_owned(),
+            valid_time: parse_time(required(fields, "valid_time", Value::as_str)?)?,
+            fname: required(fields, "fname", Value::as_str)?.to_owned(),
+            scan_start: optional_time(fields, "scan_start")?,
+            size: optional(fields, "size", Value::as_u64)?.unwrap_or_default(),
+        })
+    }
+
+    fn to_record(&self) -> [String; 6] {
+        [
+            self.satellite.clone(),
+            self.product.clone(),
+            format_time(&self.valid_time),
+            self.fname.clone(),
+            self.scan_start
+                .as_ref()
+                .map(format_time)
+                .unwrap_or_default(),
+            self.size.to_string(),
+        ]
+    }
+
+    /// A row of a CSV manifest, with the position of each column in `headers`. Columns can come in
+    /// any order, and the scan start and size columns can be left out.
+    fn from_record(record: &StringRecord, headers: &StringRecord) -> Result<Self, Box<dyn Error>> {
+        let column = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header == name)
+                .and_then(|idx| record.get(idx))
+        };
+        let required = |name: &str| {
+            column(name).ok_or_else(|| GoesArchError::new(&format!("Missing column: {}", name)))
+        };
+
+        Ok(ManifestEntry {
+            satellite: required("satellite")?.to_owned(),
+            product: required("product")?.to_owned(),
+            valid_time: parse_time(required("valid_time")?)?,
+            fname: required("fname")?.to_owned(),
+            scan_start: parse_optional_time(column("scan_start").unwrap_or_default())?,
+            size: column("size")
+                .map(str::parse)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl ManifestFormat {
+    fn write(&self, entries: &[ManifestEntry], out: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+        match self {
+            ManifestFormat::Json => {
+                let entries: Vec<Value> = entries.iter().map(ManifestEntry::to_json).collect();
+                serde_json::to_writer_pretty(&mut *out, &entries)?;
+                writeln!(out)?;
+            }
+            ManifestFormat::Csv => {
+                let mut writer = csv::Writer::from_writer(out);
+                writer.write_record(COLUMNS)?;
+                for entry in entries {
+                    writer.write_record(entry.to_record())?;
+                }
+                writer.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<ManifestEntry>, Box<dyn Error>> {
+        match self {
+            ManifestFormat::Json => serde_json::from_str::<Vec<Value>>(text)?
+                .iter()
+                .map(ManifestEntry::from_json)
+                .collect(),
+            ManifestFormat::Csv => {
+                let mut reader = csv::ReaderBuilder::new()
+                    .trim(csv::Trim::All)
+                    .from_reader(text.as_bytes());
+                let headers = reader.headers()?.clone();
+
+                reader
+                    .records()
+                    .map(|record| ManifestEntry::from_record(&record?, &headers))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Write a listing of every data file in the archive to `path`, returning the number of
+    /// files listed. Only the standard satellites and products are listed.
+    pub fn export_manifest<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: ManifestFormat,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut entries = vec![];
+        for sat in Satellite::all() {
+            if !self.root().join(sat.short_name()).is_dir() {
+                continue;
+            }
+
+            for prod in Product::all() {
+                for (dir, valid_time) in self.time_dirs(sat, prod)? {
+                    entries.extend(manifest_entries(sat, prod, &dir, valid_time)?);
+                }
+            }
+        }
+
+        let mut out = BufWriter::new(File::create(path.as_ref())?);
+        format.write(&entries, &mut out)?;
+        out.flush()?;
+
+        log::info!(
+            "Wrote {} files to manifest {:?}",
+            entries.len(),
+            path.as_ref()
+        );
+
+        Ok(entries.len())
+    }
+
+    /// Download exactly the files listed in a manifest, e.g. one written by `export_manifest`, so
+    /// a dataset can be reproduced file for file. The source is a local path or an HTTP(S) URL,
+    /// fetched with the remote so it goes through the same proxy and timeout.
+    /// Returns the paths of the listed files that are in the archive afterwards.
+    pub fn sync_manifest(
+        &self,
+        source: &str,
+        format: ManifestFormat,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let text = if source.starts_with("http://") || source.starts_with("https://") {
+            String::from_utf8(self.remote().retrieve_url(source)?)?
+        } else {
+            read_to_string(source)?
+        };
+
+        let entries = format.parse(&text)?;
+
+        let mut hours: Vec<(Satellite, Product, NaiveDateTime)> = vec![];
+        let mut seen = HashSet::new();
+        for entry in &entries {
+            let sat = Satellite::all().find(|sat| sat.short_name() == entry.satellite);
+            let prod = Product::all().find(|prod| prod.prefix() == entry.product);
+
+            match (sat, prod) {
+                (Some(sat), Some(prod)) => {
+                    if seen.insert((sat.short_name(), prod.prefix(), entry.valid_time)) {
+                        hours.push((sat, prod, entry.valid_time));
+                    }
+                }
+                _ => log::warn!(
+                    "Skipping manifest entry for unknown satellite or product: {} {} {}",
+                    entry.satellite,
+                    entry.product,
+                    entry.fname
+                ),
+            }
+        }
+
+        let filter = FileFilter::default().only_files(entries.iter().map(|e| e.fname.clone()));
+        let paths = self.retrieve(hours, &filter)?;
+
+        if paths.len() < entries.len() {
+            log::warn!(
+                "Only {} of the {} files in the manifest are in the archive",
+                paths.len(),
+                entries.len()
+            );
+        }
+
+        Ok(paths)
+    }
+}
+
+fn manifest_entries(
+    sat: Satellite,
+    prod: Product,
+    dir: &Path,
+    valid_time: NaiveDateTime,
+) -> Result<Vec<ManifestEntry>, Box<dyn Error>> {
+    let mut entries = vec![];
+    for entry in read_dir(dir)? {
+        let entry = entry?;
+        if !is_data_file(&entry.path()) {
+            continue;
+        }
+
+        let fname = entry.file_name().to_string_lossy().to_string();
+        let fname = original_name(&fname).to_owned();
+
+        entries.push(ManifestEntry {
+            satellite: sat.short_name().to_owned(),
+            product: prod.prefix().to_owned(),
+            valid_time,
+            scan_start: scan_start(&fname),
+            fname,
+            size: entry.metadata()?.len(),
+        });
+    }
+
+    entries.sort_by(|a, b| a.fname.cmp(&b.fname));
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entries() -> Vec<ManifestEntry> {
+        let time = |text| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+
+        vec![
+            ManifestEntry {
+                satellite: "G16".to_owned(),
+                product: "ABI-L2-FDCC".to_owned(),
+                valid_time: time("2023-06-01 18:00"),
+                fname: "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804176.nc"
+                    .to_owned(),
+                scan_start: Some(time("2023-06-01 18:01")),
+                size: 1234,
+            },
+            ManifestEntry {
+                satellite: "G16".to_owned(),
+                product: "ABI-L2-FDCC".to_owned(),
+                valid_time: time("2023-06-01 18:00"),
+                fname: "odd, \"quoted\" {name}.nc".to_owned(),
+                scan_start: None,
+                size: 0,
+            },
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in [ManifestFormat::Json, ManifestFormat::Csv] {
+            let mut out = vec![];
+            format.write(&entries(), &mut out).unwrap();
+
+            let text = String::from_utf8(out).unwrap();
+            assert_eq!(format.parse(&text).unwrap(), entries(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn parse_csv_with_spaces() {
+        let text = "satellite, product, valid_time, fname, scan_start, size\n\
+                    G16, ABI-L2-FDCC, 2023-06-01T18:00:00, a.nc, , 10\n";
+
+        let parsed = ManifestFormat::Csv.parse(text).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].fname, "a.nc");
+        assert_eq!(parsed[0].scan_start, None);
+        assert_eq!(parsed[0].size, 10);
+    }
+
+    #[test]
+    fn missing_fields_are_errors() {
+        assert!(ManifestFormat::Json
+            .parse(r#"[{"satellite": "G16"}]"#)
+            .is_err());
+        assert!(ManifestFormat::Csv.parse("satellite\nG16\n").is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{
+    archive::{is_data_file, write_atomic},
+    bundle::{bundled_files, read_bundled, BUNDLE_FNAME},
+    fields::{format_time, object, optional, optional_time, required},
+};
+use chrono::naive::NaiveDateTime;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    error::Error,
+    fs::{read_dir, read_to_string, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// The version of the completion marker format written by this crate.
+const MARKER_VERSION: u32 = 1;
+
+/// Why a directory was marked complete.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum MarkerBasis {
+    /// It held at least the expected number of files.
+    Count,
+    /// It was old enough that no more files would arrive.
+    Age,
+    /// The satellite had known data problems, so it would never fill up.
+    Degraded,
+    /// An old marker that only recorded when it was written.
+    Legacy,
+}
+
+impl MarkerBasis {
+    fn name(&self) -> &'static str {
+        match self {
+            MarkerBasis::Count => "count",
+            MarkerBasis::Age => "age",
+            MarkerBasis::Degraded => "degraded",
+            MarkerBasis::Legacy => "legacy",
+        }
+    }
+
+    /// Bases this version doesn't know read as legacy.
+    fn from_name(name: &str) -> Self {
+        match name {
+            "count" => MarkerBasis::Count,
+            "age" => MarkerBasis::Age,
+            "degraded" => MarkerBasis::Degraded,
+            _ => MarkerBasis::Legacy,
+        }
+    }
+}
+
+/// A file as it was when its directory was marked complete.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct MarkedFile {
+    /// The file name as stored, with any compression extension.
+    pub(crate) name: String,
+    pub(crate) size: u64,
+    /// Hex encoded SHA-256 of the file as stored, only kept with checksum files turned on.
+    pub(crate) checksum: Option<String>,
+}
+
+impl MarkedFile {
+    fn to_json(&self) -> Value {
+        let mut value = json!({ "name": self.name, "size": self.size });
+        if let Some(checksum) = &self.checksum {
+            value["checksum"] = json!(checksum);
+        }
+
+        value
+    }
+
+    fn from_json(value: &Value) -> Result<Self, Box<dyn Error>> {
+        let fields = object(value, "a marked file")?;
+
+        Ok(MarkedFile {
+            name: required(fields, "name", Value::as_str)?.to_owned(),
+            size: required(fields, "size", Value::as_u64)?,
+            checksum: optional(fields, "checksum", Value::as_str)?.map(ToOwned::to_owned),
+        })
+    }
+}
+
+/// The contents of the completion marker of a directory. Legacy markers were just the time they
+/// were written, so they have no files and no expected count.
+#[derive(Clone, Debug)]
+pub(crate) struct CompletionMarker {
+    pub(crate) completed: Option<NaiveDateTime>,
+    pub(crate) expected: Option<i32>,
+    pub(crate) basis: MarkerBasis,
+    pub(crate) files: Vec<MarkedFile>,
+}
+
+impl CompletionMarker {
+    /// A marker listing every data file in `dir` as it is now, bundled or not. Files are only
+    /// hashed with `checksums`, since it means reading all of them.
+    pub(crate) fn for_dir(
+        dir: &Path,
+        expected: i32,
+        basis: MarkerBasis,
+        checksums: bool,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut files = vec![];
+        for entry in read_dir(dir)? {
+            let entry = entry?;
+            let pth = entry.path();
+            if !is_data_file(&pth) {
+                continue;
+            }
+
+            files.push(MarkedFile {
+                name: entry.file_name().to_string_lossy().to_string(),
+                size: entry.metadata()?.len(),
+                checksum: checksums.then(|| file_checksum(&pth)).transpose()?,
+            });
+        }
+
+        // Files extracted from the bundle were listed above.
+        for file in bundled_files(dir)? {
+            if dir.join(&file.name).exists() {
+                continue;
+            }
+
+            let checksum = match checksums {
+                true => {
+                    let data = read_bundled(&dir.join(BUNDLE_FNAME).join(&file.name))?;
+                    Some(data_checksum(&data.unwrap_or_default()))
+                }
+                false => None,
+            };
+            files.push(MarkedFile {
+                name: file.name,
+                size: file.size,
+                checksum,
+            });
+        }
+
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(CompletionMarker {
+            completed: Some(chrono::Utc::now().naive_utc()),
+            expected: Some(expected),
+            basis,
+            files,
+        })
+    }
+
+    /// Read a marker in either the current or the legacy format.
+    pub(crate) fn read(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::parse(&read_to_string(path)?)
+    }
+
+    fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        if text.trim_start().starts_with('{') {
+            return Self::from_json(&serde_json::from_str(text)?);
+        }
+
+        Ok(CompletionMarker {
+            completed: NaiveDateTime::parse_from_str(text.trim(), "%Y-%m-%d %H:%M:%S%.f").ok(),
+            expected: None,
+            basis: MarkerBasis::Legacy,
+            files: vec![],
+        })
+    }
+
+    /// Fields left out, e.g. by markers from a newer version, read as unknown.
+    fn from_json(value: &Value) -> Result<Self, Box<dyn Error>> {
+        let fields = object(value, "a completion marker")?;
+
+        Ok(CompletionMarker {
+            completed: optional_time(fields, "completed")?,
+            expected: optional(fields, "expected", Value::as_i64)?
+                .map(i32::try_from)
+                .transpose()?,
+            basis: optional(fields, "basis", Value::as_str)?
+                .map_or(MarkerBasis::Legacy, MarkerBasis::from_name),
+            files: optional(fields, "files", Value::as_array)?
+                .unwrap_or(&vec![])
+                .iter()
+                .map(MarkedFile::from_json)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// The marker as written to disk, with the format version.
+    fn to_json(&self) -> Value {
+        json!({
+            "version": MARKER_VERSION,
+            "completed": self.completed.as_ref().map(format_time),
+            "expected": self.expected,
+            "basis": self.basis.name(),
+            "files": self.files.iter().map(MarkedFile::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Write the marker to `path` atomically.
+    pub(crate) fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        write_atomic(path, |mut f| {
+            serde_json::to_writer_pretty(&mut f, &self.to_json())?;
+            f.write_all(b"\n")?;
+            Ok((f, ()))
+        })
+    }
+
+    /// The recorded state of a file, if the marker lists it.
+    pub(crate) fn file(&self, name: &str) -> Option<&MarkedFile> {
+        self.files.iter().find(|file| file.name == name)
+    }
+}
+
+/// The checksum file kept next to a stored file, in the format written by `sha256sum`.
+pub(crate) fn sidecar_path(stored: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.{}", stored.to_string_lossy(), SIDECAR_EXT))
+}
+
+pub(crate) const SIDECAR_EXT: &str = "sha256";
+
+/// Write the checksum file for a stored file.
+pub(crate) fn write_sidecar(stored: &Path) -> Result<(), Box<dyn Error>> {
+    let fname = stored
+        .file_name()
+        .map(|fname| fname.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let line = format!("{}  {}\n", file_checksum(stored)?, fname);
+
+    write_atomic(&sidecar_path(stored), |mut f| {
+        f.write_all(line.as_bytes())?;
+        Ok((f, ()))
+    })
+}
+
+/// Whether a stored file still matches its checksum file, or `None` if it doesn't have one.
+pub(crate) fn check_sidecar(stored: &Path) -> Result<Option<bool>, Box<dyn Error>> {
+    let sidecar = sidecar_path(stored);
+    if !sidecar.exists() {
+        return Ok(None);
+    }
+
+    let text = read_to_string(sidecar)?;
+    let expected = text.split_whitespace().next().unwrap_or_default();
+
+    Ok(Some(file_checksum(stored)? == expected))
+}
+
+/// The hex encoded SHA-256 of a file.
+pub(crate) fn file_checksum(path: &Path) -> Result<String, Box<dyn Error>> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+
+    Ok(hex(&hasher.finalize()))
+}
+
+/// The hex encoded SHA-256 of data in memory.
+pub(crate) fn data_checksum(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn marker_round_trip() {
+        let marker = CompletionMarker {
+            completed: NaiveDateTime::parse_from_str("2023-06-01T18:00:00", "%Y-%m-%dT%H:%M:%S")
+                .ok(),
+            expected: Some(12),
+            basis: MarkerBasis::Count,
+            files: vec![
+                MarkedFile {
+                    name: "a \"quoted\" name.nc.zip".to_owned(),
+                    size: 10,
+                    checksum: Some(data_checksum(b"data")),
+                },
+                MarkedFile {
+                    name: "b.nc.zip".to_owned(),
+                    size: 20,
+                    checksum: None,
+                },
+            ],
+        };
+
+        let text = marker.to_json().to_string();
+        let read = CompletionMarker::parse(&text).unwrap();
+
+        assert_eq!(read.completed, marker.completed);
+        assert_eq!(read.expected, marker.expected);
+        assert_eq!(read.basis, marker.basis);
+        assert_eq!(read.files, marker.files);
+    }
+
+    #[test]
+    fn parse_legacy_markers() {
+        let marker = CompletionMarker::parse("2023-06-01 18:00:00.123\n").unwrap();
+        assert_eq!(marker.basis, MarkerBasis::Legacy);
+        assert!(marker.completed.is_some());
+        assert!(marker.files.is_empty());
+
+        let marker = CompletionMarker::parse(r#"{"version": 2, "basis": "new"}"#).unwrap();
+        assert_eq!(marker.basis, MarkerBasis::Legacy);
+        assert_eq!(marker.expected, None);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{
+    error::GoesArchError,
+    filename::{satellite_and_product, scan_start},
+    product::Product,
+    satellite::Satellite,
+};
+use chrono::NaiveDateTime;
+use std::{error::Error, path::Path, sync::Mutex};
+
+/// NOAA's data product maturity levels. Beta and provisional data have known quality issues.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Maturity {
+    Beta,
+    Provisional,
+    Operational,
+}
+
+impl Maturity {
+    /// The maturity of the product in a GOES file when it was scanned, from its name. `None` if
+    /// the name doesn't say which satellite, product, or scan it is.
+    pub fn of_file(pth: &Path) -> Option<Maturity> {
+        let fname = pth.file_name()?.to_string_lossy();
+        let (sat, prod) = satellite_and_product(&fname)?;
+
+        Some(sat.maturity(prod, scan_start(&fname)?))
+    }
+
+    /// Record when a product of a satellite was declared provisional and then operational, e.g.
+    /// from NOAA's product maturity declarations, since the crate doesn't know them. Adding a
+    /// period for the same satellite and product again replaces it.
+    pub fn add_period(
+        sat: Satellite,
+        prod: Product,
+        provisional: NaiveDateTime,
+        operational: NaiveDateTime,
+    ) -> Result<(), Box<dyn Error>> {
+        if provisional > operational {
+            return Err(Box::new(GoesArchError::new(
+                "A product can't be operational before it's provisional.",
+            )));
+        }
+
+        let mut periods = PERIODS.lock().unwrap_or_else(|err| err.into_inner());
+        periods.retain(|p| !p.matches(sat, prod));
+        periods.push(MaturityPeriod {
+            sat,
+            prod,
+            provisional,
+            operational,
+        });
+
+        Ok(())
+    }
+}
+
+/// When a product of a satellite became provisional and then operational.
+struct MaturityPeriod {
+    sat: Satellite,
+    prod: Product,
+    provisional: NaiveDateTime,
+    operational: NaiveDateTime,
+}
+
+impl MaturityPeriod {
+    fn matches(&self, sat: Satellite, prod: Product) -> bool {
+        self.sat.short_name() == sat.short_name() && self.prod.prefix() == prod.prefix()
+    }
+}
+
+// Only periods added with `Maturity::add_period`, the crate doesn't ship maturity dates of its own.
+static PERIODS: Mutex<Vec<MaturityPeriod>> = Mutex::new(Vec::new());
+
+impl Satellite {
+    /// The maturity level of the product at `valid_time`, from its period if one was added with
+    /// `Maturity::add_period`. Otherwise data from before `earliest_operational_date` is beta and
+    /// everything since is operational.
+    pub fn maturity(&self, prod: Product, valid_time: NaiveDateTime) -> Maturity {
+        let periods = PERIODS.lock().unwrap_or_else(|err| err.into_inner());
+        let (provisional, operational) = periods
+            .iter()
+            .find(|p| p.matches(*self, prod))
+            .map(|p| (p.provisional, p.operational))
+            .unwrap_or_else(|| {
+                let earliest = self.earliest_operational_date(prod);
+                (earliest, earliest)
+            });
+
+        match valid_time {
+            time if time < provisional => Maturity::Beta,
+            time if time < operational => Maturity::Provisional,
+            _ => Maturity::Operational,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .unwrap()
+    }
+
+    #[test]
+    fn maturity_by_operational_date() {
+        let time = date(2021, 6, 1);
+
+        assert_eq!(
+            Satellite::GOES16.maturity(Product::FDCC, time),
+            Maturity::Operational
+        );
+        assert_eq!(
+            Satellite::GOES16.maturity(Product::FDCM, date(2021, 5, 1)),
+            Maturity::Beta
+        );
+        assert_eq!(
+            Satellite::GOES18.maturity(Product::FDCC, time),
+            Maturity::Beta
+        );
+        assert_eq!(
+            Satellite::HIMAWARI8.maturity(Product::AhiFldk, time),
+            Maturity::Operational
+        );
+    }
+
+    #[test]
+    fn added_periods_replace_the_operational_date() {
+        let prod = Product::custom("ABI-L2-MATURITYTEST", 12).unwrap();
+        let sat = Satellite::GOES16;
+        assert_eq!(sat.maturity(prod, date(2018, 1, 1)), Maturity::Operational);
+
+        Maturity::add_period(sat, prod, date(2018, 6, 1), date(2019, 6, 1)).unwrap();
+        assert_eq!(sat.maturity(prod, date(2018, 1, 1)), Maturity::Beta);
+        assert_eq!(sat.maturity(prod, date(2018, 6, 1)), Maturity::Provisional);
+        assert_eq!(sat.maturity(prod, date(2019, 6, 1)), Maturity::Operational);
+        assert_eq!(
+            Satellite::GOES18.maturity(prod, date(2023, 6, 1)),
+            Maturity::Operational
+        );
+
+        assert!(Maturity::add_period(sat, prod, date(2019, 6, 1), date(2018, 6, 1)).is_err());
+    }
+
+    #[test]
+    fn maturity_of_file() {
+        let pth =
+            Path::new("OR_ABI-L2-FDCC-M3_G16_s20173001200000_e20173001202000_c20173001203000.nc");
+        assert_eq!(Maturity::of_file(pth), Some(Maturity::Beta));
+        assert_eq!(Maturity::of_file(Path::new("land_mask.nc")), None);
+    }
+}
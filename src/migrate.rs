@@ -0,0 +1,285 @@
+use crate::{
+    archive::{is_data_file, Archive, HOUR_COMPLETE_FNAME, STATIC_DIR},
+    archive_maintenance::remove_empty_dirs,
+    bundle::BUNDLE_FNAME,
+    error::GoesArchError,
+    filename::{satellite_and_product, scan_start},
+    listing::LISTING_FNAME,
+    marker::sidecar_path,
+    remote::{noaa_key_prefix, RemoteArchive},
+    satellite::Satellite,
+};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::{copy, create_dir_all, read_dir, remove_file, rename},
+    path::{Path, PathBuf},
+};
+
+/// How files are arranged below the root of an archive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveLayout {
+    /// The layout used by this crate, `<root>/G16/ABI-L2-FDCC/2023/152/18/`.
+    Native,
+    /// The layout of the NOAA buckets, as left by `aws s3 sync`,
+    /// `<root>/noaa-goes16/ABI-L2-FDCC/2023/152/18/`.
+    NoaaBucket,
+    /// Every file directly in the root.
+    Flat,
+}
+
+impl ArchiveLayout {
+    /// The layout of an archive root with no format file, going by the data already in it.
+    pub(crate) fn detect(root: &Path) -> Result<Self, Box<dyn Error>> {
+        if ArchiveLayout::NoaaBucket
+            .top_dirs(root)
+            .iter()
+            .any(|dir| dir.is_dir())
+        {
+            return Ok(ArchiveLayout::NoaaBucket);
+        }
+
+        if root.is_dir() && has_data_files(root)? {
+            return Ok(ArchiveLayout::Flat);
+        }
+
+        Ok(ArchiveLayout::Native)
+    }
+
+    /// The directories below the root that files in this layout can be in.
+    fn top_dirs(&self, root: &Path) -> Vec<PathBuf> {
+        match self {
+            ArchiveLayout::Native => Satellite::all()
+                .map(|sat| root.join(sat.short_name()))
+                .collect(),
+            ArchiveLayout::NoaaBucket => Satellite::all()
+                .map(|sat| root.join(sat.bucket_name()))
+                .collect(),
+            ArchiveLayout::Flat => vec![root.to_owned()],
+        }
+    }
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Move the files in the archive root from one layout to another, e.g. to adopt data
+    /// already synced from the NOAA buckets without downloading it again. Files are placed by
+    /// the satellite, product, and scan start in their names, files that can't be placed or
+    /// whose destination already exists are left alone. Completion markers move with their
+    /// directories when all of their data files moved to the same place, otherwise they're
+    /// deleted. The layout is recorded in the archive format file. Returns the number of files
+    /// moved.
+    pub fn migrate_layout(
+        &self,
+        from: ArchiveLayout,
+        to: ArchiveLayout,
+    ) -> Result<usize, Box<dyn Error>> {
+        let root = self.root();
+
+        let mut files = vec![];
+        for dir in from.top_dirs(root) {
+            if dir.is_dir() {
+                collect_data_files(&dir, from == ArchiveLayout::Flat, &mut files)?;
+            }
+        }
+
+        let mut num_files = 0;
+        // The one directory each source directory's files went to, or None if they were split up.
+        let mut moved_dirs: HashMap<PathBuf, Option<PathBuf>> = HashMap::new();
+        for src in files {
+            let fname = match src.file_name() {
+                Some(fname) => fname.to_string_lossy().to_string(),
+                None => continue,
+            };
+
+            let dest_dir = match self.layout_dir(to, &fname) {
+                Some(dir) => dir,
+                None => {
+                    log::warn!("Can't place {:?} in the new layout, leaving it", src);
+                    continue;
+                }
+            };
+
+            let dest = dest_dir.join(&fname);
+            if dest == src {
+                continue;
+            }
+            if dest.exists() {
+                log::warn!("{:?} already exists, leaving {:?}", dest, src);
+                continue;
+            }
+
+            create_dir_all(&dest_dir)?;
+            move_file(&src, &dest)?;
+
+            let sidecar = sidecar_path(&src);
+            if sidecar.exists() {
+                move_file(&sidecar, &sidecar_path(&dest))?;
+            }
+            log::debug!("Moved {:?} to {:?}", src, dest);
+            num_files += 1;
+
+            if let Some(src_dir) = src.parent() {
+                moved_dirs
+                    .entry(src_dir.to_owned())
+                    .and_modify(|dir| {
+                        if dir.as_ref() != Some(&dest_dir) {
+                            *dir = None;
+                        }
+                    })
+                    .or_insert(Some(dest_dir));
+            }
+        }
+
+        // A marker only describes its new directory if every data file went there with it.
+        for (src_dir, dest_dir) in moved_dirs {
+            let dest_dir = match dest_dir {
+                Some(dir) if to != ArchiveLayout::Flat && !has_data_files(&src_dir)? => Some(dir),
+                _ => None,
+            };
+
+            for fname in [HOUR_COMPLETE_FNAME, LISTING_FNAME] {
+                let src = src_dir.join(fname);
+                if !src.exists() {
+                    continue;
+                }
+
+                match &dest_dir {
+                    Some(dir) if !dir.join(fname).exists() => rename(&src, dir.join(fname))?,
+                    _ => remove_file(&src)?,
+                }
+            }
+        }
+
+        if from != ArchiveLayout::Flat {
+            for dir in from.top_dirs(root) {
+                if remove_empty_dirs(&dir)? {
+                    std::fs::remove_dir(&dir)?;
+                }
+            }
+        }
+
+        self.write_format(to)?;
+
+        log::info!(
+            "Moved {} files from {:?} to {:?} layout",
+            num_files,
+            from,
+            to
+        );
+
+        Ok(num_files)
+    }
+
+    /// Where a file belongs in a layout, based on its name.
+    fn layout_dir(&self, layout: ArchiveLayout, fname: &str) -> Option<PathBuf> {
+        let (sat, prod) = satellite_and_product(fname)?;
+
+        // Files go in the directory of the interval their scan starts in.
+        let valid_time = Self::floor_time(scan_start(fname)?, sat.directory_interval());
+
+        match layout {
+            ArchiveLayout::Native => Some(self.build_path(sat, prod, valid_time)),
+            ArchiveLayout::NoaaBucket => Some(
+                self.root()
+                    .join(sat.bucket_name())
+                    .join(noaa_key_prefix(sat, prod, valid_time)),
+            ),
+            ArchiveLayout::Flat => Some(self.root().to_owned()),
+        }
+    }
+}
+
+/// The data files below `dir`, or only those directly in it if `shallow`.
+fn collect_data_files(
+    dir: &Path,
+    shallow: bool,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    for entry in read_dir(dir)? {
+        let entry = entry?;
+        let pth = entry.path();
+
+        if entry.file_type()?.is_dir() {
+            if !shallow && entry.file_name() != STATIC_DIR {
+                collect_data_files(&pth, shallow, files)?;
+            }
+        } else if is_data_file(&pth) {
+            files.push(pth);
+        } else if entry.file_name() == BUNDLE_FNAME {
+            log::error!("Can't migrate the compacted directory {:?}", dir);
+            return Err(Box::new(GoesArchError::new(
+                "Archive has compacted directories, see Archive::unbundle.",
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether any data files are directly in `dir`.
+fn has_data_files(dir: &Path) -> Result<bool, Box<dyn Error>> {
+    for entry in read_dir(dir)? {
+        if is_data_file(&entry?.path()) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Move a file, copying it if it's going to another file system.
+fn move_file(src: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    if rename(src, dest).is_err() {
+        copy(src, dest)?;
+        remove_file(src)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::LocalDirRemote;
+    use std::fs::write;
+
+    #[test]
+    fn bucket_files_move_into_the_native_layout_and_back_out_flat() {
+        let root = std::env::temp_dir().join(format!("goes_arch_migrate_{}", std::process::id()));
+        let fname = "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804087.nc";
+        let bucket_dir = root.join("noaa-goes16/ABI-L2-FDCC/2023/152/18");
+        create_dir_all(&bucket_dir).unwrap();
+        write(bucket_dir.join(fname), b"data").unwrap();
+        write(bucket_dir.join(HOUR_COMPLETE_FNAME), b"marker").unwrap();
+
+        let arch = Archive::connect(&root, LocalDirRemote::new(&root, 1));
+        assert_eq!(
+            ArchiveLayout::detect(&root).unwrap(),
+            ArchiveLayout::NoaaBucket
+        );
+        assert!(arch.check_format().is_err());
+
+        let moved = arch
+            .migrate_layout(ArchiveLayout::NoaaBucket, ArchiveLayout::Native)
+            .unwrap();
+        let native_dir = root.join("G16/ABI-L2-FDCC/2023/152/18");
+        assert_eq!(moved, 1);
+        assert!(native_dir.join(fname).is_file());
+        assert!(native_dir.join(HOUR_COMPLETE_FNAME).is_file());
+        assert!(!root.join("noaa-goes16").exists());
+        arch.check_format().unwrap();
+
+        let moved = arch
+            .migrate_layout(ArchiveLayout::Native, ArchiveLayout::Flat)
+            .unwrap();
+        assert_eq!(moved, 1);
+        assert!(root.join(fname).is_file());
+        assert!(!native_dir.join(HOUR_COMPLETE_FNAME).exists());
+        assert!(arch.check_format().is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
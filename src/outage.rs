@@ -0,0 +1,130 @@
+use crate::{
+    product::{Instrument, Product},
+    satellite::Satellite,
+};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+
+/// Whether a satellite was expected to produce its full complement of files at a given time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataAvailability {
+    Normal,
+    /// Known periods with missing or degraded data, so an hour with fewer files than expected
+    /// is not a sign of an incomplete download.
+    Degraded,
+}
+
+/// A period when data is degraded every day, e.g. the nightly GOES-17 loop heat pipe gaps.
+struct SeasonalOutage {
+    sat: Satellite,
+    // The products the outage degrades
+    products: fn(Product) -> bool,
+    // Inclusive range of years the outage recurs
+    years: (i32, i32),
+    // Inclusive (month, day) range each year
+    season: ((u32, u32), (u32, u32)),
+    // Hours of the day (UTC), start inclusive and end exclusive
+    hours: (u32, u32),
+}
+
+// The loop heat pipe anomaly on the GOES-17 ABI left the infrared bands saturated for several
+// hours around local midnight in the seasons around the equinoxes, degrading every ABI product
+// made from them. The windows are approximate and err on the side of being too wide.
+const SEASONAL_OUTAGES: &[SeasonalOutage] = &[
+    SeasonalOutage {
+        sat: Satellite::GOES17,
+        products: uses_abi_infrared,
+        years: (2019, 2022),
+        season: ((2, 15), (4, 20)),
+        hours: (5, 13),
+    },
+    SeasonalOutage {
+        sat: Satellite::GOES17,
+        products: uses_abi_infrared,
+        years: (2019, 2022),
+        season: ((8, 15), (10, 20)),
+        hours: (5, 13),
+    },
+];
+
+// The reflectance products are daytime only and made from the visible and near infrared bands.
+fn uses_abi_infrared(prod: Product) -> bool {
+    use Product::*;
+
+    prod.instrument() == Instrument::ABI && !matches!(prod, BRFC | BRFF | LSAC | LSAF)
+}
+
+impl Satellite {
+    /// Whether the satellite was producing all the files of the product at `time`.
+    pub fn data_availability(&self, prod: Product, time: NaiveDateTime) -> DataAvailability {
+        let degraded = SEASONAL_OUTAGES
+            .iter()
+            .filter(|o| o.sat.short_name() == self.short_name() && (o.products)(prod))
+            .any(|o| o.contains(time));
+
+        if degraded {
+            DataAvailability::Degraded
+        } else {
+            DataAvailability::Normal
+        }
+    }
+}
+
+impl SeasonalOutage {
+    fn contains(&self, time: NaiveDateTime) -> bool {
+        let year = time.year();
+        if year < self.years.0 || year > self.years.1 {
+            return false;
+        }
+
+        let ((start_month, start_day), (end_month, end_day)) = self.season;
+        let date = time.date();
+        let in_season = NaiveDate::from_ymd_opt(year, start_month, start_day)
+            .zip(NaiveDate::from_ymd_opt(year, end_month, end_day))
+            .is_some_and(|(start, end)| date >= start && date <= end);
+
+        in_season && time.hour() >= self.hours.0 && time.hour() < self.hours.1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn time(year: i32, month: u32, day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|d| d.and_hms_opt(hour, 0, 0))
+            .unwrap()
+    }
+
+    #[test]
+    fn heat_pipe_outages() {
+        let night = time(2020, 3, 15, 8);
+        let day = time(2020, 3, 15, 18);
+
+        let sat = Satellite::GOES17;
+        assert_eq!(
+            sat.data_availability(Product::FDCF, night),
+            DataAvailability::Degraded
+        );
+        assert_eq!(
+            sat.data_availability(Product::FDCF, day),
+            DataAvailability::Normal
+        );
+        assert_eq!(
+            sat.data_availability(Product::BRFF, night),
+            DataAvailability::Normal
+        );
+        assert_eq!(
+            sat.data_availability(Product::MagGeof, night),
+            DataAvailability::Normal
+        );
+        assert_eq!(
+            Satellite::GOES16.data_availability(Product::FDCF, night),
+            DataAvailability::Normal
+        );
+        assert_eq!(
+            sat.data_availability(Product::FDCF, time(2020, 6, 15, 8)),
+            DataAvailability::Normal
+        );
+    }
+}
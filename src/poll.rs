@@ -0,0 +1,131 @@
+use crate::{
+    archive::Archive,
+    compression::original_name,
+    filename::{scan_start, split_superseded},
+    filter::FileFilter,
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::{naive::NaiveDateTime, Duration};
+use std::{
+    collections::{HashSet, VecDeque},
+    error::Error,
+    path::PathBuf,
+    thread,
+};
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Follow the product as it comes in, for monitoring that runs a few minutes behind the
+    /// satellite. The current and previous hours are listed every `poll_interval`, since files
+    /// still trickle into an hour just after it turns over, and the returned iterator yields each
+    /// new file as soon as it's downloaded, oldest scan first. Files already in the archive when
+    /// polling starts aren't yielded. Only the new files are downloaded each time, so the
+    /// hours are never marked complete. The iterator blocks between polls and never ends; errors
+    /// are logged and polling carries on.
+    pub fn poll(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        poll_interval: Duration,
+    ) -> Result<impl Iterator<Item = PathBuf>, Box<dyn Error>> {
+        let window = move || {
+            let now = chrono::Utc::now().naive_utc();
+            (now - Duration::hours(1), now)
+        };
+
+        let (start, end) = window();
+        Self::validate_dates(sat, prod, start, end)?;
+        let sleep = poll_interval.to_std()?;
+
+        let mut seen: HashSet<String> = self
+            .local_paths(sat, prod, start, end)?
+            .iter()
+            .filter_map(|pth| pth.file_name())
+            .map(|fname| original_name(&fname.to_string_lossy()).to_owned())
+            .collect();
+
+        let archive = self.clone();
+        let mut landed: VecDeque<PathBuf> = VecDeque::new();
+        let mut first = true;
+
+        Ok(std::iter::from_fn(move || loop {
+            if let Some(pth) = landed.pop_front() {
+                return Some(pth);
+            }
+
+            if !first {
+                thread::sleep(sleep);
+            }
+            first = false;
+
+            let (start, end) = window();
+            match archive.poll_once(sat, prod, start, end, &mut seen) {
+                Ok(paths) => landed.extend(paths),
+                Err(err) => log::error!("Error polling {:?} {:?} : {}", sat, prod, err),
+            }
+        }))
+    }
+
+    /// List the hours from `start` through `end` and download the files that aren't in `seen`,
+    /// returning them oldest scan first and adding them to `seen`.
+    fn poll_once(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        seen: &mut HashSet<String>,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let mut hours = vec![];
+        let mut new_fnames = vec![];
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let fnames = self.remote().retrieve_remote_filenames(
+                sat,
+                prod,
+                valid_time,
+                &FileFilter::default(),
+            )?;
+            let fnames = match self.all_versions() {
+                true => fnames,
+                false => split_superseded(fnames).0,
+            };
+
+            let before = new_fnames.len();
+            new_fnames.extend(fnames.into_iter().filter(|fname| !seen.contains(fname)));
+            if new_fnames.len() > before {
+                hours.push((sat, prod, valid_time));
+            }
+        }
+
+        // Anything older than the window won't be listed again.
+        seen.retain(|fname| {
+            scan_start(fname).is_none_or(|scan| scan >= start - Duration::hours(1))
+        });
+
+        if new_fnames.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut paths = self.retrieve(hours, &FileFilter::default().only_files(new_fnames))?;
+        paths.sort_by_key(|pth| {
+            pth.file_name()
+                .and_then(|fname| scan_start(&fname.to_string_lossy()))
+        });
+
+        seen.extend(
+            paths
+                .iter()
+                .filter_map(|pth| pth.file_name())
+                .map(|fname| original_name(&fname.to_string_lossy()).to_owned()),
+        );
+        log::debug!("Poll found {} new files", paths.len());
+
+        Ok(paths)
+    }
+}
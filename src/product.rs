@@ -1,6 +1,12 @@
-use strum::IntoStaticStr;
+use crate::{
+    band::AbiBand, compression::original_name, custom::CustomProduct, error::GoesArchError,
+    filename::has_extension, satellite::Satellite,
+};
+use chrono::NaiveDateTime;
+use std::{error::Error, str::FromStr};
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
 
-#[derive(Clone, Copy, Debug, IntoStaticStr)]
+#[derive(Clone, Copy, Debug, EnumIter, IntoStaticStr)]
 pub enum Product {
     #[strum(serialize = "ABI-L2-FDCC")]
     FDCC,
@@ -8,14 +14,485 @@ pub enum Product {
     FDCM,
     #[strum(serialize = "ABI-L2-FDCF")]
     FDCF,
+    #[strum(serialize = "ABI-L2-ADPC")]
+    ADPC,
+    #[strum(serialize = "ABI-L2-ADPM")]
+    ADPM,
+    #[strum(serialize = "ABI-L2-ADPF")]
+    ADPF,
+    #[strum(serialize = "ABI-L2-LSTC")]
+    LSTC,
+    #[strum(serialize = "ABI-L2-LSTF")]
+    LSTF,
+    #[strum(serialize = "ABI-L2-SSTF")]
+    SSTF,
+    #[strum(serialize = "ABI-L2-RRQPEF")]
+    RRQPEF,
+    #[strum(serialize = "ABI-L2-TPWC")]
+    TPWC,
+    #[strum(serialize = "ABI-L2-TPWF")]
+    TPWF,
+    #[strum(serialize = "ABI-L2-ACHAC")]
+    ACHAC,
+    #[strum(serialize = "ABI-L2-ACHAM")]
+    ACHAM,
+    #[strum(serialize = "ABI-L2-ACHAF")]
+    ACHAF,
+    #[strum(serialize = "ABI-L2-ACMC")]
+    ACMC,
+    #[strum(serialize = "ABI-L2-ACMM")]
+    ACMM,
+    #[strum(serialize = "ABI-L2-ACMF")]
+    ACMF,
+    #[strum(serialize = "ABI-L2-CTPC")]
+    CTPC,
+    #[strum(serialize = "ABI-L2-CTPF")]
+    CTPF,
+    #[strum(serialize = "ABI-L2-ACHTF")]
+    ACHTF,
+    #[strum(serialize = "ABI-L2-CODC")]
+    CODC,
+    #[strum(serialize = "ABI-L2-CODF")]
+    CODF,
+    #[strum(serialize = "ABI-L2-CPSC")]
+    CPSC,
+    #[strum(serialize = "ABI-L2-CPSF")]
+    CPSF,
+    #[strum(serialize = "ABI-L2-DMWC")]
+    DMWC,
+    #[strum(serialize = "ABI-L2-DMWM")]
+    DMWM,
+    #[strum(serialize = "ABI-L2-DMWF")]
+    DMWF,
+    #[strum(serialize = "ABI-L2-DMWVC")]
+    DMWVC,
+    #[strum(serialize = "ABI-L2-DMWVF")]
+    DMWVF,
+    #[strum(serialize = "ABI-L2-DSRC")]
+    DSRC,
+    #[strum(serialize = "ABI-L2-DSRM")]
+    DSRM,
+    #[strum(serialize = "ABI-L2-DSRF")]
+    DSRF,
+    #[strum(serialize = "ABI-L2-LVTPC")]
+    LVTPC,
+    #[strum(serialize = "ABI-L2-LVTPF")]
+    LVTPF,
+    #[strum(serialize = "ABI-L2-LVMPC")]
+    LVMPC,
+    #[strum(serialize = "ABI-L2-LVMPF")]
+    LVMPF,
+    #[strum(serialize = "ABI-L2-DSIC")]
+    DSIC,
+    #[strum(serialize = "ABI-L2-DSIF")]
+    DSIF,
+    #[strum(serialize = "ABI-L2-VAAF")]
+    VAAF,
+    #[strum(serialize = "SUVI-L1b-Fe093")]
+    SuviFe093,
+    #[strum(serialize = "SUVI-L1b-Fe131")]
+    SuviFe131,
+    #[strum(serialize = "SUVI-L1b-Fe171")]
+    SuviFe171,
+    #[strum(serialize = "SUVI-L1b-Fe195")]
+    SuviFe195,
+    #[strum(serialize = "SUVI-L1b-Fe284")]
+    SuviFe284,
+    #[strum(serialize = "SUVI-L1b-He303")]
+    SuviHe303,
+    #[strum(serialize = "MAG-L1b-GEOF")]
+    MagGeof,
+    #[strum(serialize = "EXIS-L1b-SFEU")]
+    ExisSfeu,
+    #[strum(serialize = "EXIS-L1b-SFXR")]
+    ExisSfxr,
+    #[strum(serialize = "SEIS-L1b-EHIS")]
+    SeisEhis,
+    #[strum(serialize = "SEIS-L1b-MPSH")]
+    SeisMpsh,
+    #[strum(serialize = "SEIS-L1b-MPSL")]
+    SeisMpsl,
+    #[strum(serialize = "SEIS-L1b-SGPS")]
+    SeisSgps,
+    #[strum(serialize = "AHI-L1b-FLDK")]
+    AhiFldk,
+    #[strum(serialize = "AHI-L1b-Japan")]
+    AhiJapan,
+    #[strum(serialize = "AHI-L1b-Target")]
+    AhiTarget,
+    #[strum(serialize = "ABI-L2-BRFC")]
+    BRFC,
+    #[strum(serialize = "ABI-L2-BRFF")]
+    BRFF,
+    #[strum(serialize = "ABI-L2-LSAC")]
+    LSAC,
+    #[strum(serialize = "ABI-L2-LSAF")]
+    LSAF,
+    /// Any other product prefix in the bucket, e.g. `ABI-L2-CMIPC`, made with `Product::custom`.
+    /// The prefix is used for both the remote key and the local directory name.
+    #[strum(disabled)]
+    Custom(CustomProduct),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sector {
+    Conus,
+    Meso,
+    FullDisk,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, IntoStaticStr)]
+pub enum Instrument {
+    ABI,
+    SUVI,
+    MAG,
+    EXIS,
+    SEIS,
+    AHI,
+    Unknown,
 }
 
 impl Product {
+    /// A product not built in to the crate, with up to `max_per_hour` files in each hour. Errors
+    /// if `max_per_hour` is zero, or if the prefix is already registered with different values.
+    pub fn custom(prefix: &str, max_per_hour: u32) -> Result<Product, Box<dyn Error>> {
+        Self::custom_with_extensions(prefix, max_per_hour, &["nc"])
+    }
+
+    /// Same as `custom`, for a product whose files aren't NetCDF, e.g. `&["json"]` or
+    /// `&["nc.gz"]`. Errors if there are no extensions.
+    pub fn custom_with_extensions(
+        prefix: &str,
+        max_per_hour: u32,
+        extensions: &[&str],
+    ) -> Result<Product, Box<dyn Error>> {
+        CustomProduct::register(prefix, max_per_hour, extensions).map(Product::Custom)
+    }
+
+    /// Every built in product followed by the custom products registered so far. `iter` only
+    /// yields the built in ones.
+    pub fn all() -> impl Iterator<Item = Product> {
+        Product::iter().chain(CustomProduct::registered().map(Product::Custom))
+    }
+
+    /// The product prefix as used in the remote bucket keys and local archive paths. Prefer this
+    /// to the `&'static str` conversion, which cannot see the prefix of a `Custom` product.
+    pub fn prefix(&self) -> &'static str {
+        match *self {
+            Product::Custom(custom) => custom.prefix(),
+            prod => prod.into(),
+        }
+    }
+
+    /// The extensions of the product's files as the remote serves them, without the leading dot.
+    /// Every built in product is NetCDF except the Himawari imager, which is bzip2 compressed
+    /// HSD.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Product::Custom(custom) => custom.extensions(),
+            Product::AhiFldk | Product::AhiJapan | Product::AhiTarget => &["DAT.bz2"],
+            _ => &["nc"],
+        }
+    }
+
+    /// Whether a file name has one of the product's extensions, ignoring any compression added
+    /// by the archive.
+    pub(crate) fn has_data_extension(&self, fname: &str) -> bool {
+        let fname = original_name(fname);
+        self.extensions()
+            .iter()
+            .any(|ext| has_extension(fname, ext))
+    }
+
+    /// The prefix without the instrument and processing level, e.g. `FDCC` for `ABI-L2-FDCC`.
+    pub fn short_name(&self) -> &'static str {
+        let prefix = self.prefix();
+        prefix.rsplit('-').next().unwrap_or(prefix)
+    }
+
+    pub fn instrument(&self) -> Instrument {
+        match self.prefix().split('-').next() {
+            Some("ABI") => Instrument::ABI,
+            Some("SUVI") => Instrument::SUVI,
+            Some("MAG") => Instrument::MAG,
+            Some("EXIS") => Instrument::EXIS,
+            Some("SEIS") => Instrument::SEIS,
+            Some("AHI") => Instrument::AHI,
+            _ => Instrument::Unknown,
+        }
+    }
+
+    /// The scan sector for imager products, `None` for the other instruments.
+    pub fn sector(&self) -> Option<Sector> {
+        match self.instrument() {
+            Instrument::ABI => {}
+            Instrument::AHI if self.short_name() == "FLDK" => return Some(Sector::FullDisk),
+            _ => return None,
+        }
+
+        match self.short_name().chars().last() {
+            Some('C') => Some(Sector::Conus),
+            Some('M') => Some(Sector::Meso),
+            Some('F') => Some(Sector::FullDisk),
+            _ => None,
+        }
+    }
+
+    /// Whether the satellite carries the instrument that produces this product, at any time. See
+    /// `available_at` for whether it was being produced at a particular time.
+    pub fn available_on(&self, sat: Satellite) -> bool {
+        use Satellite::*;
+
+        match (sat, self.instrument()) {
+            (HIMAWARI8 | HIMAWARI9, Instrument::AHI) => true,
+            (HIMAWARI8 | HIMAWARI9, _) => false,
+            (GOES16 | GOES17 | GOES18 | GOES19, Instrument::AHI) => false,
+            // The GOES-R series all carry the same instrument suite.
+            (GOES16 | GOES17 | GOES18 | GOES19, _) => true,
+            (Custom(_), _) => true,
+        }
+    }
+
+    /// Whether the satellite was producing this product at `valid_time`, between its operational
+    /// dates for the product. Outages and degraded periods inside those dates aren't counted, see
+    /// `Satellite::data_availability`.
+    pub fn available_at(&self, sat: Satellite, valid_time: NaiveDateTime) -> bool {
+        self.available_on(sat)
+            && valid_time >= sat.earliest_operational_date(*self)
+            && sat
+                .latest_operational_date(*self)
+                .is_none_or(|latest| valid_time <= latest)
+    }
+
+    /// Nominal minutes between scans of the same domain.
+    pub fn cadence_minutes(&self) -> f64 {
+        use Product::*;
+
+        match *self {
+            FDCM | ADPM | ACHAM | ACMM => 1.0,
+            MagGeof | ExisSfeu | ExisSfxr | SeisEhis | SeisMpsh | SeisMpsl | SeisSgps => 1.0,
+            AhiJapan | AhiTarget => 2.5,
+            SuviFe093 | SuviFe131 | SuviFe171 | SuviFe195 | SuviFe284 | SuviHe303 => 4.0,
+            DMWC | DMWVC => 15.0,
+            LSTC | LSTF | SSTF | TPWF | DSRC | DSRM | DSRF | LVTPF | LVMPF | DSIF | BRFC | BRFF
+            | LSAC | LSAF => 60.0,
+            Custom(custom) => 60.0 / custom.max_per_hour() as f64,
+            prod => match prod.sector() {
+                Some(Sector::FullDisk) => 10.0,
+                _ => 5.0,
+            },
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        use Product::*;
+
+        match *self {
+            FDCC => "Fire/Hot Spot Characterization, CONUS",
+            FDCM => "Fire/Hot Spot Characterization, Mesoscale",
+            FDCF => "Fire/Hot Spot Characterization, Full Disk",
+            ADPC => "Aerosol Detection, CONUS",
+            ADPM => "Aerosol Detection, Mesoscale",
+            ADPF => "Aerosol Detection, Full Disk",
+            LSTC => "Land Surface Temperature, CONUS",
+            LSTF => "Land Surface Temperature, Full Disk",
+            SSTF => "Sea Surface Temperature, Full Disk",
+            RRQPEF => "Rainfall Rate / QPE, Full Disk",
+            TPWC => "Total Precipitable Water, CONUS",
+            TPWF => "Total Precipitable Water, Full Disk",
+            ACHAC => "Cloud Top Height, CONUS",
+            ACHAM => "Cloud Top Height, Mesoscale",
+            ACHAF => "Cloud Top Height, Full Disk",
+            ACMC => "Clear Sky Mask, CONUS",
+            ACMM => "Clear Sky Mask, Mesoscale",
+            ACMF => "Clear Sky Mask, Full Disk",
+            CTPC => "Cloud Top Pressure, CONUS",
+            CTPF => "Cloud Top Pressure, Full Disk",
+            ACHTF => "Cloud Top Temperature, Full Disk",
+            CODC => "Cloud Optical Depth, CONUS",
+            CODF => "Cloud Optical Depth, Full Disk",
+            CPSC => "Cloud Particle Size, CONUS",
+            CPSF => "Cloud Particle Size, Full Disk",
+            DMWC => "Derived Motion Winds, CONUS",
+            DMWM => "Derived Motion Winds, Mesoscale",
+            DMWF => "Derived Motion Winds, Full Disk",
+            DMWVC => "Derived Motion Winds, Clear Sky Water Vapor, CONUS",
+            DMWVF => "Derived Motion Winds, Clear Sky Water Vapor, Full Disk",
+            DSRC => "Downward Shortwave Radiation, CONUS",
+            DSRM => "Downward Shortwave Radiation, Mesoscale",
+            DSRF => "Downward Shortwave Radiation, Full Disk",
+            LVTPC => "Legacy Vertical Temperature Profile, CONUS",
+            LVTPF => "Legacy Vertical Temperature Profile, Full Disk",
+            LVMPC => "Legacy Vertical Moisture Profile, CONUS",
+            LVMPF => "Legacy Vertical Moisture Profile, Full Disk",
+            DSIC => "Derived Stability Indices, CONUS",
+            DSIF => "Derived Stability Indices, Full Disk",
+            VAAF => "Volcanic Ash, Full Disk",
+            SuviFe093 => "Solar Ultraviolet Imager, Fe 093 channel",
+            SuviFe131 => "Solar Ultraviolet Imager, Fe 131 channel",
+            SuviFe171 => "Solar Ultraviolet Imager, Fe 171 channel",
+            SuviFe195 => "Solar Ultraviolet Imager, Fe 195 channel",
+            SuviFe284 => "Solar Ultraviolet Imager, Fe 284 channel",
+            SuviHe303 => "Solar Ultraviolet Imager, He 303 channel",
+            MagGeof => "Magnetometer, geomagnetic field",
+            ExisSfeu => "EXIS, solar flux EUV",
+            ExisSfxr => "EXIS, solar flux X-ray",
+            SeisEhis => "SEISS, energetic heavy ions",
+            SeisMpsh => "SEISS, magnetospheric particles, high energy",
+            SeisMpsl => "SEISS, magnetospheric particles, low energy",
+            SeisSgps => "SEISS, solar and galactic protons",
+            BRFC => "Bidirectional Reflectance Factor, CONUS",
+            BRFF => "Bidirectional Reflectance Factor, Full Disk",
+            LSAC => "Land Surface Albedo, CONUS",
+            LSAF => "Land Surface Albedo, Full Disk",
+            AhiFldk => "Himawari Imager, Full Disk",
+            AhiJapan => "Himawari Imager, Japan Area",
+            AhiTarget => "Himawari Imager, Target Area",
+            Custom(_) => "Custom product",
+        }
+    }
+
     pub fn max_num_per_hour(&self) -> i32 {
+        self.scans_per_hour() * self.files_per_scan()
+    }
+
+    /// The number of files expected in a complete hour, accounting for the scan mode the
+    /// satellite was operating in at `valid_time`.
+    pub fn expected_files_per_hour(&self, sat: Satellite, valid_time: NaiveDateTime) -> i32 {
+        // Before the switch to ABI scan mode 6 the full disk was scanned every 15 minutes.
+        if self.sector() == Some(Sector::FullDisk)
+            && self.scans_per_hour() == 6
+            && valid_time < sat.mode_6_start()
+        {
+            return 4 * self.files_per_scan();
+        }
+
+        self.max_num_per_hour()
+    }
+
+    fn scans_per_hour(&self) -> i32 {
+        use Product::*;
+
         match *self {
-            Product::FDCM => 120,
-            Product::FDCC => 12,
-            Product::FDCF => 6,
+            // Mesoscale sectors, both domains at 1 minute each
+            FDCM | ADPM | ACHAM | ACMM => 120,
+            // Space weather instruments, one minute files
+            MagGeof | ExisSfeu | ExisSfxr | SeisEhis | SeisMpsh | SeisMpsl | SeisSgps => 60,
+            // SUVI, long and short exposures every 4 minute cycle
+            SuviFe093 | SuviFe131 | SuviFe171 | SuviFe195 | SuviFe284 | SuviHe303 => 30,
+            // Mesoscale winds, every 5 minutes
+            DMWM => 12,
+            // Himawari regional areas, every 2.5 minutes
+            AhiJapan | AhiTarget => 24,
+            // CONUS, every 5 minutes
+            FDCC | ADPC | TPWC | ACHAC | ACMC | CTPC | CODC | CPSC | LVTPC | LVMPC | DSIC => 12,
+            // Full disk, every 10 minutes
+            FDCF | ADPF | RRQPEF | ACHAF | ACMF | CTPF | ACHTF | CODF | CPSF | DMWF | DMWVF
+            | VAAF | AhiFldk => 6,
+            // CONUS winds, every 15 minutes
+            DMWC | DMWVC => 4,
+            // Mesoscale hourly, both domains
+            DSRM => 2,
+            // Hourly products
+            LSTC | LSTF | SSTF | TPWF | DSRC | DSRF | LVTPF | LVMPF | DSIF | BRFC | BRFF | LSAC
+            | LSAF => 1,
+            Custom(custom) => custom.max_per_hour() as i32,
         }
     }
+
+    /// The bands of a band-resolved product, `files_per_band` files per band are produced for
+    /// each scan. Empty for products that aren't band resolved.
+    pub fn bands(&self) -> &'static [AbiBand] {
+        use AbiBand::*;
+        use Product::*;
+
+        match *self {
+            // The tracking bands
+            DMWC | DMWM | DMWF => &[C02, C07, C08, C09, C10, C14],
+            _ => &[],
+        }
+    }
+
+    /// The files produced for each band of a band-resolved product in a scan.
+    fn files_per_band(&self) -> i32 {
+        match *self {
+            // A file per mesoscale domain, M1 and M2
+            Product::DMWM => 2,
+            _ => 1,
+        }
+    }
+
+    fn files_per_scan(&self) -> i32 {
+        use Product::*;
+
+        match *self {
+            // 16 bands split into 10 segments
+            AhiFldk => 160,
+            AhiJapan | AhiTarget => 16,
+            prod if !prod.bands().is_empty() => prod.bands().len() as i32 * prod.files_per_band(),
+            _ => 1,
+        }
+    }
+}
+
+impl FromStr for Product {
+    type Err = GoesArchError;
+
+    /// Parses either the full prefix, e.g. "ABI-L2-FDCC", or the short name, e.g. "FDCC". Custom
+    /// products are never produced.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        Product::iter()
+            .find(|p| p.prefix().eq_ignore_ascii_case(s) || p.short_name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| GoesArchError::new(&format!("Unknown product: {}", s)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::archive::is_data_file;
+    use std::path::Path;
+
+    #[test]
+    fn data_files_go_by_product_extensions() {
+        let json = Product::custom_with_extensions("GLM-L3-TEST", 60, &["json"]).unwrap();
+        assert_eq!(json.extensions(), &["json"]);
+        assert_eq!(Product::FDCC.extensions(), &["nc"]);
+
+        let nc = "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804088.nc";
+        assert!(is_data_file(Path::new(nc)));
+        assert!(is_data_file(Path::new(&format!("{}.zip", nc))));
+        assert!(!is_data_file(Path::new(&format!("{}.zip.sha256", nc))));
+
+        let fname = "OR_GLM-L3-TEST_G16_s20231521801172_e20231521803545_c20231521804088";
+        assert!(is_data_file(Path::new(&format!("{}.json.zip", fname))));
+        assert!(!is_data_file(Path::new(&format!("{}.nc", fname))));
+    }
+
+    #[test]
+    fn all_includes_custom_products() {
+        let prod = Product::custom("ABI-L2-ALLTEST", 6).unwrap();
+
+        assert!(Product::all().any(|p| p.prefix() == prod.prefix()));
+        assert!(!Product::iter().any(|p| p.prefix() == prod.prefix()));
+        assert!(Product::all()
+            .skip_while(|p| !matches!(p, Product::Custom(_)))
+            .all(|p| matches!(p, Product::Custom(_))));
+        assert!(Product::custom("ABI-L2-ALLTEST", 12).is_err());
+    }
+
+    #[test]
+    fn winds_count_files_per_band() {
+        // Six tracking bands, and a file per band for each mesoscale domain.
+        assert_eq!(Product::DMWC.max_num_per_hour(), 4 * 6);
+        assert_eq!(Product::DMWF.max_num_per_hour(), 6 * 6);
+        assert_eq!(Product::DMWM.max_num_per_hour(), 12 * 6 * 2);
+        assert_eq!(Product::DMWM.cadence_minutes(), 5.0);
+
+        // The clear sky water vapor winds aren't split by band.
+        assert!(Product::DMWVC.bands().is_empty());
+        assert_eq!(Product::DMWVC.max_num_per_hour(), 4);
+    }
 }
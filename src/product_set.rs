@@ -0,0 +1,47 @@
+use crate::product::Product;
+
+/// A group of products to retrieve together.
+#[derive(Clone, Debug)]
+pub struct ProductSet {
+    products: Vec<Product>,
+}
+
+impl ProductSet {
+    pub fn new(products: &[Product]) -> Self {
+        let mut set = ProductSet { products: vec![] };
+        for &prod in products {
+            set = set.with(prod);
+        }
+
+        set
+    }
+
+    /// The fire detection and characterization products for all sectors.
+    pub fn fire_suite() -> Self {
+        use Product::*;
+
+        Self::new(&[FDCC, FDCM, FDCF])
+    }
+
+    /// The cloud mask and cloud property products for all sectors.
+    pub fn cloud_suite() -> Self {
+        use Product::*;
+
+        Self::new(&[
+            ACMC, ACMM, ACMF, ACHAC, ACHAM, ACHAF, CTPC, CTPF, ACHTF, CODC, CODF, CPSC, CPSF,
+        ])
+    }
+
+    /// Add a product to the set, ignoring it if it is already present.
+    pub fn with(mut self, prod: Product) -> Self {
+        if !self.products.iter().any(|p| p.prefix() == prod.prefix()) {
+            self.products.push(prod);
+        }
+
+        self
+    }
+
+    pub fn products(&self) -> &[Product] {
+        &self.products
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{
+    archive::Archive,
+    compression::{open_archived_file, original_name},
+    filename::split_superseded,
+    filter::FileFilter,
+    product::Product,
+    remote::{RemoteArchive, RemoteSink},
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use std::{error::Error, io::Read};
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Upload the archived files of the product from `start` through `end` to a writable
+    /// remote, e.g. a team bucket built with `AmazonS3NoaaBigData::builder` and credentials (a
+    /// Google Cloud Storage bucket works through its S3 compatible endpoint), so others can use
+    /// it as their remote. Files are uploaded decompressed under the same keys as
+    /// the NOAA buckets, and files the sink already has are skipped. Only files already in the
+    /// archive are uploaded. Returns the number of files uploaded.
+    pub fn push_to<S: RemoteSink>(
+        &self,
+        sink: &S,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<usize, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+        let filter = FileFilter::default();
+
+        // One buffer is reused for every file.
+        let mut data = vec![];
+        let mut num_files = 0;
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let dir = self.build_path(sat, prod, valid_time);
+            if !dir.is_dir() {
+                continue;
+            }
+
+            let (paths, _) = split_superseded(Self::dir_files(&dir, &filter));
+            if paths.is_empty() {
+                continue;
+            }
+
+            let existing = sink.retrieve_remote_filenames(sat, prod, valid_time, &filter)?;
+
+            for pth in paths {
+                let fname = match pth.file_name() {
+                    Some(fname) => original_name(&fname.to_string_lossy()).to_owned(),
+                    None => continue,
+                };
+
+                if existing.contains(&fname) {
+                    log::debug!("Sink already has {}", fname);
+                    continue;
+                }
+
+                data.clear();
+                let read = open_archived_file(&pth)
+                    .and_then(|mut reader| Ok(reader.read_to_end(&mut data)?));
+                if let Err(err) = read {
+                    log::error!("Error reading {:?}, not uploading it : {}", pth, err);
+                    continue;
+                }
+
+                sink.upload_remote_file(sat, prod, valid_time, &fname, &data)?;
+                log::debug!("Uploaded {}", fname);
+                num_files += 1;
+            }
+        }
+
+        log::info!("Uploaded {} files of {:?} {:?}", num_files, sat, prod);
+
+        Ok(num_files)
+    }
+}
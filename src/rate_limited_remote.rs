@@ -0,0 +1,335 @@
+use crate::{
+    error::GoesArchError,
+    filter::FileFilter,
+    listing::ListedFile,
+    product::Product,
+    remote::{ConditionalFile, RemoteArchive, RemoteMetadata},
+    satellite::Satellite,
+};
+use chrono::naive::{NaiveDate, NaiveDateTime};
+use std::{
+    error::Error,
+    io::Write,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Limit the requests per second and bytes per second sent through a remote. The limits are
+/// shared by every clone of the remote, so they hold across all of the downloader threads, e.g.
+/// to keep a long backfill under a provider's throttling threshold or off the office uplink.
+#[derive(Debug, Clone)]
+pub struct RateLimitedRemote<R> {
+    remote: R,
+    requests: Option<Arc<TokenBucket>>,
+    bytes: Option<Arc<TokenBucket>>,
+}
+
+impl<R> RateLimitedRemote<R>
+where
+    R: RemoteArchive,
+{
+    /// Wrap the remote with no limits, add them with `requests_per_second` and
+    /// `bytes_per_second`.
+    pub fn new(remote: R) -> Self {
+        RateLimitedRemote {
+            remote,
+            requests: None,
+            bytes: None,
+        }
+    }
+
+    /// Start at most this many requests per second, with bursts of up to a second's worth.
+    /// Errors if the rate isn't a positive number.
+    pub fn requests_per_second(mut self, rate: f64) -> Result<Self, Box<dyn Error>> {
+        self.requests = Some(Arc::new(TokenBucket::new(rate)?));
+        Ok(self)
+    }
+
+    /// Download at most this many bytes per second, with bursts of up to a second's worth.
+    /// Errors if the rate isn't a positive number.
+    pub fn bytes_per_second(mut self, rate: f64) -> Result<Self, Box<dyn Error>> {
+        self.bytes = Some(Arc::new(TokenBucket::new(rate)?));
+        Ok(self)
+    }
+
+    fn start_request(&self) {
+        if let Some(bucket) = &self.requests {
+            bucket.take(1.0);
+        }
+    }
+
+    fn received(&self, num_bytes: usize) {
+        if let Some(bucket) = &self.bytes {
+            bucket.take(num_bytes as f64);
+        }
+    }
+}
+
+impl<R> RemoteArchive for RateLimitedRemote<R>
+where
+    R: RemoteArchive,
+{
+    fn connect(num_max_downloads: usize) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        Ok(Self::new(R::connect(num_max_downloads)?))
+    }
+
+    fn retrieve_remote_filenames(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        self.start_request();
+        self.remote
+            .retrieve_remote_filenames(sat, prod, valid_hour, filter)
+    }
+
+    fn retrieve_remote_filenames_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, String)>, Box<dyn Error>> {
+        self.start_request();
+        self.remote
+            .retrieve_remote_filenames_for_day(sat, prod, day, filter)
+    }
+
+    fn lists_whole_days(&self) -> bool {
+        self.remote.lists_whole_days()
+    }
+
+    fn retrieve_remote_listing(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<ListedFile>, Box<dyn Error>> {
+        self.start_request();
+        self.remote
+            .retrieve_remote_listing(sat, prod, valid_hour, filter)
+    }
+
+    fn retrieve_remote_listing_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, ListedFile)>, Box<dyn Error>> {
+        self.start_request();
+        self.remote
+            .retrieve_remote_listing_for_day(sat, prod, day, filter)
+    }
+
+    fn retrieve_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.start_request();
+        let data = self
+            .remote
+            .retrieve_remote_file(sat, prod, valid_hour, remote_path)?;
+        self.received(data.len());
+
+        Ok(data)
+    }
+
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        self.start_request();
+
+        // Throttle as the data arrives so a large file doesn't go out in one burst.
+        let mut throttled = ThrottledWriter {
+            inner: writer,
+            bytes: self.bytes.as_deref(),
+        };
+
+        self.remote
+            .retrieve_remote_file_to(sat, prod, valid_hour, remote_path, &mut throttled)
+    }
+
+    fn retrieve_remote_range(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.start_request();
+        let data =
+            self.remote
+                .retrieve_remote_range(sat, prod, valid_hour, remote_path, start, end)?;
+        self.received(data.len());
+
+        Ok(data)
+    }
+
+    fn retrieve_remote_file_if_changed(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        etag: &str,
+    ) -> Result<ConditionalFile, Box<dyn Error>> {
+        self.start_request();
+        let file = self.remote.retrieve_remote_file_if_changed(
+            sat,
+            prod,
+            valid_hour,
+            remote_path,
+            etag,
+        )?;
+        if let ConditionalFile::Modified { data, .. } = &file {
+            self.received(data.len());
+        }
+
+        Ok(file)
+    }
+
+    fn retrieve_remote_metadata(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        self.start_request();
+        self.remote
+            .retrieve_remote_metadata(sat, prod, valid_hour, remote_path)
+    }
+
+    fn retrieve_static_file(
+        &self,
+        sat: Satellite,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.start_request();
+        let data = self.remote.retrieve_static_file(sat, remote_path)?;
+        self.received(data.len());
+
+        Ok(data)
+    }
+
+    fn retrieve_url(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.start_request();
+        let data = self.remote.retrieve_url(url)?;
+        self.received(data.len());
+
+        Ok(data)
+    }
+
+    fn max_downloads(&self) -> usize {
+        self.remote.max_downloads()
+    }
+}
+
+/// A token bucket that lets callers go into debt. Taking more than is available succeeds, but the
+/// caller sleeps until the bucket would have refilled to zero, so the next caller waits its turn
+/// behind the debt too.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Result<Self, GoesArchError> {
+        if !rate.is_finite() || rate <= 0.0 {
+            return Err(GoesArchError::new(&format!("Invalid rate limit: {}", rate)));
+        }
+
+        Ok(TokenBucket {
+            rate,
+            state: Mutex::new((rate, Instant::now())),
+        })
+    }
+
+    fn take(&self, amount: f64) {
+        let wait = self.debt_after(amount);
+
+        if wait > 0.0 {
+            // A tiny rate can put the wait past what a Duration holds.
+            thread::sleep(Duration::try_from_secs_f64(wait).unwrap_or(Duration::MAX));
+        }
+    }
+
+    /// Take the tokens, returning how many seconds it will take to pay off any debt.
+    fn debt_after(&self, amount: f64) -> f64 {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        let (tokens, last) = &mut *state;
+
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.rate).min(self.rate);
+        *last = now;
+        *tokens -= amount;
+
+        if *tokens < 0.0 {
+            -*tokens / self.rate
+        } else {
+            0.0
+        }
+    }
+}
+
+struct ThrottledWriter<'a> {
+    inner: &'a mut dyn Write,
+    bytes: Option<&'a TokenBucket>,
+}
+
+impl Write for ThrottledWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let num_bytes = self.inner.write(buf)?;
+        if let Some(bucket) = self.bytes {
+            bucket.take(num_bytes as f64);
+        }
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rates_must_be_positive() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(TokenBucket::new(rate).is_err(), "{}", rate);
+        }
+        assert!(TokenBucket::new(f64::MIN_POSITIVE).is_ok());
+    }
+
+    #[test]
+    fn debt_is_paid_at_the_rate() {
+        let bucket = TokenBucket::new(10.0).unwrap();
+
+        // Starts with a second's worth
+        assert_eq!(bucket.debt_after(10.0), 0.0);
+
+        let wait = bucket.debt_after(5.0);
+        assert!(wait > 0.45 && wait <= 0.5, "{}", wait);
+    }
+}
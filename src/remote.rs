@@ -1,20 +1,116 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
 
-use crate::{product::Product, satellite::Satellite};
-use chrono::naive::NaiveDateTime;
+use crate::{
+    error::GoesArchError, filter::FileFilter, listing::ListedFile, product::Product,
+    satellite::Satellite,
+};
+use chrono::{
+    naive::{NaiveDate, NaiveDateTime},
+    Datelike, Duration, Timelike,
+};
+
+/// What a remote knows about a file, for checking local copies against it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteMetadata {
+    /// Size in bytes.
+    pub size: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<NaiveDateTime>,
+}
+
+/// The result of a conditional download.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConditionalFile {
+    /// The file still has the ETag it was checked against.
+    Unchanged,
+    Modified {
+        data: Vec<u8>,
+        etag: Option<String>,
+    },
+}
 
 pub trait RemoteArchive: Clone + Send {
     fn connect(max_downloads: usize) -> Result<Self, Box<dyn Error>>
     where
         Self: Sized;
 
+    /// List the files available for the hour, skipping any that don't pass the filter.
     fn retrieve_remote_filenames(
         &self,
         sat: Satellite,
         prod: Product,
         valid_hour: NaiveDateTime,
+        filter: &FileFilter,
     ) -> Result<Vec<String>, Box<dyn Error>>;
 
+    /// List the files for every directory of the day, paired with the start of the directory
+    /// interval they belong to. Remotes that can list a whole day in one request override this
+    /// and `lists_whole_days`, the default lists each directory in turn.
+    fn retrieve_remote_filenames_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, String)>, Box<dyn Error>> {
+        let step = sat.directory_interval();
+        let start = day.and_hms_opt(0, 0, 0).unwrap();
+
+        let mut fnames = vec![];
+        let mut valid_time = start;
+        while valid_time < start + Duration::days(1) {
+            for fname in self.retrieve_remote_filenames(sat, prod, valid_time, filter)? {
+                fnames.push((valid_time, fname));
+            }
+            valid_time += step;
+        }
+
+        Ok(fnames)
+    }
+
+    /// Whether `retrieve_remote_filenames_for_day` lists the whole day at once, so it's cheaper
+    /// than listing more than one of its directories separately.
+    fn lists_whole_days(&self) -> bool {
+        false
+    }
+
+    /// Same as `retrieve_remote_filenames`, with the size and ETag of each file when the listing
+    /// has them, so they don't need a metadata request each. Remotes whose listings carry them
+    /// override this, the default only has the names.
+    fn retrieve_remote_listing(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<ListedFile>, Box<dyn Error>> {
+        Ok(self
+            .retrieve_remote_filenames(sat, prod, valid_hour, filter)?
+            .into_iter()
+            .map(ListedFile::named)
+            .collect())
+    }
+
+    /// Same as `retrieve_remote_filenames_for_day`, with the size and ETag of each file when the
+    /// listing has them.
+    fn retrieve_remote_listing_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, ListedFile)>, Box<dyn Error>> {
+        Ok(self
+            .retrieve_remote_filenames_for_day(sat, prod, day, filter)?
+            .into_iter()
+            .map(|(valid_time, fname)| (valid_time, ListedFile::named(fname)))
+            .collect())
+    }
+
     fn retrieve_remote_file(
         &self,
         sat: Satellite,
@@ -23,5 +119,226 @@ pub trait RemoteArchive: Clone + Send {
         remote_path: &str,
     ) -> Result<Vec<u8>, Box<dyn Error>>;
 
+    /// Write the file to `writer` as it downloads instead of holding it all in memory, returning
+    /// the number of bytes written. Remotes that can't stream fall back to buffering the file.
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        let data = self.retrieve_remote_file(sat, prod, valid_hour, remote_path)?;
+        writer.write_all(&data)?;
+
+        Ok(data.len() as u64)
+    }
+
+    /// Retrieve the bytes from `start` up to and including `end` of a file, or through the end of
+    /// the file if `end` is `None`. Useful for resuming downloads or reading just the header.
+    /// Remotes without ranged reads download the whole file and slice it.
+    fn retrieve_remote_range(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        check_range(start, end)?;
+
+        let data = self.retrieve_remote_file(sat, prod, valid_hour, remote_path)?;
+        let start = (start as usize).min(data.len());
+        let end = end.map_or(data.len(), |end| (end as usize + 1).min(data.len()));
+
+        Ok(data[start..end].to_vec())
+    }
+
+    /// Download the file only if its ETag no longer matches `etag`, e.g. because NOAA reprocessed
+    /// it. Remotes without conditional requests compare the metadata first.
+    fn retrieve_remote_file_if_changed(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        etag: &str,
+    ) -> Result<ConditionalFile, Box<dyn Error>> {
+        let current = self
+            .retrieve_remote_metadata(sat, prod, valid_hour, remote_path)
+            .ok()
+            .and_then(|meta| meta.etag);
+
+        if current.as_deref() == Some(etag) {
+            return Ok(ConditionalFile::Unchanged);
+        }
+
+        Ok(ConditionalFile::Modified {
+            data: self.retrieve_remote_file(sat, prod, valid_hour, remote_path)?,
+            etag: current,
+        })
+    }
+
+    /// Look up the size, ETag, and modification time of a file without downloading it.
+    fn retrieve_remote_metadata(
+        &self,
+        _sat: Satellite,
+        _prod: Product,
+        _valid_hour: NaiveDateTime,
+        _remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        Err(Box::new(GoesArchError::new(
+            "Metadata not supported by this remote.",
+        )))
+    }
+
+    /// Retrieve an auxiliary file that isn't tied to a valid time, such as fixed grid navigation
+    /// files. The path is relative to the root of the satellite's storage.
+    fn retrieve_static_file(
+        &self,
+        _sat: Satellite,
+        _remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        Err(Box::new(GoesArchError::new(
+            "Static files not supported by this remote.",
+        )))
+    }
+
+    /// Download a file from any URL through the remote's HTTP client, so it gets the same proxy
+    /// and timeout settings, e.g. a manifest shared on a web server.
+    fn retrieve_url(&self, _url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        Err(Box::new(GoesArchError::new(
+            "URLs not supported by this remote.",
+        )))
+    }
+
     fn max_downloads(&self) -> usize;
 }
+
+/// A remote that files can be uploaded to, laid out like the NOAA buckets so it can be read
+/// back as a `RemoteArchive`, e.g. a shared mirror in a team's own bucket.
+pub trait RemoteSink: RemoteArchive {
+    fn upload_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        fname: &str,
+        data: &[u8],
+    ) -> Result<(), Box<dyn Error>>;
+}
+
+/// The key prefix of the directory holding the files for `valid_hour` in the NOAA Open Data
+/// Dissemination buckets and their mirrors.
+pub(crate) fn noaa_key_prefix(sat: Satellite, prod: Product, valid_hour: NaiveDateTime) -> String {
+    let prod = prod.prefix();
+    let year = valid_hour.year();
+    let hour = valid_hour.hour();
+
+    match sat {
+        // Himawari keys use month/day and have a directory for every 10 minutes.
+        Satellite::HIMAWARI8 | Satellite::HIMAWARI9 => {
+            let month = valid_hour.month();
+            let day = valid_hour.day();
+            let minute = valid_hour.minute() / 10 * 10;
+
+            format!(
+                "{}/{}/{:02}/{:02}/{:02}{:02}/",
+                prod, year, month, day, hour, minute
+            )
+        }
+        _ => {
+            let day = valid_hour.ordinal();
+
+            format!("{}/{}/{:03}/{:02}/", prod, year, day, hour)
+        }
+    }
+}
+
+/// The key prefix holding all the directories for a day in the NOAA buckets and their mirrors.
+pub(crate) fn noaa_day_prefix(sat: Satellite, prod: Product, day: NaiveDate) -> String {
+    let prod = prod.prefix();
+    let year = day.year();
+
+    match sat {
+        Satellite::HIMAWARI8 | Satellite::HIMAWARI9 => {
+            format!("{}/{}/{:02}/{:02}/", prod, year, day.month(), day.day())
+        }
+        _ => format!("{}/{}/{:03}/", prod, year, day.ordinal()),
+    }
+}
+
+/// Split a key from a day listing into the start of its directory interval and its file name.
+/// `rest` is the key with the day prefix removed, e.g. `18/OR_ABI-L2-...nc` or
+/// `0210/HS_H08_...DAT.bz2` for Himawari.
+pub(crate) fn split_day_key(day: NaiveDate, rest: &str) -> Option<(NaiveDateTime, String)> {
+    let (dir, fname) = rest.split_once('/')?;
+    if fname.is_empty() || fname.contains('/') {
+        return None;
+    }
+
+    let (hour, minute) = match dir.len() {
+        2 => (dir.parse().ok()?, 0),
+        4 => (dir[..2].parse().ok()?, dir[2..].parse().ok()?),
+        _ => return None,
+    };
+
+    Some((day.and_hms_opt(hour, minute, 0)?, fname.to_owned()))
+}
+
+pub(crate) fn check_range(start: u64, end: Option<u64>) -> Result<(), GoesArchError> {
+    match end {
+        Some(end) if end < start => Err(GoesArchError::new("Invalid byte range.")),
+        _ => Ok(()),
+    }
+}
+
+/// The path of a static file relative to the bucket, refusing anything that could climb out of
+/// it, e.g. `..` or a drive prefix. A leading `/` is allowed since bucket keys are often written
+/// that way.
+pub(crate) fn static_file_path(remote_path: &str) -> Result<PathBuf, GoesArchError> {
+    let pth = Path::new(remote_path.trim_start_matches('/'));
+
+    if pth.as_os_str().is_empty()
+        || !pth
+            .components()
+            .all(|comp| matches!(comp, Component::Normal(_)))
+    {
+        return Err(GoesArchError::new(&format!(
+            "Invalid static file path: {}",
+            remote_path
+        )));
+    }
+
+    Ok(pth.to_path_buf())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn byte_ranges() {
+        assert!(check_range(0, None).is_ok());
+        assert!(check_range(10, None).is_ok());
+        assert!(check_range(0, Some(0)).is_ok());
+        assert!(check_range(5, Some(9)).is_ok());
+        assert!(check_range(9, Some(5)).is_err());
+    }
+
+    #[test]
+    fn static_file_paths_stay_in_bucket() {
+        assert_eq!(
+            static_file_path("/ABI-L2-FDCF/land_mask.nc").unwrap(),
+            PathBuf::from("ABI-L2-FDCF/land_mask.nc")
+        );
+
+        assert!(static_file_path("../secrets").is_err());
+        assert!(static_file_path("ABI-L2-FDCF/../../secrets").is_err());
+        assert!(static_file_path("./land_mask.nc").is_err());
+        assert!(static_file_path("/").is_err());
+        assert!(static_file_path("").is_err());
+    }
+}
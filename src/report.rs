@@ -0,0 +1,104 @@
+use crate::{
+    archive::Archive, filename::scanned_within, filter::FileFilter, product::Product,
+    remote::RemoteArchive, satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use std::{error::Error, io, path::PathBuf};
+
+/// Something that went wrong during a retrieval without stopping it.
+#[derive(Clone, Debug)]
+pub struct RetrievalFailure {
+    pub sat: Satellite,
+    pub prod: Product,
+    pub valid_time: NaiveDateTime,
+    /// The remote file that failed, or `None` if the whole directory did, e.g. it couldn't be
+    /// listed.
+    pub fname: Option<String>,
+    pub error: String,
+    /// Whether trying again later might succeed, e.g. after a timeout or a server error, as
+    /// opposed to a missing file or a full disk.
+    pub retryable: bool,
+}
+
+impl RetrievalFailure {
+    pub(crate) fn new(
+        sat: Satellite,
+        prod: Product,
+        valid_time: NaiveDateTime,
+        fname: Option<&str>,
+        err: &(dyn Error + 'static),
+    ) -> Self {
+        RetrievalFailure {
+            sat,
+            prod,
+            valid_time,
+            fname: fname.map(ToOwned::to_owned),
+            error: err.to_string(),
+            retryable: is_retryable(err),
+        }
+    }
+}
+
+/// The files a retrieval returned along with everything that failed along the way.
+#[derive(Clone, Debug, Default)]
+pub struct RetrievalReport {
+    pub paths: Vec<PathBuf>,
+    pub failures: Vec<RetrievalFailure>,
+}
+
+impl RetrievalReport {
+    /// True if nothing failed.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Whether an error looks transient. Errors that can't be told apart, like those remotes report
+/// as plain messages, are assumed to be.
+pub(crate) fn is_retryable(err: &(dyn Error + 'static)) -> bool {
+    if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+        return match err.status() {
+            Some(status) => status.is_server_error() || status.as_u16() == 429,
+            None => !err.is_builder() && !err.is_redirect(),
+        };
+    }
+
+    if let Some(err) = err.downcast_ref::<io::Error>() {
+        return !matches!(
+            err.kind(),
+            io::ErrorKind::NotFound
+                | io::ErrorKind::PermissionDenied
+                | io::ErrorKind::InvalidInput
+                | io::ErrorKind::InvalidData
+                | io::ErrorKind::StorageFull
+                | io::ErrorKind::ReadOnlyFilesystem
+        );
+    }
+
+    true
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Same as `retrieve_paths_filtered`, but also reports every listing, download, and save
+    /// that failed, so automated jobs can decide whether to try again or raise an alarm.
+    pub fn retrieve_paths_report(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<RetrievalReport, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let hours = Self::time_steps(sat.directory_interval(), start, end).map(|t| (sat, prod, t));
+
+        let mut report = self.retrieve_report(hours, filter)?;
+        report.paths.retain(|pth| scanned_within(pth, start, end));
+
+        Ok(report)
+    }
+}
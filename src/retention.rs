@@ -0,0 +1,61 @@
+use crate::product::Product;
+use chrono::Duration;
+
+/// How long to keep each product in the archive and how large it may grow, e.g. keep FDCC
+/// forever, FDCM for 30 days, everything else for 7 days, and no more than 2 TB in total.
+/// Enforce it with `Archive::apply_retention`.
+#[derive(Clone, Debug, Default)]
+pub struct RetentionPolicy {
+    rules: Vec<(Product, Option<Duration>)>,
+    default_age: Option<Duration>,
+    max_bytes: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// A policy that keeps everything, add rules to it to remove data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Never remove the product by age, regardless of the default.
+    pub fn keep_forever(self, prod: Product) -> Self {
+        self.rule(prod, None)
+    }
+
+    /// Remove data for the product once it is older than `age`.
+    pub fn keep_for(self, prod: Product, age: Duration) -> Self {
+        self.rule(prod, Some(age))
+    }
+
+    /// Remove data for products without their own rule once it is older than `age`. Without a
+    /// default they are kept forever.
+    pub fn default_keep_for(mut self, age: Duration) -> Self {
+        self.default_age = Some(age);
+        self
+    }
+
+    /// Limit the size of the whole archive, evicting the data written longest ago once the age
+    /// rules are applied.
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    fn rule(mut self, prod: Product, age: Option<Duration>) -> Self {
+        self.rules.retain(|(p, _)| p.prefix() != prod.prefix());
+        self.rules.push((prod, age));
+        self
+    }
+
+    /// How long to keep the product, `None` to keep it forever.
+    pub(crate) fn max_age(&self, prod: Product) -> Option<Duration> {
+        self.rules
+            .iter()
+            .find(|(p, _)| p.prefix() == prod.prefix())
+            .map_or(self.default_age, |(_, age)| *age)
+    }
+
+    pub(crate) fn quota(&self) -> Option<u64> {
+        self.max_bytes
+    }
+}
@@ -0,0 +1,107 @@
+use crate::report::is_retryable;
+use std::{
+    error::Error,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// How often and how patiently a failed download is tried again.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RetryPolicy {
+    retries: u32,
+    initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            retries: 3,
+            initial_backoff: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub(crate) fn new(retries: u32, initial_backoff: Duration) -> Self {
+        RetryPolicy {
+            retries,
+            initial_backoff,
+        }
+    }
+
+    /// How long to wait before trying again after `tries` failed tries, or `None` to give up.
+    /// Errors that won't go away by waiting, like a missing file, are never retried.
+    pub(crate) fn backoff(&self, tries: u32, err: &(dyn Error + 'static)) -> Option<Duration> {
+        if tries > self.retries || !is_retryable(err) {
+            return None;
+        }
+
+        let delay = self.initial_backoff * 2u32.pow(tries.saturating_sub(1).min(16));
+
+        // Up to half again at random, so downloads that failed together don't retry together.
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.subsec_nanos());
+
+        Some(delay + delay.mul_f64(f64::from(nanos % 1000) / 2000.0))
+    }
+
+    /// Run a download, waiting and trying again while it fails with errors worth retrying.
+    pub(crate) fn run<T>(
+        &self,
+        remote_fname: &str,
+        mut download: impl FnMut() -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        let mut tries = 0;
+        loop {
+            let err = match download() {
+                Ok(val) => return Ok(val),
+                Err(err) => err,
+            };
+
+            tries += 1;
+            match self.backoff(tries, err.as_ref()) {
+                Some(delay) => {
+                    log::warn!(
+                        "Download of {} failed, retry {} of {} in {:?}: {}",
+                        remote_fname,
+                        tries,
+                        self.retries(),
+                        delay,
+                        err
+                    );
+                    thread::sleep(delay);
+                }
+                None => return Err(err),
+            }
+        }
+    }
+
+    pub(crate) fn retries(&self) -> u32 {
+        self.retries
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::GoesArchError;
+    use std::io;
+
+    #[test]
+    fn backoff_doubles_and_gives_up() {
+        let policy = RetryPolicy::new(3, Duration::from_secs(1));
+        let err = GoesArchError::new("Download error");
+
+        for (tries, delay) in [(1, 1), (2, 2), (3, 4)] {
+            let delay = Duration::from_secs(delay);
+            let backoff = policy.backoff(tries, &err).unwrap();
+            assert!(backoff >= delay && backoff <= delay.mul_f64(1.5));
+        }
+        assert_eq!(policy.backoff(4, &err), None);
+
+        // Missing files don't show up by waiting.
+        let missing = io::Error::new(io::ErrorKind::NotFound, "missing");
+        assert_eq!(policy.backoff(1, &missing), None);
+    }
+}
@@ -1,40 +1,227 @@
-use crate::{error::GoesArchError, product::Product, remote::RemoteArchive, satellite::Satellite};
-use chrono::{naive::NaiveDateTime, Datelike, Timelike};
+use crate::{
+    error::GoesArchError,
+    filter::FileFilter,
+    http::{get_if_none_match, get_range, parse_http_date, XmlElement},
+    listing::ListedFile,
+    product::Product,
+    remote::{
+        noaa_day_prefix, noaa_key_prefix, split_day_key, ConditionalFile, RemoteArchive,
+        RemoteMetadata, RemoteSink,
+    },
+    retry::RetryPolicy,
+    satellite::Satellite,
+};
+use bytes::Bytes;
+use chrono::{
+    naive::{NaiveDate, NaiveDateTime},
+    DateTime,
+};
+use reqwest::{
+    blocking::{Client, Response},
+    header::{CONTENT_LENGTH, ETAG, LAST_MODIFIED},
+    Proxy,
+};
 use s3::{bucket::Bucket, creds::Credentials, region::Region};
-use std::error::Error;
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::Write,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
 
 #[derive(Debug, Clone)]
 pub struct AmazonS3NoaaBigData {
-    bucket_g16: Bucket,
-    bucket_g17: Bucket,
-    bucket_g18: Bucket,
+    region: Region,
+    credentials: Credentials,
+    bucket_names: Vec<(&'static str, String)>,
+    path_style: bool,
+    requester_pays: bool,
+    timeout: Duration,
+    retry_policy: RetryPolicy,
+    client: Client,
     num_max_downloads: usize,
+    // Buckets are made the first time a satellite is used, since custom satellites can't be
+    // known when connecting.
+    buckets: Arc<Mutex<HashMap<&'static str, Bucket>>>,
 }
 
 impl AmazonS3NoaaBigData {
+    /// Configure a connection to an S3 compatible store other than the NOAA Open Data
+    /// Dissemination buckets, e.g. MinIO, Ceph, or a private mirror.
+    pub fn builder(num_max_downloads: usize) -> S3RemoteBuilder {
+        S3RemoteBuilder {
+            region: "us-east-1".to_owned(),
+            endpoint: None,
+            credentials: None,
+            bucket_names: vec![],
+            path_style: false,
+            requester_pays: false,
+            timeout: Duration::from_secs(60),
+            retries: 0,
+            proxy: None,
+            client: None,
+            num_max_downloads,
+        }
+    }
+
     fn get_storage_location(
         &self,
         sat: Satellite,
         prod: Product,
         valid_hour: NaiveDateTime,
-    ) -> (&Bucket, String) {
-        let bucket = self.get_bucket(sat);
+    ) -> Result<(Bucket, String), Box<dyn Error>> {
+        let bucket = self.get_bucket(sat)?;
 
-        let prod: &'static str = prod.into();
-        let year = valid_hour.year();
-        let day = valid_hour.ordinal();
-        let hour = valid_hour.hour();
+        Ok((bucket, noaa_key_prefix(sat, prod, valid_hour)))
+    }
+
+    fn get_bucket(&self, sat: Satellite) -> Result<Bucket, Box<dyn Error>> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(bucket) = buckets.get(sat.bucket_name()) {
+            return Ok(bucket.clone());
+        }
 
-        (bucket, format!("{}/{}/{:03}/{:02}/", prod, year, day, hour))
+        let name = self
+            .bucket_names
+            .iter()
+            .find(|(default, _)| *default == sat.bucket_name())
+            .map(|(_, name)| name.as_str())
+            .unwrap_or_else(|| sat.bucket_name());
+
+        let mut bucket = Bucket::new(name, self.region.clone(), self.credentials.clone())?;
+        if self.path_style {
+            bucket.set_path_style();
+        }
+        if self.requester_pays {
+            bucket.add_header("x-amz-request-payer", "requester");
+        }
+        bucket.set_request_timeout(Some(self.timeout));
+
+        buckets.insert(sat.bucket_name(), bucket.clone());
+        Ok(bucket)
     }
 
-    fn get_bucket(&self, sat: Satellite) -> &Bucket {
-        match sat {
-            Satellite::GOES16 => &self.bucket_g16,
-            Satellite::GOES17 => &self.bucket_g17,
-            Satellite::GOES18 => &self.bucket_g18,
+    /// Run a request, waiting and trying again up to the configured number of retries while it
+    /// fails with errors worth retrying. Downloads of data files aren't run through this, the
+    /// archive's own retry policy covers them.
+    fn retry<T>(
+        &self,
+        mut request: impl FnMut() -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, Box<dyn Error>> {
+        let mut tries = 0;
+        loop {
+            let err = match request() {
+                Ok(val) => return Ok(val),
+                Err(err) => err,
+            };
+
+            tries += 1;
+            match self.retry_policy.backoff(tries, err.as_ref()) {
+                Some(delay) => {
+                    log::warn!(
+                        "Request failed, retry {} of {} in {:?}: {}",
+                        tries,
+                        self.retry_policy.retries(),
+                        delay,
+                        err
+                    );
+                    thread::sleep(delay);
+                }
+                None => return Err(err),
+            }
+        }
+    }
+
+    /// The URL for a request to the bucket, presigned if there are credentials. Requests to these
+    /// go through the remote's own client, so they share its connection pool, proxy, and timeout.
+    fn request_url(
+        &self,
+        bucket: &Bucket,
+        path: &str,
+        mut queries: HashMap<String, String>,
+    ) -> Result<String, Box<dyn Error>> {
+        if self.credentials.access_key.is_none() {
+            let url =
+                reqwest::Url::parse_with_params(&format!("{}{}", bucket.url(), path), &queries)?;
+            return Ok(url.to_string());
         }
+
+        if self.requester_pays {
+            queries.insert("x-amz-request-payer".to_owned(), "requester".to_owned());
+        }
+
+        Ok(bucket.presign_get(path, 3600, Some(queries))?)
     }
+
+    fn object_url(&self, bucket: &Bucket, key: &str) -> Result<String, Box<dyn Error>> {
+        self.request_url(bucket, &format!("/{}", key), HashMap::new())
+    }
+
+    /// A failed status comes back as the `reqwest` error, so retries can tell a missing object
+    /// from a server error.
+    fn get(&self, url: &str) -> Result<Response, Box<dyn Error>> {
+        let response = self.client.get(url).send()?;
+
+        if let Err(err) = response.error_for_status_ref() {
+            log::error!("Error {} requesting {}", response.status(), url);
+            return Err(Box::new(err));
+        }
+
+        Ok(response)
+    }
+
+    /// Every object under the prefix with its size and ETag, following the continuation tokens of
+    /// a paged listing. The names are the full keys.
+    fn list_objects(
+        &self,
+        bucket: &Bucket,
+        prefix: &str,
+        delimiter: Option<&str>,
+    ) -> Result<Vec<ListedFile>, Box<dyn Error>> {
+        let mut objects = vec![];
+        let mut continuation = None;
+        loop {
+            let mut queries = HashMap::new();
+            queries.insert("list-type".to_owned(), "2".to_owned());
+            queries.insert("prefix".to_owned(), prefix.to_owned());
+            if let Some(delimiter) = delimiter {
+                queries.insert("delimiter".to_owned(), delimiter.to_owned());
+            }
+            if let Some(token) = continuation.take() {
+                queries.insert("continuation-token".to_owned(), token);
+            }
+
+            let url = self.request_url(bucket, "/", queries)?;
+            let body = self.retry(|| Ok(self.get(&url)?.text()?))?;
+            let listing = XmlElement::parse(&body)?;
+
+            objects.extend(listing_objects(&listing));
+
+            match listing.child_text("NextContinuationToken") {
+                Some(token) if !token.is_empty() => continuation = Some(token.to_owned()),
+                _ => break,
+            }
+        }
+
+        Ok(objects)
+    }
+}
+
+/// The objects in a page of a `ListObjectsV2` listing.
+fn listing_objects(listing: &XmlElement) -> impl Iterator<Item = ListedFile> + '_ {
+    listing.children("Contents").filter_map(|contents| {
+        Some(ListedFile {
+            name: contents.child_text("Key")?.to_owned(),
+            size: contents.child_text("Size").and_then(|s| s.parse().ok()),
+            etag: contents.child_text("ETag").map(String::from),
+            last_modified: contents
+                .child_text("LastModified")
+                .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+                .map(|date| date.naive_utc()),
+        })
+    })
 }
 
 impl RemoteArchive for AmazonS3NoaaBigData {
@@ -42,36 +229,7 @@ impl RemoteArchive for AmazonS3NoaaBigData {
     where
         Self: Sized,
     {
-        let region: Region = "us-east-1".parse()?;
-        let credentials = Credentials::anonymous()?;
-        let bucket_str_g18 = "noaa-goes18";
-        let bucket_str_g17 = "noaa-goes17";
-        let bucket_str_g16 = "noaa-goes16";
-
-        let bucket_g16 = {
-            let region = region.clone();
-            let credentials = credentials.clone();
-            Bucket::new(&bucket_str_g16, region, credentials)?
-        };
-
-        let bucket_g17 = {
-            let region = region.clone();
-            let credentials = credentials.clone();
-            Bucket::new(&bucket_str_g17, region, credentials)?
-        };
-
-        let bucket_g18 = {
-            let region = region;
-            let credentials = credentials;
-            Bucket::new(&bucket_str_g18, region, credentials)?
-        };
-
-        Ok(AmazonS3NoaaBigData {
-            bucket_g16,
-            bucket_g17,
-            bucket_g18,
-            num_max_downloads,
-        })
+        Self::builder(num_max_downloads).build()
     }
 
     fn retrieve_remote_filenames(
@@ -79,23 +237,79 @@ impl RemoteArchive for AmazonS3NoaaBigData {
         sat: Satellite,
         prod: Product,
         valid_hour: NaiveDateTime,
+        filter: &FileFilter,
     ) -> Result<Vec<String>, Box<dyn Error>> {
-        let (bucket, common_prefix) = self.get_storage_location(sat, prod, valid_hour);
+        Ok(self
+            .retrieve_remote_listing(sat, prod, valid_hour, filter)?
+            .into_iter()
+            .map(|file| file.name)
+            .collect())
+    }
 
-        let results = bucket.list_blocking(common_prefix, Some("/".into()))?;
+    fn retrieve_remote_filenames_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, String)>, Box<dyn Error>> {
+        Ok(self
+            .retrieve_remote_listing_for_day(sat, prod, day, filter)?
+            .into_iter()
+            .map(|(valid_time, file)| (valid_time, file.name))
+            .collect())
+    }
+
+    fn retrieve_remote_listing(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<ListedFile>, Box<dyn Error>> {
+        let (bucket, common_prefix) = self.get_storage_location(sat, prod, valid_hour)?;
 
-        let mut fnames: Vec<String> = vec![];
-        for res in results {
-            for obj in &res.contents {
-                let path = &obj.key;
-                if let Some(i) = path.rfind("/") {
-                    let fname = String::from(&path[(i + 1)..]);
-                    fnames.push(fname);
+        let objects = self.list_objects(&bucket, &common_prefix, Some("/"))?;
+
+        Ok(objects
+            .into_iter()
+            .filter_map(|obj| {
+                let fname = obj.name.rsplit('/').next()?;
+                (!fname.is_empty() && filter.accepts(fname)).then(|| ListedFile {
+                    name: fname.to_owned(),
+                    ..obj
+                })
+            })
+            .collect())
+    }
+
+    fn retrieve_remote_listing_for_day(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        day: NaiveDate,
+        filter: &FileFilter,
+    ) -> Result<Vec<(NaiveDateTime, ListedFile)>, Box<dyn Error>> {
+        let bucket = self.get_bucket(sat)?;
+        let day_prefix = noaa_day_prefix(sat, prod, day);
+
+        let objects = self.list_objects(&bucket, &day_prefix, None)?;
+
+        Ok(objects
+            .into_iter()
+            .filter_map(|obj| {
+                let (valid_time, fname) = split_day_key(day, obj.name.strip_prefix(&day_prefix)?)?;
+                if !filter.accepts(&fname) {
+                    return None;
                 }
-            }
-        }
 
-        Ok(fnames)
+                Some((valid_time, ListedFile { name: fname, ..obj }))
+            })
+            .collect())
+    }
+
+    fn lists_whole_days(&self) -> bool {
+        true
     }
 
     fn retrieve_remote_file(
@@ -105,20 +319,380 @@ impl RemoteArchive for AmazonS3NoaaBigData {
         valid_hour: NaiveDateTime,
         remote_path: &str,
     ) -> Result<Vec<u8>, Box<dyn Error>> {
-        let (bucket, common_prefix) = self.get_storage_location(sat, prod, valid_hour);
+        let (bucket, common_prefix) = self.get_storage_location(sat, prod, valid_hour)?;
+        let url = self.object_url(&bucket, &(common_prefix + remote_path))?;
+
+        Ok(self.get(&url)?.bytes()?.to_vec())
+    }
+
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        let (bucket, common_prefix) = self.get_storage_location(sat, prod, valid_hour)?;
+        let url = self.object_url(&bucket, &(common_prefix + remote_path))?;
+
+        let mut response = self.get(&url)?;
+
+        Ok(response.copy_to(writer)?)
+    }
+
+    fn retrieve_remote_range(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let (bucket, common_prefix) = self.get_storage_location(sat, prod, valid_hour)?;
+        let url = self.object_url(&bucket, &(common_prefix + remote_path))?;
+
+        self.retry(|| get_range(&self.client, &url, start, end))
+    }
+
+    fn retrieve_remote_file_if_changed(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        etag: &str,
+    ) -> Result<ConditionalFile, Box<dyn Error>> {
+        let (bucket, common_prefix) = self.get_storage_location(sat, prod, valid_hour)?;
+        let url = self.object_url(&bucket, &(common_prefix + remote_path))?;
+
+        self.retry(|| get_if_none_match(&self.client, &url, etag))
+    }
 
+    fn retrieve_remote_metadata(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        let (bucket, common_prefix) = self.get_storage_location(sat, prod, valid_hour)?;
         let key = common_prefix + remote_path;
 
-        let (data, code) = bucket.get_object_blocking(key)?;
+        // Presigned URLs only work for the method they were signed for and HEAD can't be
+        // presigned, so signed requests go through the bucket's own client.
+        if self.credentials.access_key.is_some() {
+            return self.retry(|| {
+                let (head, status) = bucket.head_object_blocking(format!("/{}", key))?;
+                if !(200..300).contains(&status) {
+                    log::error!("Error {} requesting metadata for {}", status, key);
+                    return Err(Box::new(GoesArchError::new("Metadata error")));
+                }
 
-        if code != 200 {
-            return Err(Box::new(GoesArchError::new("Download error")));
+                Ok(RemoteMetadata {
+                    size: head.content_length.unwrap_or(0).max(0) as u64,
+                    etag: head.e_tag,
+                    last_modified: head.last_modified.as_deref().and_then(parse_http_date),
+                })
+            });
         }
 
-        Ok(data)
+        let url = self.object_url(&bucket, &key)?;
+        self.retry(|| {
+            let response = self.client.head(&url).send()?;
+            if let Err(err) = response.error_for_status_ref() {
+                log::error!(
+                    "Error {} requesting metadata for {}",
+                    response.status(),
+                    key
+                );
+                return Err(Box::new(err));
+            }
+
+            let header = |name| {
+                response
+                    .headers()
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+            };
+
+            Ok(RemoteMetadata {
+                size: header(CONTENT_LENGTH)
+                    .and_then(|size| size.parse().ok())
+                    .unwrap_or(0),
+                etag: header(ETAG).map(String::from),
+                last_modified: header(LAST_MODIFIED).and_then(parse_http_date),
+            })
+        })
+    }
+
+    fn retrieve_static_file(
+        &self,
+        sat: Satellite,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let bucket = self.get_bucket(sat)?;
+        let url = self.object_url(&bucket, remote_path.trim_start_matches('/'))?;
+
+        self.retry(|| Ok(self.get(&url)?.bytes()?.to_vec()))
+    }
+
+    fn retrieve_url(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.retry(|| Ok(self.get(url)?.bytes()?.to_vec()))
     }
 
     fn max_downloads(&self) -> usize {
         self.num_max_downloads
     }
 }
+
+impl RemoteSink for AmazonS3NoaaBigData {
+    fn upload_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        fname: &str,
+        data: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        if self.credentials.access_key.is_none() {
+            return Err(Box::new(GoesArchError::new("Uploading needs credentials.")));
+        }
+
+        let (bucket, prefix) = self.get_storage_location(sat, prod, valid_hour)?;
+        let url = bucket.presign_put(format!("/{}{}", prefix, fname), 3600, None)?;
+
+        // Copied once, retries share the buffer.
+        let body = Bytes::copy_from_slice(data);
+        self.retry(|| {
+            let response = self.client.put(&url).body(body.clone()).send()?;
+
+            if let Err(err) = response.error_for_status_ref() {
+                log::error!("Error {} uploading {}", response.status(), fname);
+                return Err(Box::new(err));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Builder for an `AmazonS3NoaaBigData` remote, see `AmazonS3NoaaBigData::builder`.
+#[derive(Debug, Clone)]
+pub struct S3RemoteBuilder {
+    region: String,
+    endpoint: Option<String>,
+    credentials: Option<(String, String)>,
+    bucket_names: Vec<(&'static str, String)>,
+    path_style: bool,
+    requester_pays: bool,
+    timeout: Duration,
+    retries: u32,
+    proxy: Option<String>,
+    client: Option<Client>,
+    num_max_downloads: usize,
+}
+
+impl S3RemoteBuilder {
+    /// The endpoint URL of the store, e.g. `https://minio.example.edu:9000`.
+    pub fn endpoint(mut self, url: &str) -> Self {
+        self.endpoint = Some(url.to_owned());
+        self
+    }
+
+    /// The region name, defaults to `us-east-1`.
+    pub fn region(mut self, region: &str) -> Self {
+        self.region = region.to_owned();
+        self
+    }
+
+    /// Sign requests with these keys instead of connecting anonymously.
+    pub fn credentials(mut self, access_key: &str, secret_key: &str) -> Self {
+        self.credentials = Some((access_key.to_owned(), secret_key.to_owned()));
+        self
+    }
+
+    /// Use this bucket instead of the NOAA bucket for the satellite.
+    pub fn bucket_name(mut self, sat: Satellite, name: &str) -> Self {
+        self.bucket_names
+            .retain(|(default, _)| *default != sat.bucket_name());
+        self.bucket_names.push((sat.bucket_name(), name.to_owned()));
+        self
+    }
+
+    /// Put the bucket name in the URL path instead of the host name, as most self hosted stores
+    /// require.
+    pub fn path_style(mut self) -> Self {
+        self.path_style = true;
+        self
+    }
+
+    /// Accept the charges for requests to a requester pays bucket. The requests must be signed,
+    /// so this needs credentials too.
+    pub fn requester_pays(mut self) -> Self {
+        self.requester_pays = true;
+        self
+    }
+
+    /// The longest a single request, including the download, may take. Defaults to 60 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How many times to retry a failed listing, metadata, or upload request before giving up,
+    /// waiting longer each time. Errors that won't go away, like a missing object, aren't retried.
+    /// Downloads are retried by the archive instead, see `Archive::with_retries`. Defaults to no
+    /// retries.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Send all requests through this HTTP(S) proxy, e.g. `http://proxy.example.edu:3128`.
+    /// Credentials for the proxy can go in the URL. Without this the `HTTP_PROXY` and
+    /// `HTTPS_PROXY` environment variables are used if they are set.
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.proxy = Some(url.to_owned());
+        self
+    }
+
+    /// Use this client for all requests instead of building one, e.g. to share a connection pool
+    /// with other remotes. It's a `reqwest::blocking::Client` from reqwest 0.11. The timeout and
+    /// proxy settings of the builder don't apply to it, set them on the client instead.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn build(self) -> Result<AmazonS3NoaaBigData, Box<dyn Error>> {
+        if self.requester_pays && self.credentials.is_none() {
+            return Err(Box::new(GoesArchError::new(
+                "Requester pays buckets need credentials.",
+            )));
+        }
+
+        let region = match self.endpoint {
+            Some(endpoint) => Region::Custom {
+                region: self.region,
+                endpoint,
+            },
+            None => self.region.parse()?,
+        };
+
+        let credentials = match self.credentials {
+            Some((access_key, secret_key)) => Credentials {
+                access_key: Some(access_key),
+                secret_key: Some(secret_key),
+                security_token: None,
+                session_token: None,
+            },
+            None => Credentials::anonymous()?,
+        };
+
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let mut client = Client::builder().timeout(self.timeout);
+                if let Some(proxy) = &self.proxy {
+                    client = client.proxy(Proxy::all(proxy)?);
+                }
+                client.build()?
+            }
+        };
+
+        Ok(AmazonS3NoaaBigData {
+            region,
+            credentials,
+            bucket_names: self.bucket_names,
+            path_style: self.path_style,
+            requester_pays: self.requester_pays,
+            timeout: self.timeout,
+            retry_policy: RetryPolicy::new(self.retries, Duration::from_secs(1)),
+            client,
+            num_max_downloads: self.num_max_downloads,
+            buckets: Arc::default(),
+        })
+    }
+}
+
+/// Requests run on tokio's blocking threads through the same client as the blocking ones, so the
+/// proxy, timeout, and retry settings apply to them too.
+#[cfg(feature = "tokio")]
+impl crate::async_archive::AsyncRemoteArchive for AmazonS3NoaaBigData {
+    async fn retrieve_remote_filenames_async(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let (remote, filter) = (self.clone(), filter.clone());
+        crate::async_archive::blocking(move || {
+            remote.retrieve_remote_filenames(sat, prod, valid_hour, &filter)
+        })
+        .await
+    }
+
+    async fn retrieve_remote_file_async(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let (remote, remote_path) = (self.clone(), remote_path.to_owned());
+        crate::async_archive::blocking(move || {
+            remote.retrieve_remote_file(sat, prod, valid_hour, &remote_path)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn listing_pages_are_parsed() {
+        let body = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Name>noaa-goes16</Name>
+  <Prefix>ABI-L2-FDCC/2023/152/18/</Prefix>
+  <KeyCount>2</KeyCount>
+  <MaxKeys>1000</MaxKeys>
+  <IsTruncated>true</IsTruncated>
+  <NextContinuationToken>1a&amp;b==</NextContinuationToken>
+  <Contents>
+    <Key>ABI-L2-FDCC/2023/152/18/OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804087.nc</Key>
+    <LastModified>2023-06-01T18:04:32.000Z</LastModified>
+    <ETag>&quot;5b5e1c1bd4cdb2aa2a1c8b6b1e0c9c71&quot;</ETag>
+    <Size>372981</Size>
+    <StorageClass>INTELLIGENT_TIERING</StorageClass>
+  </Contents>
+  <Contents>
+    <Key><![CDATA[ABI-L2-FDCC/2023/152/18/a<b>.nc]]></Key>
+    <Size>12</Size>
+  </Contents>
+</ListBucketResult>"#;
+
+        let listing = XmlElement::parse(body).unwrap();
+        let objects: Vec<ListedFile> = listing_objects(&listing).collect();
+
+        assert_eq!(objects.len(), 2);
+        assert!(objects[0].name.ends_with("c20231521804087.nc"));
+        assert_eq!(objects[0].size, Some(372981));
+        assert_eq!(
+            objects[0].etag.as_deref(),
+            Some("\"5b5e1c1bd4cdb2aa2a1c8b6b1e0c9c71\"")
+        );
+        assert_eq!(
+            objects[0].last_modified.map(|t| t.to_string()).as_deref(),
+            Some("2023-06-01 18:04:32")
+        );
+        assert_eq!(objects[1].name, "ABI-L2-FDCC/2023/152/18/a<b>.nc");
+        assert_eq!(objects[1].etag, None);
+        assert_eq!(listing.child_text("NextContinuationToken"), Some("1a&b=="));
+    }
+}
@@ -1,8 +1,13 @@
-use crate::Product;
-use chrono::{NaiveDate, NaiveDateTime};
-use strum::IntoStaticStr;
+use crate::{custom::CustomSatellite, error::GoesArchError, Product};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use std::{
+    error::Error,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
 
-#[derive(Clone, Copy, Debug, IntoStaticStr)]
+#[derive(Clone, Copy, Debug, EnumIter, IntoStaticStr)]
 pub enum Satellite {
     #[strum(serialize = "G16")]
     GOES16,
@@ -10,9 +15,116 @@ pub enum Satellite {
     GOES17,
     #[strum(serialize = "G18")]
     GOES18,
+    #[strum(serialize = "G19")]
+    GOES19,
+    #[strum(serialize = "H08")]
+    HIMAWARI8,
+    #[strum(serialize = "H09")]
+    HIMAWARI9,
+    /// A satellite backed by an arbitrary bucket laid out like the GOES buckets, e.g. a test
+    /// bucket, made with `Satellite::custom`. The bucket name is also used as the directory name
+    /// in the local archive.
+    #[strum(disabled)]
+    Custom(CustomSatellite),
+}
+
+/// The operational slots the GOES satellites occupy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    East,
+    West,
+}
+
+impl Role {
+    pub fn satellite_at(&self, time: NaiveDateTime) -> Satellite {
+        match self {
+            Role::East => Satellite::east_at(time),
+            Role::West => Satellite::west_at(time),
+        }
+    }
 }
 
 impl Satellite {
+    /// A satellite backed by `bucket`, with data from `earliest` on. Errors if the bucket is
+    /// already registered with another date.
+    pub fn custom(bucket: &str, earliest: NaiveDateTime) -> Result<Satellite, Box<dyn Error>> {
+        Ok(Satellite::Custom(CustomSatellite::register(
+            bucket, earliest,
+        )?))
+    }
+
+    /// Every built in satellite followed by the custom satellites registered so far. `iter` only
+    /// yields the built in ones.
+    pub fn all() -> impl Iterator<Item = Satellite> {
+        Satellite::iter().chain(CustomSatellite::registered().map(Satellite::Custom))
+    }
+
+    /// The name used for this satellite in the local archive, e.g. `G16`. Prefer this to the
+    /// `&'static str` conversion, which cannot see the bucket of a `Custom` satellite.
+    pub fn short_name(&self) -> &'static str {
+        match *self {
+            Satellite::Custom(custom) => custom.bucket(),
+            sat => sat.into(),
+        }
+    }
+
+    /// The name of the NOAA Open Data Dissemination bucket holding this satellite's data.
+    pub fn bucket_name(&self) -> &'static str {
+        match *self {
+            Satellite::GOES16 => "noaa-goes16",
+            Satellite::GOES17 => "noaa-goes17",
+            Satellite::GOES18 => "noaa-goes18",
+            Satellite::GOES19 => "noaa-goes19",
+            Satellite::HIMAWARI8 => "noaa-himawari8",
+            Satellite::HIMAWARI9 => "noaa-himawari9",
+            Satellite::Custom(custom) => custom.bucket(),
+        }
+    }
+
+    /// The satellite operating as GOES-East at `time`.
+    pub fn east_at(time: NaiveDateTime) -> Satellite {
+        if time < Satellite::GOES19.earliest_operational_date(Product::FDCF) {
+            Satellite::GOES16
+        } else {
+            Satellite::GOES19
+        }
+    }
+
+    /// The satellite operating as GOES-West at `time`.
+    pub fn west_at(time: NaiveDateTime) -> Satellite {
+        if time < Satellite::GOES18.earliest_operational_date(Product::FDCF) {
+            Satellite::GOES17
+        } else {
+            Satellite::GOES18
+        }
+    }
+
+    /// Pick GOES-East or GOES-West, whichever views the location closest to nadir at `time`.
+    /// Returns `None` if the location is too close to the edge of both disks to be useful.
+    pub fn best_for(lon: f64, lat: f64, time: NaiveDateTime) -> Option<Satellite> {
+        // Nominal sub-satellite longitudes of the operational slots
+        const EAST_LON: f64 = -75.2;
+        const WEST_LON: f64 = -137.2;
+        // Beyond this great circle angle from the sub-satellite point the view is too oblique.
+        const MAX_VIEW_ANGLE: f64 = 70.0;
+
+        let view_angle = |sat_lon: f64| {
+            let cos_angle = lat.to_radians().cos() * (lon - sat_lon).to_radians().cos();
+            cos_angle.clamp(-1.0, 1.0).acos().to_degrees()
+        };
+
+        let east = view_angle(EAST_LON);
+        let west = view_angle(WEST_LON);
+
+        if east.min(west) > MAX_VIEW_ANGLE {
+            None
+        } else if east <= west {
+            Some(Role::East.satellite_at(time))
+        } else {
+            Some(Role::West.satellite_at(time))
+        }
+    }
+
     pub fn earliest_operational_date(&self, prod: Product) -> NaiveDateTime {
         match (*self, prod) {
             (Satellite::GOES16 | Satellite::GOES17, Product::FDCM) => {
@@ -29,6 +141,177 @@ impl Satellite {
             (Satellite::GOES18, _) => NaiveDate::from_ymd_opt(2023, 1, 17)
                 .and_then(|d| d.and_hms_opt(12, 0, 0))
                 .unwrap(),
+            (Satellite::GOES19, _) => NaiveDate::from_ymd_opt(2025, 4, 7)
+                .and_then(|d| d.and_hms_opt(12, 0, 0))
+                .unwrap(),
+            (Satellite::HIMAWARI8, _) => NaiveDate::from_ymd_opt(2015, 7, 7)
+                .and_then(|d| d.and_hms_opt(2, 0, 0))
+                .unwrap(),
+            (Satellite::HIMAWARI9, _) => NaiveDate::from_ymd_opt(2022, 12, 13)
+                .and_then(|d| d.and_hms_opt(2, 0, 0))
+                .unwrap(),
+            (Satellite::Custom(custom), _) => custom.earliest(),
+        }
+    }
+
+    /// The last time data is available, `None` if the satellite is still operational.
+    pub fn latest_operational_date(&self, prod: Product) -> Option<NaiveDateTime> {
+        match *self {
+            // Replaced as GOES-East by GOES-19
+            Satellite::GOES16 => Some(Satellite::GOES19.earliest_operational_date(prod)),
+            // Replaced as GOES-West by GOES-18 and moved to storage
+            Satellite::GOES17 => Some(Satellite::GOES18.earliest_operational_date(prod)),
+            // Replaced by Himawari-9
+            Satellite::HIMAWARI8 => Some(Satellite::HIMAWARI9.earliest_operational_date(prod)),
+            Satellite::GOES18 | Satellite::GOES19 | Satellite::HIMAWARI9 => None,
+            Satellite::Custom(_) => None,
         }
     }
+
+    /// When the ABI switched from scan mode 3 to scan mode 6 as the default.
+    pub fn mode_6_start(&self) -> NaiveDateTime {
+        match *self {
+            Satellite::GOES16 | Satellite::GOES17 => NaiveDate::from_ymd_opt(2019, 4, 2)
+                .and_then(|d| d.and_hms_opt(16, 0, 0))
+                .unwrap(),
+            Satellite::GOES18 | Satellite::GOES19 | Satellite::Custom(_) => {
+                self.earliest_operational_date(Product::FDCF)
+            }
+            Satellite::HIMAWARI8 | Satellite::HIMAWARI9 => {
+                self.earliest_operational_date(Product::AhiFldk)
+            }
+        }
+    }
+
+    /// The span of time covered by each directory in the remote and local archives.
+    pub fn directory_interval(&self) -> Duration {
+        match *self {
+            Satellite::HIMAWARI8 | Satellite::HIMAWARI9 => Duration::minutes(10),
+            _ => Duration::hours(1),
+        }
+    }
+}
+
+const CUSTOM_DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+impl Display for Satellite {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        let name = match *self {
+            Satellite::GOES16 => "GOES-16",
+            Satellite::GOES17 => "GOES-17",
+            Satellite::GOES18 => "GOES-18",
+            Satellite::GOES19 => "GOES-19",
+            Satellite::HIMAWARI8 => "Himawari-8",
+            Satellite::HIMAWARI9 => "Himawari-9",
+            Satellite::Custom(custom) => custom.bucket(),
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Satellite {
+    type Err = GoesArchError;
+
+    /// Parses the short names used in the archive, e.g. "G16", and the full names with or without
+    /// separators, e.g. "GOES16" or "goes-16". Case is ignored. The bucket of a custom satellite
+    /// parses if it's already registered, and `<bucket>@<earliest>` registers it, e.g.
+    /// "my-bucket@2024-01-01T00:00:00".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((bucket, earliest)) = s.split_once('@') {
+            let earliest = NaiveDateTime::parse_from_str(earliest, CUSTOM_DATE_FORMAT)
+                .map_err(|err| GoesArchError::new(&format!("Unknown satellite: {}: {}", s, err)))?;
+            return CustomSatellite::register(bucket, earliest).map(Satellite::Custom);
+        }
+
+        if let Some(custom) = CustomSatellite::find(s) {
+            return Ok(Satellite::Custom(custom));
+        }
+
+        let normalize = |s: &str| -> String {
+            s.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .map(|c| c.to_ascii_lowercase())
+                .collect()
+        };
+
+        let target = normalize(s);
+
+        Satellite::iter()
+            .find(|sat| {
+                normalize(sat.short_name()) == target || normalize(&sat.to_string()) == target
+            })
+            .ok_or_else(|| GoesArchError::new(&format!("Unknown satellite: {}", s)))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Satellite {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            // With the earliest date, so it parses in a process it isn't registered in.
+            Satellite::Custom(custom) => serializer.serialize_str(&format!(
+                "{}@{}",
+                custom.bucket(),
+                custom.earliest().format(CUSTOM_DATE_FORMAT)
+            )),
+            sat => serializer.serialize_str(sat.short_name()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Satellite {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_names() {
+        for name in ["G16", "GOES16", "goes-16", "GOES-16"] {
+            assert!(matches!(name.parse(), Ok(Satellite::GOES16)), "{}", name);
+        }
+        assert!(matches!("h09".parse(), Ok(Satellite::HIMAWARI9)));
+        assert!("G15".parse::<Satellite>().is_err());
+    }
+
+    #[test]
+    fn parse_custom() {
+        let earliest = NaiveDate::from_ymd_opt(2024, 6, 1)
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .unwrap();
+
+        let sat: Satellite = "parse-test-bucket@2024-06-01T00:00:00".parse().unwrap();
+        assert_eq!(sat.bucket_name(), "parse-test-bucket");
+        assert_eq!(sat.earliest_operational_date(Product::FDCC), earliest);
+
+        let again: Satellite = "parse-test-bucket".parse().unwrap();
+        assert_eq!(again.short_name(), "parse-test-bucket");
+
+        assert!("parse-test-bucket@yesterday".parse::<Satellite>().is_err());
+    }
+
+    #[test]
+    fn all_includes_custom_satellites() {
+        let sat = Satellite::custom("all-test-bucket", NaiveDateTime::MIN).unwrap();
+        assert!(Satellite::all().any(|s| s.bucket_name() == sat.bucket_name()));
+        assert!(!Satellite::iter().any(|s| s.bucket_name() == sat.bucket_name()));
+
+        assert!(Satellite::custom("all-test-bucket", NaiveDateTime::MIN).is_ok());
+        assert!("all-test-bucket@2024-06-01T00:00:00"
+            .parse::<Satellite>()
+            .is_err());
+    }
 }
@@ -0,0 +1,52 @@
+use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
+
+/// The ABI scan modes, which set how often each sector is scanned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, IntoStaticStr)]
+pub enum ScanMode {
+    /// Full disk every 15 minutes, the default until 2019.
+    M3,
+    /// Full disk only, every 5 minutes.
+    M4,
+    /// Full disk every 10 minutes, the default since 2019.
+    M6,
+}
+
+impl ScanMode {
+    /// Find the scan mode in a file name like OR_ABI-L1b-RadC-M6C13_G16_s..., `None` if the file
+    /// is not from the ABI.
+    pub fn from_file_name(fname: &str) -> Option<ScanMode> {
+        let product_field = fname.split('_').nth(1)?;
+        if !product_field.starts_with("ABI-") {
+            return None;
+        }
+
+        let mode_field = product_field.split('-').nth(3)?;
+        let mode = mode_field.get(..2)?;
+
+        ScanMode::iter().find(|m| <&str>::from(*m) == mode)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scan_mode_from_file_name() {
+        assert_eq!(
+            ScanMode::from_file_name("OR_ABI-L1b-RadC-M6C13_G16_s20231521801172_e1_c1.nc"),
+            Some(ScanMode::M6)
+        );
+        assert_eq!(
+            ScanMode::from_file_name("OR_ABI-L2-FDCC-M3_G16_s20181001200000_e1_c1.nc"),
+            Some(ScanMode::M3)
+        );
+
+        // Not from the ABI, or no mode in the name.
+        assert_eq!(
+            ScanMode::from_file_name("OR_GLM-L2-LCFA_G16_s20231521801000_e1_c1.nc"),
+            None
+        );
+        assert_eq!(ScanMode::from_file_name("land_mask.nc"), None);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::fields::{format_time, parse_optional_time, parse_time};
+use chrono::naive::NaiveDateTime;
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+use std::borrow::Cow;
+
+// The public types derive serde in the same time format as the files the crate writes.
+
+pub(crate) fn serialize<S: Serializer>(time: &NaiveDateTime, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&format_time(time))
+}
+
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<NaiveDateTime, D::Error> {
+    let text = <Cow<str>>::deserialize(d)?;
+    parse_time(&text).map_err(D::Error::custom)
+}
+
+/// For optional times, written as `null` in JSON and left empty in CSV.
+pub(crate) mod option {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        time: &Option<NaiveDateTime>,
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => super::serialize(time, s),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        d: D,
+    ) -> Result<Option<NaiveDateTime>, D::Error> {
+        match <Option<Cow<str>>>::deserialize(d)? {
+            Some(text) => parse_optional_time(&text).map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde::Serialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Times {
+        #[serde(with = "super")]
+        time: NaiveDateTime,
+        #[serde(with = "super::option")]
+        maybe: Option<NaiveDateTime>,
+    }
+
+    #[test]
+    fn round_trip() {
+        let time = parse_time("2023-06-01T18:00:00").unwrap();
+
+        for times in [
+            Times { time, maybe: None },
+            Times {
+                time,
+                maybe: Some(time),
+            },
+        ] {
+            let text = serde_json::to_string(&times).unwrap();
+            assert_eq!(serde_json::from_str::<Times>(&text).unwrap(), times);
+        }
+
+        let text = serde_json::to_string(&Times { time, maybe: None }).unwrap();
+        assert_eq!(text, r#"{"time":"2023-06-01T18:00:00","maybe":null}"#);
+    }
+}
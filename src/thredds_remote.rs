@@ -0,0 +1,329 @@
+use crate::{
+    error::GoesArchError,
+    filename::scan_start,
+    filter::FileFilter,
+    http::{get_if_none_match, get_range, head_metadata, proxied_client, XmlElement},
+    product::Product,
+    remote::{ConditionalFile, RemoteArchive, RemoteMetadata},
+    satellite::Satellite,
+};
+use chrono::{naive::NaiveDateTime, Datelike, Timelike};
+use reqwest::blocking::Client;
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+/// Where files are served from when a catalog doesn't list an HTTP file server.
+const DEFAULT_FILE_SERVER: &str = "/thredds/fileServer/";
+
+/// How many catalogs to keep the file URLs of before starting over.
+const MAX_CACHED_CATALOGS: usize = 64;
+
+/// The catalog layout of a THREDDS server that mirrors the NOAA buckets.
+pub const NOAA_CATALOG_LAYOUT: &str = "{bucket}/{prefix}/{year}/{doy}/{hour}";
+
+/// A THREDDS Data Server, such as the one Unidata runs with recent GOES data.
+///
+/// The catalog layout is a path below `thredds/catalog/` with the placeholders `{bucket}`,
+/// `{sat}`, `{prefix}`, `{year}`, `{month}`, `{day}`, `{doy}`, `{date}` (YYYYMMDD), and `{hour}`.
+/// Catalogs covering more than an hour, e.g. a directory per day, are filtered down to the hour
+/// using the scan start time in the file names. Files are downloaded from the `urlPath` the
+/// catalog gives them on its HTTP file server.
+#[derive(Debug, Clone)]
+pub struct ThreddsRemote {
+    server_url: String,
+    layout: String,
+    client: Client,
+    num_max_downloads: usize,
+    // The download URL of each file by name, for the catalogs listed so far.
+    file_urls: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
+}
+
+impl ThreddsRemote {
+    pub fn new(
+        server_url: &str,
+        layout: &str,
+        num_max_downloads: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        Ok(ThreddsRemote {
+            server_url: server_url.trim_end_matches('/').to_owned(),
+            layout: layout.trim_matches('/').to_owned(),
+            client: Client::builder().build()?,
+            num_max_downloads,
+            file_urls: Arc::default(),
+        })
+    }
+
+    /// Send all requests through this HTTP(S) proxy, e.g. `http://proxy.example.edu:3128`.
+    /// Without this the `HTTP_PROXY` and `HTTPS_PROXY` environment variables are used if they are
+    /// set.
+    pub fn with_proxy(mut self, proxy_url: &str) -> Result<Self, Box<dyn Error>> {
+        self.client = proxied_client(proxy_url)?;
+        Ok(self)
+    }
+
+    /// Use this client for all requests, e.g. one shared with other remotes so they pool their
+    /// connections, or one configured with custom TLS roots or keep-alive settings. It's a
+    /// `reqwest::blocking::Client` from reqwest 0.11.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn catalog_path(&self, sat: Satellite, prod: Product, valid_hour: NaiveDateTime) -> String {
+        self.layout
+            .replace("{bucket}", sat.bucket_name())
+            .replace("{sat}", sat.short_name())
+            .replace("{prefix}", prod.prefix())
+            .replace("{year}", &format!("{:04}", valid_hour.year()))
+            .replace("{month}", &format!("{:02}", valid_hour.month()))
+            .replace("{day}", &format!("{:02}", valid_hour.day()))
+            .replace("{doy}", &format!("{:03}", valid_hour.ordinal()))
+            .replace("{date}", &valid_hour.format("%Y%m%d").to_string())
+            .replace("{hour}", &format!("{:02}", valid_hour.hour()))
+    }
+
+    fn catalog_url(&self, sat: Satellite, prod: Product, valid_hour: NaiveDateTime) -> String {
+        format!(
+            "{}/thredds/catalog/{}/catalog.xml",
+            self.server_url,
+            self.catalog_path(sat, prod, valid_hour)
+        )
+    }
+
+    /// The name and download URL of every file in a catalog, in the order listed.
+    fn catalog_files(&self, catalog_url: &str) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let catalog = XmlElement::parse(&self.get(catalog_url)?.text()?)?;
+        let files = file_urls(&self.server_url, &catalog);
+
+        let mut cache = self.file_urls.lock().unwrap_or_else(|err| err.into_inner());
+        if cache.len() >= MAX_CACHED_CATALOGS {
+            cache.clear();
+        }
+        cache.insert(catalog_url.to_owned(), files.iter().cloned().collect());
+
+        Ok(files)
+    }
+
+    fn file_url(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        fname: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let catalog_url = self.catalog_url(sat, prod, valid_hour);
+
+        let cached = self
+            .file_urls
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(&catalog_url)
+            .and_then(|files| files.get(fname).cloned());
+        if let Some(url) = cached {
+            return Ok(url);
+        }
+
+        self.catalog_files(&catalog_url)?
+            .into_iter()
+            .find(|(name, _)| name == fname)
+            .map(|(_, url)| url)
+            .ok_or_else(|| {
+                log::error!("{} isn't in the catalog {}", fname, catalog_url);
+                GoesArchError::new("File not in catalog").into()
+            })
+    }
+
+    fn get(&self, url: &str) -> Result<reqwest::blocking::Response, Box<dyn Error>> {
+        let response = self.client.get(url).send()?;
+
+        if !response.status().is_success() {
+            log::error!("Error {} requesting {}", response.status(), url);
+            return Err(Box::new(GoesArchError::new("Download error")));
+        }
+
+        Ok(response)
+    }
+}
+
+impl RemoteArchive for ThreddsRemote {
+    fn connect(_num_max_downloads: usize) -> Result<Self, Box<dyn Error>>
+    where
+        Self: Sized,
+    {
+        Err(Box::new(GoesArchError::new(
+            "ThreddsRemote needs a server URL and catalog layout, use ThreddsRemote::new.",
+        )))
+    }
+
+    fn retrieve_remote_filenames(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let files = self.catalog_files(&self.catalog_url(sat, prod, valid_hour))?;
+
+        let end = valid_hour + sat.directory_interval();
+
+        Ok(files
+            .into_iter()
+            .map(|(fname, _)| fname)
+            .filter(|fname| {
+                scan_start(fname).is_none_or(|start| start >= valid_hour && start < end)
+            })
+            .filter(|fname| filter.accepts(fname))
+            .collect())
+    }
+
+    fn retrieve_remote_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = self.file_url(sat, prod, valid_hour, remote_path)?;
+
+        Ok(self.get(&url)?.bytes()?.to_vec())
+    }
+
+    fn retrieve_remote_file_to(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        writer: &mut dyn Write,
+    ) -> Result<u64, Box<dyn Error>> {
+        let url = self.file_url(sat, prod, valid_hour, remote_path)?;
+
+        Ok(self.get(&url)?.copy_to(writer)?)
+    }
+
+    fn retrieve_remote_range(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let url = self.file_url(sat, prod, valid_hour, remote_path)?;
+
+        get_range(&self.client, &url, start, end)
+    }
+
+    fn retrieve_remote_file_if_changed(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+        etag: &str,
+    ) -> Result<ConditionalFile, Box<dyn Error>> {
+        let url = self.file_url(sat, prod, valid_hour, remote_path)?;
+
+        get_if_none_match(&self.client, &url, etag)
+    }
+
+    fn retrieve_remote_metadata(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_hour: NaiveDateTime,
+        remote_path: &str,
+    ) -> Result<RemoteMetadata, Box<dyn Error>> {
+        head_metadata(
+            &self.client,
+            &self.file_url(sat, prod, valid_hour, remote_path)?,
+        )
+    }
+
+    fn retrieve_url(&self, url: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(self.get(url)?.bytes()?.to_vec())
+    }
+
+    fn max_downloads(&self) -> usize {
+        self.num_max_downloads
+    }
+}
+
+/// The name and download URL of every dataset in a catalog with a `urlPath`, served from the
+/// catalog's HTTP file server.
+fn file_urls(server_url: &str, catalog: &XmlElement) -> Vec<(String, String)> {
+    let base = catalog
+        .descendants("service")
+        .into_iter()
+        .find(|service| {
+            service
+                .attribute("serviceType")
+                .is_some_and(|kind| kind.eq_ignore_ascii_case("HTTPServer"))
+        })
+        .and_then(|service| service.attribute("base"))
+        .unwrap_or(DEFAULT_FILE_SERVER);
+
+    // The base is usually a path on the same server, but can be a full URL.
+    let base = if base.starts_with("http://") || base.starts_with("https://") {
+        base.trim_end_matches('/').to_owned()
+    } else {
+        format!("{}/{}", server_url, base.trim_matches('/'))
+    };
+
+    catalog
+        .descendants("dataset")
+        .into_iter()
+        .filter_map(|dataset| dataset.attribute("urlPath"))
+        .filter_map(|url_path| {
+            let fname = url_path.rsplit('/').next()?;
+            let url = format!("{}/{}", base, url_path.trim_start_matches('/'));
+            Some((fname.to_owned(), url))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn file_urls_come_from_the_catalog() {
+        let catalog = r#"<?xml version="1.0" encoding="UTF-8"?>
+<catalog xmlns="http://www.unidata.ucar.edu/namespaces/thredds/InvCatalog/v1.0" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.2">
+  <service name="all" serviceType="Compound" base="">
+    <service name="OPENDAP" serviceType="OPENDAP" base="/thredds/dodsC/" />
+    <service name="HTTPServer" serviceType="HTTPServer" base="/thredds/fileServer/" />
+  </service>
+  <dataset name="FDCC" ID="satellite/goes/east/products/FireDetection/CONUS/20230601">
+    <metadata inherited="true">
+      <serviceName>all</serviceName>
+    </metadata>
+    <dataset name="OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804087.nc" ID="x" urlPath="satellite/goes/east/products/FireDetection/CONUS/20230601/OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804087.nc">
+      <dataSize units="Kbytes">364.2</dataSize>
+    </dataset>
+  </dataset>
+</catalog>"#;
+
+        let files = file_urls(
+            "https://thredds.ucar.edu",
+            &XmlElement::parse(catalog).unwrap(),
+        );
+
+        assert_eq!(
+            files,
+            vec![(
+                "OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804087.nc"
+                    .to_owned(),
+                "https://thredds.ucar.edu/thredds/fileServer/satellite/goes/east/products/\
+                 FireDetection/CONUS/20230601/\
+                 OR_ABI-L2-FDCC-M6_G16_s20231521801172_e20231521803545_c20231521804087.nc"
+                    .to_owned()
+            )]
+        );
+    }
+}
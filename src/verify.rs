@@ -0,0 +1,352 @@
+use crate::{
+    archive::{Archive, HOUR_COMPLETE_FNAME},
+    bundle::read_bundled,
+    compression::{find_stored, open_archived_file, original_name, stored_paths},
+    filter::FileFilter,
+    marker::{check_sidecar, data_checksum, file_checksum, sidecar_path, CompletionMarker},
+    product::Product,
+    remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::naive::NaiveDateTime;
+use std::{
+    error::Error,
+    fs::remove_file,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// What is wrong with a file in the archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileProblem {
+    /// The remote has the file but the archive doesn't.
+    Missing,
+    /// The file is smaller than the remote copy, e.g. an empty file left by a power failure.
+    Truncated { expected: u64, actual: u64 },
+    /// The file is empty and the remote doesn't say how big it should be.
+    Empty,
+    /// The file can't be read back, or no longer matches its checksum in the index.
+    Corrupt(String),
+}
+
+/// A file that failed verification.
+#[derive(Clone, Debug)]
+pub struct BadFile {
+    pub sat: Satellite,
+    pub prod: Product,
+    pub valid_time: NaiveDateTime,
+    /// The name of the file on the remote.
+    pub fname: String,
+    /// Where the file is, or would be, in the archive.
+    pub path: PathBuf,
+    pub problem: FileProblem,
+}
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Check the archived files of the product from `start` through `end` against the remote,
+    /// reporting files that are missing, truncated, or corrupt. Zipped files are decompressed to
+    /// check their CRC, sizes are compared when the remote supports metadata, and checksums are
+    /// compared when the archive has an index. Hours not in the archive at all are skipped. Only
+    /// the remote files that pass the filter are expected in the archive, so verify filtered
+    /// retrievals with the same filter.
+    pub fn verify(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<BadFile>, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let mut bad_files = vec![];
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let dir = self.build_path(sat, prod, valid_time);
+            if !dir.is_dir() {
+                continue;
+            }
+
+            let marker = read_marker(&dir)?;
+
+            let fnames = self
+                .remote()
+                .retrieve_remote_filenames(sat, prod, valid_time, filter)?;
+
+            for fname in fnames {
+                let path = dir.join(&fname);
+                let problem =
+                    match self.check_file(sat, prod, valid_time, &fname, &path, marker.as_ref()) {
+                        Ok(problem) => problem,
+                        Err(err) => {
+                            log::error!("Error verifying {:?} : {}", path, err);
+                            continue;
+                        }
+                    };
+
+                if let Some(problem) = problem {
+                    log::warn!("{:?} failed verification: {:?}", path, problem);
+                    bad_files.push(BadFile {
+                        sat,
+                        prod,
+                        valid_time,
+                        fname,
+                        path,
+                        problem,
+                    });
+                }
+            }
+        }
+
+        Ok(bad_files)
+    }
+
+    /// Check the archived files of the product from `start` through `end` without touching the
+    /// remote. Files are compared against the sizes and checksums recorded in their directory's
+    /// completion marker, and compressed files are decompressed to check them. Directories whose
+    /// markers don't list their files only get the decompression check. Files in a directory's
+    /// listing snapshot that aren't in the archive are reported missing.
+    pub fn verify_local(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<BadFile>, Box<dyn Error>> {
+        let (start, end) = Self::validate_dates(sat, prod, start, end)?;
+
+        let mut bad_files = vec![];
+        for valid_time in Self::time_steps(sat.directory_interval(), start, end) {
+            let dir = self.build_path(sat, prod, valid_time);
+            if !dir.is_dir() {
+                continue;
+            }
+
+            let marker = read_marker(&dir)?;
+            let listing = self.listing_snapshot(sat, prod, valid_time)?;
+
+            let mut fnames: Vec<String> = Self::dir_files(&dir, &FileFilter::default())
+                .iter()
+                .filter_map(|pth| Some(pth.file_name()?.to_string_lossy().to_string()))
+                .collect();
+            if let Some(ref marker) = marker {
+                fnames.extend(marker.files.iter().map(|file| file.name.clone()));
+            }
+            if let Some(ref listing) = listing {
+                fnames.extend(listing.files.iter().map(|file| file.name.clone()));
+            }
+            let mut fnames: Vec<String> = fnames
+                .iter()
+                .map(|fname| original_name(fname).to_owned())
+                .collect();
+            fnames.sort();
+            fnames.dedup();
+
+            for fname in fnames {
+                let path = dir.join(&fname);
+
+                // The listing has the size of the file on the remote, if it was known.
+                let listed_size = listing.as_ref().and_then(|listing| {
+                    let file = listing.files.iter().find(|file| file.name == fname)?;
+                    file.size
+                });
+
+                let problem = match find_stored(&path) {
+                    Some(stored) => match check_local(&stored, &path, marker.as_ref())? {
+                        Ok(actual) if listed_size.is_some_and(|size| actual < size) => {
+                            Some(FileProblem::Truncated {
+                                expected: listed_size.unwrap_or_default(),
+                                actual,
+                            })
+                        }
+                        Ok(_) => None,
+                        Err(problem) => Some(problem),
+                    },
+                    None => Some(FileProblem::Missing),
+                };
+
+                if let Some(problem) = problem {
+                    log::warn!("{:?} failed verification: {:?}", path, problem);
+                    bad_files.push(BadFile {
+                        sat,
+                        prod,
+                        valid_time,
+                        fname,
+                        path,
+                        problem,
+                    });
+                }
+            }
+        }
+
+        Ok(bad_files)
+    }
+
+    /// Verify the product from `start` through `end`, delete the bad files and the completion
+    /// markers of their directories, and download those directories again. Only the files that
+    /// pass the filter are verified and downloaded. Returns the files that failed verification.
+    pub fn repair(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        filter: &FileFilter,
+    ) -> Result<Vec<BadFile>, Box<dyn Error>> {
+        let bad_files = self.verify(sat, prod, start, end, filter)?;
+
+        let mut hours: Vec<NaiveDateTime> = vec![];
+        for bad in &bad_files {
+            for pth in stored_paths(&bad.path) {
+                if pth.exists() {
+                    remove_file(&pth)?;
+                    log::info!("Removed bad file {:?}", pth);
+                }
+
+                let sidecar = sidecar_path(&pth);
+                if sidecar.exists() {
+                    remove_file(sidecar)?;
+                }
+
+                #[cfg(feature = "sqlite")]
+                if let Some(index) = self.index() {
+                    index.remove(&pth)?;
+                }
+            }
+
+            if let Some(dir) = bad.path.parent() {
+                let marker = dir.join(HOUR_COMPLETE_FNAME);
+                if marker.exists() {
+                    remove_file(marker)?;
+                }
+            }
+
+            if !hours.contains(&bad.valid_time) {
+                hours.push(bad.valid_time);
+            }
+        }
+
+        if !hours.is_empty() {
+            self.retrieve(hours.into_iter().map(|hour| (sat, prod, hour)), filter)?;
+        }
+
+        Ok(bad_files)
+    }
+
+    fn check_file(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        valid_time: NaiveDateTime,
+        fname: &str,
+        path: &Path,
+        marker: Option<&CompletionMarker>,
+    ) -> Result<Option<FileProblem>, Box<dyn Error>> {
+        let stored = match find_stored(path) {
+            Some(stored) => stored,
+            None => return Ok(Some(FileProblem::Missing)),
+        };
+
+        #[cfg(feature = "sqlite")]
+        if let Some(index) = self.index() {
+            if let Some(checksum) = index.checksum(&stored)? {
+                if file_checksum(&stored)? != checksum {
+                    return Ok(Some(FileProblem::Corrupt("Checksum mismatch".to_owned())));
+                }
+            }
+        }
+
+        let actual = match check_local(&stored, path, marker)? {
+            Ok(actual) => actual,
+            Err(problem) => return Ok(Some(problem)),
+        };
+
+        let expected = match self
+            .remote()
+            .retrieve_remote_metadata(sat, prod, valid_time, fname)
+        {
+            Ok(meta) => Some(meta.size),
+            Err(err) => {
+                log::debug!("No remote metadata for {}: {}", fname, err);
+                None
+            }
+        };
+
+        match expected {
+            Some(expected) if actual < expected => {
+                Ok(Some(FileProblem::Truncated { expected, actual }))
+            }
+            None if actual == 0 => Ok(Some(FileProblem::Empty)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// The completion marker of a directory, if it has one.
+fn read_marker(dir: &Path) -> Result<Option<CompletionMarker>, Box<dyn Error>> {
+    let marker = dir.join(HOUR_COMPLETE_FNAME);
+    if !marker.exists() {
+        return Ok(None);
+    }
+
+    CompletionMarker::read(&marker).map(Some)
+}
+
+/// Check a stored file against its completion marker entry and read it back, returning its
+/// uncompressed size or what is wrong with it.
+fn check_local(
+    stored: &Path,
+    path: &Path,
+    marker: Option<&CompletionMarker>,
+) -> Result<Result<u64, FileProblem>, Box<dyn Error>> {
+    let marked = stored
+        .file_name()
+        .and_then(|name| marker?.file(&name.to_string_lossy()));
+
+    // Files in a bundle are checked from the bundle.
+    let bundled = match read_bundled(stored) {
+        Ok(bundled) => bundled,
+        Err(err) => return Ok(Err(FileProblem::Corrupt(err.to_string()))),
+    };
+
+    if let Some(marked) = marked {
+        let size = match bundled {
+            Some(ref data) => data.len() as u64,
+            None => stored.metadata()?.len(),
+        };
+        if size < marked.size {
+            return Ok(Err(FileProblem::Truncated {
+                expected: marked.size,
+                actual: size,
+            }));
+        }
+
+        if size != marked.size {
+            return Ok(Err(FileProblem::Corrupt("Size mismatch".to_owned())));
+        }
+
+        if let Some(expected) = &marked.checksum {
+            let checksum = match bundled {
+                Some(ref data) => data_checksum(data),
+                None => file_checksum(stored)?,
+            };
+            if &checksum != expected {
+                return Ok(Err(FileProblem::Corrupt("Checksum mismatch".to_owned())));
+            }
+        }
+    } else if bundled.is_none() && check_sidecar(stored)? == Some(false) {
+        return Ok(Err(FileProblem::Corrupt("Checksum mismatch".to_owned())));
+    }
+
+    // Decompressing to the end checks the zip CRC or zstd checksum.
+    if stored == path {
+        return Ok(Ok(stored.metadata()?.len()));
+    }
+
+    let read = open_archived_file(stored)
+        .and_then(|mut reader| Ok(io::copy(&mut reader, &mut io::sink())?));
+
+    Ok(read.map_err(|err| FileProblem::Corrupt(err.to_string())))
+}
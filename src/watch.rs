@@ -0,0 +1,76 @@
+use crate::{
+    archive::Archive, filename::scan_start, product::Product, remote::RemoteArchive,
+    satellite::Satellite,
+};
+use chrono::Duration;
+use std::{
+    collections::HashSet,
+    error::Error,
+    path::{Path, PathBuf},
+    thread,
+};
+
+/// How far back each pass of `watch` looks, enough to catch files that land late in the hour
+/// before the current one.
+const WATCH_WINDOW_HOURS: i64 = 2;
+
+impl<RA: 'static> Archive<RA>
+where
+    RA: RemoteArchive + Clone + Send,
+{
+    /// Keep the most recent hours of the product current, retrieving them every `poll_interval`
+    /// and calling `callback` with each file that lands in the archive, oldest scan first. Files
+    /// already in the archive when the watch starts aren't reported. Errors retrieving are
+    /// logged and the watch carries on. Returns once `callback` returns false.
+    pub fn watch<F>(
+        &self,
+        sat: Satellite,
+        prod: Product,
+        poll_interval: Duration,
+        mut callback: F,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(&Path) -> bool,
+    {
+        let window = || {
+            let now = chrono::Utc::now().naive_utc();
+            (now - Duration::hours(WATCH_WINDOW_HOURS), now)
+        };
+
+        let (start, end) = window();
+        Self::validate_dates(sat, prod, start, end)?;
+        let sleep = poll_interval.to_std()?;
+
+        let mut seen: HashSet<PathBuf> = self
+            .local_paths(sat, prod, start, end)?
+            .into_iter()
+            .collect();
+
+        loop {
+            let (start, end) = window();
+            match self.retrieve_paths(sat, prod, start, end) {
+                Ok(paths) => {
+                    let mut landed: Vec<&PathBuf> =
+                        paths.iter().filter(|pth| !seen.contains(*pth)).collect();
+                    landed.sort_by_key(|pth| {
+                        pth.file_name()
+                            .and_then(|fname| scan_start(&fname.to_string_lossy()))
+                    });
+                    log::debug!("Watch found {} new files", landed.len());
+
+                    for pth in landed {
+                        if !callback(pth) {
+                            return Ok(());
+                        }
+                    }
+
+                    // Anything that has left the window won't come back.
+                    seen = paths.into_iter().collect();
+                }
+                Err(err) => log::error!("Error watching {:?} {:?} : {}", sat, prod, err),
+            }
+
+            thread::sleep(sleep);
+        }
+    }
+}